@@ -0,0 +1,224 @@
+use crate::csp::{Limits, SearchStatus};
+use crate::matrix::{Objective, TravelMatrix};
+use crate::rng::Rng;
+use crate::roadgraph::haversine;
+use crate::tour::Solver;
+use crate::waypoint::{gazetteer, Category};
+use std::time::{Duration, Instant};
+
+/// The park counts in the default suite.
+pub const SIZES: [usize; 4] = [5, 10, 15, 20];
+
+/// Random points are spread over a square this many meters across, about
+/// the width of the lower 48 states.
+const RANDOM_SPAN: f64 = 4_000_000.0;
+
+/// A tour problem to benchmark: `roadtrip`-style edges over `len`
+/// vertices, with vertex 0 as home.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub name: String,
+    pub len: usize,
+    pub edges: Vec<(usize, usize, u32)>,
+}
+
+impl Instance {
+    /// The ten parks of the original experiment, with the driving distances
+    /// from the bundled matrix.
+    pub fn ten_parks() -> Self {
+        let matrix = TravelMatrix::builtin("ten-parks").unwrap();
+        let selection: Vec<usize> = (0..matrix.len()).collect();
+        Self {
+            name: "ten-parks".to_string(),
+            len: selection.len(),
+            edges: matrix.edges(&selection, Objective::Distance).unwrap(),
+        }
+    }
+
+    /// `n` national parks spread evenly through the gazetteer, with
+    /// straight-line distances between them.  Fails if the gazetteer has
+    /// fewer than `n` parks.
+    pub fn parks(n: usize) -> Result<Self, String> {
+        let parks: Vec<_> = gazetteer()
+            .into_iter()
+            .filter(|w| w.category == Category::Park)
+            .collect();
+        if n > parks.len() {
+            return Err(format!(
+                "only {} parks to choose from, not {}",
+                parks.len(),
+                n
+            ));
+        }
+        let points: Vec<_> = (0..n)
+            .map(|i| parks[i * parks.len() / n].coords())
+            .collect();
+        Ok(Self {
+            name: format!("parks-{}", n),
+            len: n,
+            edges: complete(&points, haversine),
+        })
+    }
+
+    /// `n` points scattered uniformly at random over a square, with
+    /// straight-line distances between them.  The same seed always gives
+    /// the same instance.
+    pub fn random(n: usize, seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let points: Vec<_> = (0..n)
+            .map(|_| (rng.unit() * RANDOM_SPAN, rng.unit() * RANDOM_SPAN))
+            .collect();
+        Self {
+            name: format!("random-{}-s{}", n, seed),
+            len: n,
+            edges: complete(&points, |a, b| (a.0 - b.0).hypot(a.1 - b.1)),
+        }
+    }
+}
+
+/// Edges between every pair of points, in whole meters.
+fn complete(
+    points: &[(f64, f64)],
+    meters: impl Fn((f64, f64), (f64, f64)) -> f64,
+) -> Vec<(usize, usize, u32)> {
+    let mut edges = Vec::new();
+    for (a, &from) in points.iter().enumerate() {
+        for (b, &to) in points.iter().enumerate() {
+            if a != b {
+                edges.push((a, b, meters(from, to).round() as u32));
+            }
+        }
+    }
+    edges
+}
+
+/// The benchmark suite: the ten parks of the original experiment, parks
+/// instances of each size, and `random` random instances of each size
+/// seeded from `seed` onwards.
+pub fn suite(sizes: &[usize], random: usize, seed: u64) -> Result<Vec<Instance>, String> {
+    let mut instances = vec![Instance::ten_parks()];
+    for &n in sizes {
+        instances.push(Instance::parks(n)?);
+    }
+    for &n in sizes {
+        instances.extend((0..random as u64).map(|i| Instance::random(n, seed + i)));
+    }
+    Ok(instances)
+}
+
+/// One solver's run on one instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    pub instance: String,
+    pub len: usize,
+    pub solver: Solver,
+    pub status: SearchStatus,
+    pub cost: Option<u32>,
+    pub nodes: usize,
+    pub elapsed: Duration,
+    /// The cost the gap is measured against: the optimum if some solver
+    /// proved one, otherwise the best cost any solver found.
+    pub reference: Option<u32>,
+    /// Whether `reference` is a proven optimum.
+    pub proven: bool,
+}
+
+impl Run {
+    /// How much costlier this run's tour is than the reference, in percent.
+    pub fn gap(&self) -> Option<f64> {
+        let (cost, best) = (self.cost?, self.reference?);
+        Some(100.0 * (cost as f64 - best as f64) / best.max(1) as f64)
+    }
+}
+
+/// Runs every solver on every instance, each under `limits`, and works out
+/// the gaps.
+pub fn run(instances: &[Instance], solvers: &[Solver], limits: &Limits) -> Vec<Run> {
+    let mut runs = Vec::new();
+    for instance in instances {
+        let mut these: Vec<Run> = solvers
+            .iter()
+            .map(|&solver| {
+                let start = Instant::now();
+                let outcome = solver.solve(instance.len, &instance.edges, limits);
+                Run {
+                    instance: instance.name.clone(),
+                    len: instance.len,
+                    solver,
+                    status: outcome.status,
+                    cost: outcome.tour.map(|t| t.cost),
+                    nodes: outcome.nodes,
+                    elapsed: start.elapsed(),
+                    reference: None,
+                    proven: false,
+                }
+            })
+            .collect();
+        let optimum = these
+            .iter()
+            .find(|r| r.status == SearchStatus::Optimal)
+            .and_then(|r| r.cost);
+        let best = these.iter().filter_map(|r| r.cost).min();
+        for r in these.iter_mut() {
+            r.reference = optimum.or(best);
+            r.proven = optimum.is_some();
+        }
+        runs.append(&mut these);
+    }
+    runs
+}
+
+const COLUMNS: [&str; 9] = [
+    "instance",
+    "size",
+    "solver",
+    "status",
+    "cost",
+    "gap_percent",
+    "reference",
+    "nodes",
+    "seconds",
+];
+
+/// The table cells for a run, in `COLUMNS` order.
+fn cells(run: &Run) -> Vec<String> {
+    let or_dash = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+    vec![
+        run.instance.clone(),
+        run.len.to_string(),
+        run.solver.to_string(),
+        run.status.to_string(),
+        or_dash(run.cost.map(|c| c.to_string())),
+        or_dash(run.gap().map(|g| format!("{:.2}", g))),
+        match run.reference {
+            Some(_) if run.proven => "optimal".to_string(),
+            Some(_) => "best found".to_string(),
+            None => "-".to_string(),
+        },
+        run.nodes.to_string(),
+        format!("{:.3}", run.elapsed.as_secs_f64()),
+    ]
+}
+
+/// Writes the runs as CSV with a header row.  No cell needs quoting.
+pub fn to_csv(runs: &[Run]) -> String {
+    let mut text = COLUMNS.join(",") + "\n";
+    for run in runs {
+        text.push_str(&cells(run).join(","));
+        text.push('\n');
+    }
+    text
+}
+
+/// Writes the runs as a Markdown table.
+pub fn to_markdown(runs: &[Run]) -> String {
+    let mut text = format!("| {} |\n", COLUMNS.join(" | "));
+    text.push_str(&format!("|{}\n", "---|".repeat(COLUMNS.len())));
+    for run in runs {
+        text.push_str(&format!("| {} |\n", cells(run).join(" | ")));
+    }
+    text
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,73 @@
+// tests
+
+use super::*;
+
+#[test]
+fn test_instances() {
+    let parks = Instance::parks(5).unwrap();
+    assert_eq!((parks.name.as_str(), parks.len), ("parks-5", 5));
+    assert_eq!(parks.edges.len(), 5 * 4);
+    assert!(Instance::parks(500).is_err());
+
+    // the same seed gives the same trip, another seed another trip
+    assert_eq!(Instance::random(6, 3), Instance::random(6, 3));
+    assert_ne!(Instance::random(6, 3).edges, Instance::random(6, 4).edges);
+
+    let names: Vec<_> = suite(&[4, 6], 2, 7)
+        .unwrap()
+        .into_iter()
+        .map(|i| i.name)
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "ten-parks",
+            "parks-4",
+            "parks-6",
+            "random-4-s7",
+            "random-4-s8",
+            "random-6-s7",
+            "random-6-s8"
+        ]
+    );
+}
+
+#[test]
+fn test_run_and_tables() {
+    let instances = [Instance::parks(6).unwrap(), Instance::random(6, 1)];
+    let runs = run(
+        &instances,
+        &[Solver::BranchAndBound, Solver::NearestNeighbor],
+        &Limits::default(),
+    );
+    assert_eq!(runs.len(), 4);
+    for r in runs.iter() {
+        // branch and bound proves the optimum, so no gap is negative
+        assert!(r.proven);
+        assert!(r.gap().unwrap() >= 0.0);
+    }
+    assert_eq!(runs[0].gap(), Some(0.0));
+    assert_eq!(runs[0].cost, runs[0].reference);
+
+    let csv = to_csv(&runs);
+    assert!(csv.starts_with(
+        "instance,size,solver,status,cost,gap_percent,reference,nodes,seconds\nparks-6,6,bnb,optimal,"
+    ));
+    assert_eq!(csv.lines().count(), 5);
+    let markdown = to_markdown(&runs);
+    assert!(markdown
+        .contains("\n|---|---|---|---|---|---|---|---|---|\n| parks-6 | 6 | bnb | optimal | "));
+    assert!(markdown.contains(" | nearest | feasible | "));
+
+    // with too few nodes nothing is found and there is nothing to compare to
+    let runs = run(
+        &instances[..1],
+        &[Solver::BranchAndBound],
+        &Limits {
+            max_nodes: Some(1),
+            time_limit: None,
+        },
+    );
+    assert_eq!((runs[0].cost, runs[0].gap()), (None, None));
+    assert!(to_csv(&runs).contains(",bnb,unknown,-,-,-,"));
+}
@@ -0,0 +1,1028 @@
+use crate::bench;
+use crate::cost::CostOverflow;
+use crate::csp::{Limits, SearchStatus};
+use crate::itinerary::{DateTime, Itinerary};
+use crate::matrix::{Objective, Overlay, TravelMatrix};
+use crate::opt::{TourOutcome, Tradeoff};
+use crate::picker::Picker;
+use crate::route::Route;
+use crate::server::{self, ServerConfig};
+use crate::spec::{Trip, TripSpec};
+use crate::tour::{cycle_cost, Solver};
+use crate::tsplib::{Problem, TourFile};
+use crate::waypoint::{gazetteer, lookup, Filter};
+use crate::{geojson, gpx, ics, json::Json, kml, report};
+use std::io::Write;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+/// Exit code when the tour was proven optimal (or a command succeeded).
+pub const EXIT_OPTIMAL: i32 = 0;
+/// Exit code for bad arguments, unreadable files or invalid matrices.
+pub const EXIT_ERROR: i32 = 1;
+/// Exit code when a tour was found but a limit stopped the proof of optimality.
+pub const EXIT_FEASIBLE: i32 = 2;
+/// Exit code when no tour was found.
+pub const EXIT_INFEASIBLE: i32 = 3;
+
+pub const USAGE: &str = "\
+usage: final-project <command> [options]
+
+commands:
+  solve     plan a round trip through the selected waypoints
+  plan      plan the trip described in a trip spec file
+  compare   run several solvers on the same trip and compare them
+  bench     time every solver on a fixed suite of trips
+  validate  check a matrix file for missing or suspicious legs
+  convert   write the selected trip as a TSPLIB problem
+  list      browse the gazetteer of parks and cities
+  pick      choose waypoints interactively and plan a trip through them
+  serve     run the planning service over HTTP on localhost
+
+matrix and waypoint options (solve, compare, validate, convert):
+  --matrix FILE         tab-separated file as written by trip.py, or a TSPLIB
+                        .tsp/.atsp file whose waypoints are its node numbers
+                        (default: the ten parks from the original main.rs)
+  --waypoint NAME       visit this waypoint; repeat for more.  The first is home
+  --waypoints A;B;C     visit these waypoints (default: all of them)
+  --objective distance|duration
+  --closures FILE       close legs or scale their costs for this trip only
+                        (solve, compare); tab-separated waypoint1, waypoint2,
+                        change (closed or a factor) and optionally one_way
+
+solver options (solve, compare):
+  --solver bnb|nearest|2opt|genetic      (solve; default bnb)
+  --solvers LIST        comma-separated  (compare; default all)
+  --time-limit SECONDS  stop searching after this long
+  --node-limit N        stop searching after this many search nodes
+
+output options (solve, plan):
+  --format text|json|geojson|gpx|kml|html|tour|itinerary|markdown|csv|ics
+                        (default text; tour is a TSPLIB tour numbering the
+                        waypoints as convert does; the last four lay the
+                        legs out in time from --start, ics as calendar
+                        events in each stop's time zone)
+  --output FILE         write there instead of to standard output
+  --title TEXT          name used in gpx, kml, html and ics output
+  --start DATETIME      when the trip leaves home, like 2024-06-01T08:00
+                        (needed by the itinerary formats)
+  --stay HOURS          time spent at each stop but home (default 0)
+
+plan options:
+  --spec FILE           the trip spec (JSON; see spec.rs for the format)
+  --pareto distance|duration|stops   list every trade-off between the
+                        objective and this instead of one best plan
+  --alternatives K      list the K best plans instead of one
+  --min-different M     each alternative has M legs none of the better
+                        ones drive (default 1, so no plan is the reverse
+                        of another)
+  --time-limit SECONDS  --node-limit N  --output FILE  --start  --title
+  --format text|json|itinerary|markdown|csv|ics   the itinerary formats
+                        follow the plan's days and need --start
+
+bench options:
+  --sizes LIST          comma-separated park counts (default 5,10,15,20)
+  --random N            random trips of each size to add (default 1)
+  --seed N              seed of the first random trip (default 1)
+  --solvers LIST        comma-separated (default all)
+  --time-limit SECONDS  --node-limit N   per run (default: a 10 second limit)
+  --format csv|markdown (default markdown)  --output FILE
+
+pick options:
+  --matrix FILE         plan with these driving distances instead of straight lines
+  --time-limit SECONDS  --node-limit N   (default: a 30 second limit)
+
+serve options:
+  --port N              listen on 127.0.0.1:N (default 8080)
+  --workers N           jobs solved at once (default 2)
+  --queue N             jobs that may wait for a worker (default 16)
+  --keep N              finished jobs kept for fetching (default 100)
+  --time-limit SECONDS  --node-limit N   per job (default: a 60 second limit)
+
+validate options:
+  --tour FILE           also work out the cost of a TSPLIB tour, such as a
+                        published .opt.tour, through the whole matrix
+
+list options:
+  --category park|city  --state NAME  --search TEXT
+
+exit status: 0 optimal, 1 error, 2 feasible but not proven optimal, 3 no tour found
+";
+
+/// Runs the command line with the given arguments (not including the
+/// program name), writing results to `out`.  Returns the exit code.
+pub fn run(args: &[String], out: &mut dyn Write) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("solve") => parse(&args[1..], SOLVE_OPTIONS).and_then(|o| solve(&o, out)),
+        Some("plan") => parse(&args[1..], PLAN_OPTIONS).and_then(|o| plan(&o, out)),
+        Some("compare") => parse(&args[1..], COMPARE_OPTIONS).and_then(|o| compare(&o, out)),
+        Some("bench") => parse(&args[1..], BENCH_OPTIONS).and_then(|o| run_bench(&o, out)),
+        Some("validate") => parse(&args[1..], VALIDATE_OPTIONS).and_then(|o| validate(&o, out)),
+        Some("convert") => parse(&args[1..], CONVERT_OPTIONS).and_then(|o| convert(&o, out)),
+        Some("list") => parse(&args[1..], LIST_OPTIONS).and_then(|o| list(&o, out)),
+        Some("serve") => parse(&args[1..], SERVE_OPTIONS).and_then(|o| serve(&o, out)),
+        Some("pick") => parse(&args[1..], PICK_OPTIONS).and_then(|o| pick(&o, out)),
+        Some("help") | Some("--help") | Some("-h") => write!(out, "{}", USAGE)
+            .map(|_| EXIT_OPTIMAL)
+            .map_err(|e| e.to_string()),
+        Some(other) => Err(format!("unknown command {:?}\n\n{}", other, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{}", message.trim_end());
+            EXIT_ERROR
+        }
+    }
+}
+
+/// Which options each command accepts, and whether each takes a value.
+type OptionSpec = &'static [(&'static str, bool)];
+
+const SOLVE_OPTIONS: OptionSpec = &[
+    ("matrix", true),
+    ("closures", true),
+    ("waypoint", true),
+    ("waypoints", true),
+    ("objective", true),
+    ("solver", true),
+    ("time-limit", true),
+    ("node-limit", true),
+    ("format", true),
+    ("output", true),
+    ("title", true),
+    ("start", true),
+    ("stay", true),
+];
+const PLAN_OPTIONS: OptionSpec = &[
+    ("spec", true),
+    ("pareto", true),
+    ("alternatives", true),
+    ("min-different", true),
+    ("time-limit", true),
+    ("node-limit", true),
+    ("format", true),
+    ("output", true),
+    ("start", true),
+    ("title", true),
+];
+const COMPARE_OPTIONS: OptionSpec = &[
+    ("matrix", true),
+    ("closures", true),
+    ("waypoint", true),
+    ("waypoints", true),
+    ("objective", true),
+    ("solvers", true),
+    ("time-limit", true),
+    ("node-limit", true),
+];
+const BENCH_OPTIONS: OptionSpec = &[
+    ("sizes", true),
+    ("random", true),
+    ("seed", true),
+    ("solvers", true),
+    ("time-limit", true),
+    ("node-limit", true),
+    ("format", true),
+    ("output", true),
+];
+const VALIDATE_OPTIONS: OptionSpec = &[("matrix", true), ("tour", true)];
+const CONVERT_OPTIONS: OptionSpec = &[
+    ("matrix", true),
+    ("waypoint", true),
+    ("waypoints", true),
+    ("objective", true),
+    ("title", true),
+    ("output", true),
+];
+const PICK_OPTIONS: OptionSpec = &[("matrix", true), ("time-limit", true), ("node-limit", true)];
+const SERVE_OPTIONS: OptionSpec = &[
+    ("port", true),
+    ("workers", true),
+    ("queue", true),
+    ("keep", true),
+    ("time-limit", true),
+    ("node-limit", true),
+];
+const LIST_OPTIONS: OptionSpec = &[("category", true), ("state", true), ("search", true)];
+
+/// Parsed `--name value` options, in the order given.
+struct Options(Vec<(String, String)>);
+
+impl Options {
+    /// Returns the last value given for an option.
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+    /// Returns every value given for an option.
+    fn all(&self, name: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+    /// Parses an option's value, if given.
+    fn parse<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, String>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.get(name)
+            .map(|v| v.parse().map_err(|e| format!("--{}: {}", name, e)))
+            .transpose()
+    }
+}
+
+fn parse(args: &[String], spec: OptionSpec) -> Result<Options, String> {
+    let mut options = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = arg
+            .strip_prefix("--")
+            .ok_or_else(|| format!("unexpected argument {:?}", arg))?;
+        match spec.iter().find(|(n, _)| *n == name) {
+            Some((_, true)) => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("--{} needs a value", name))?;
+                options.push((name.to_string(), value.clone()));
+            }
+            Some((_, false)) => options.push((name.to_string(), String::new())),
+            None => return Err(format!("unknown option --{}", name)),
+        }
+    }
+    Ok(Options(options))
+}
+
+/// A trip to solve: the matrix, which of its waypoints to visit (home
+/// first), and the edges `roadtrip` should minimize over.
+struct Instance {
+    matrix: TravelMatrix,
+    selection: Vec<usize>,
+    objective: Objective,
+    edges: Vec<(usize, usize, u32)>,
+}
+
+fn load_matrix(options: &Options) -> Result<TravelMatrix, String> {
+    match options.get("matrix") {
+        Some(path) if path.ends_with(".tsp") || path.ends_with(".atsp") => Problem::read(path)
+            .map(|p| p.to_matrix())
+            .map_err(|e| format!("{}: {}", path, e)),
+        Some(path) => TravelMatrix::read(path).map_err(|e| format!("{}: {}", path, e)),
+        None => Ok(TravelMatrix::builtin("ten-parks").unwrap()),
+    }
+}
+
+fn load_instance(options: &Options) -> Result<Instance, String> {
+    let mut matrix = load_matrix(options)?;
+    if let Some(path) = options.get("closures") {
+        matrix = Overlay::read(path)
+            .and_then(|overlay| matrix.with_overlay(&overlay))
+            .map_err(|e| format!("{}: {}", path, e))?;
+    }
+    let mut names: Vec<&str> = options.all("waypoint");
+    for list in options.all("waypoints") {
+        names.extend(list.split(';').map(str::trim).filter(|n| !n.is_empty()));
+    }
+    let selection = if names.is_empty() {
+        (0..matrix.len()).collect()
+    } else {
+        matrix.select(&names).map_err(|e| e.to_string())?
+    };
+    if selection.len() < 2 {
+        return Err("a trip needs at least two waypoints".to_string());
+    }
+    if let Some(i) = (1..selection.len()).find(|&i| selection[..i].contains(&selection[i])) {
+        return Err(format!(
+            "{} is selected more than once",
+            matrix.names()[selection[i]]
+        ));
+    }
+    let objective = options.parse("objective")?.unwrap_or(Objective::Distance);
+    match objective {
+        Objective::Cost => {
+            return Err(
+                "--objective cost needs a trip spec with a costs section; use plan".to_string(),
+            )
+        }
+        Objective::Elapsed => {
+            return Err("--objective elapsed needs a trip spec with stays; use plan".to_string())
+        }
+        _ => {}
+    }
+    let edges = matrix
+        .edges(&selection, objective)
+        .map_err(|e| e.to_string())?;
+    Ok(Instance {
+        matrix,
+        selection,
+        objective,
+        edges,
+    })
+}
+
+fn limits(options: &Options) -> Result<Limits, String> {
+    let seconds: Option<f64> = options.parse("time-limit")?;
+    if seconds.is_some_and(|s| !(s >= 0.0 && s.is_finite())) {
+        return Err("--time-limit must be a non-negative number of seconds".to_string());
+    }
+    Ok(Limits {
+        max_nodes: options.parse("node-limit")?,
+        time_limit: seconds.map(Duration::from_secs_f64),
+    })
+}
+
+/// The solvers named by `--solvers`, or all of them.
+fn solvers(options: &Options) -> Result<Vec<Solver>, String> {
+    match options.get("solvers") {
+        Some(list) => list.split(',').map(|s| s.trim().parse()).collect(),
+        None => Ok(Solver::ALL.to_vec()),
+    }
+}
+
+fn exit_code(status: SearchStatus) -> i32 {
+    match status {
+        SearchStatus::Optimal => EXIT_OPTIMAL,
+        SearchStatus::Feasible => EXIT_FEASIBLE,
+        SearchStatus::Infeasible | SearchStatus::Unknown => EXIT_INFEASIBLE,
+    }
+}
+
+fn unit(objective: Objective) -> &'static str {
+    match objective {
+        Objective::Distance => "meters",
+        Objective::Duration | Objective::Elapsed => "seconds",
+        Objective::Cost => "cents",
+    }
+}
+
+/// Formats cents as dollars, like `$12.05`.
+fn dollars(cents: u32) -> String {
+    format!("${}.{:02}", cents / 100, cents % 100)
+}
+
+/// Builds a route for the map and GPS formats, which need coordinates
+/// from the gazetteer for every stop.
+fn route(instance: &Instance, order: &[usize]) -> Result<Route, String> {
+    let names = instance.matrix.names();
+    let stops = instance
+        .selection
+        .iter()
+        .map(|&i| lookup(&names[i]).ok_or_else(|| format!("no coordinates known for {}", names[i])))
+        .collect::<Result<Vec<_>, _>>()?;
+    let distances = instance
+        .matrix
+        .edges(&instance.selection, Objective::Distance)
+        .map_err(|e| e.to_string())?;
+    let mut route = Route::new(stops, order.to_vec(), &distances);
+    if let Ok(durations) = instance
+        .matrix
+        .edges(&instance.selection, Objective::Duration)
+    {
+        route.add_durations(&durations);
+    }
+    Ok(route)
+}
+
+/// Lays the tour out in time from `--start`, staying `--stay` hours at
+/// each stop but home.
+fn itinerary(
+    options: &Options,
+    instance: &Instance,
+    order: &[usize],
+) -> Result<(Route, Itinerary), String> {
+    let start = start_time(options)?;
+    let hours: f64 = options.parse("stay")?.unwrap_or(0.0);
+    if !(hours >= 0.0 && hours.is_finite()) {
+        return Err("--stay must be a non-negative number of hours".to_string());
+    }
+    let mut stays = vec![(hours * 3600.0).round() as u32; instance.selection.len()];
+    stays[0] = 0;
+    let route = route(instance, order)?;
+    let itinerary = Itinerary::new(&route, start, &stays);
+    Ok((route, itinerary))
+}
+
+/// The `--start` time the itinerary formats need.
+fn start_time(options: &Options) -> Result<DateTime, String> {
+    options
+        .parse("start")?
+        .ok_or_else(|| "itinerary formats need a start time; give one with --start".to_string())
+}
+
+/// Writes a route's itinerary in one of the itinerary formats.
+fn render_itinerary(
+    format: &str,
+    route: &Route,
+    itinerary: &Itinerary,
+    title: &str,
+) -> Result<String, String> {
+    Ok(match format {
+        "itinerary" => itinerary.to_text(),
+        "markdown" => itinerary.to_markdown(),
+        "csv" => itinerary.to_csv(),
+        _ => ics::to_ics(route, itinerary, title)?,
+    })
+}
+
+fn solve(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let instance = load_instance(options)?;
+    let limits = limits(options)?;
+    let solver = options.parse("solver")?.unwrap_or(Solver::BranchAndBound);
+    let format = options.get("format").unwrap_or("text");
+    let title = options.get("title").unwrap_or("Road trip");
+
+    let start = Instant::now();
+    let outcome = solver.solve(instance.selection.len(), &instance.edges, &limits);
+    let elapsed = start.elapsed();
+    let names = instance.matrix.names();
+    let name = |v: usize| names[instance.selection[v]].as_str();
+
+    let text = match (format, &outcome.tour) {
+        ("text", tour) => {
+            let mut text = String::new();
+            match tour {
+                Some(tour) => {
+                    text.push_str("Visit the locations in this order:\n");
+                    for (i, &v) in tour.order.iter().enumerate() {
+                        text.push_str(&format!("{:>4}. {}\n", i + 1, name(v)));
+                    }
+                    text.push_str(&format!(
+                        "Total {} is {} {}\n",
+                        instance.objective,
+                        tour.cost,
+                        unit(instance.objective)
+                    ));
+                }
+                None => text.push_str("No tour found.\n"),
+            }
+            text.push_str(&format!(
+                "Status: {} ({} solver, {} nodes, {:?})\n",
+                outcome.status, solver, outcome.nodes, elapsed
+            ));
+            text
+        }
+        ("json", tour) => {
+            let mut fields = vec![
+                ("status", Json::str(&outcome.status.to_string())),
+                ("solver", Json::str(&solver.to_string())),
+                ("objective", Json::str(&instance.objective.to_string())),
+                ("nodes", outcome.nodes.into()),
+                ("seconds", elapsed.as_secs_f64().into()),
+            ];
+            if let Some(tour) = tour {
+                fields.push((
+                    "order",
+                    Json::Array(tour.order.iter().map(|&v| Json::str(name(v))).collect()),
+                ));
+                fields.push(("cost", tour.cost.into()));
+            }
+            Json::object(fields).pretty() + "\n"
+        }
+        ("geojson", Some(tour)) => geojson::to_geojson(&route(&instance, &tour.order)?) + "\n",
+        ("gpx", Some(tour)) => gpx::to_gpx(&route(&instance, &tour.order)?, title),
+        ("kml", Some(tour)) => kml::to_kml(&route(&instance, &tour.order)?, title),
+        ("html", Some(tour)) => report::to_html(&route(&instance, &tour.order)?, title),
+        ("tour", Some(tour)) => TourFile::from_order(title, &tour.order).to_tsplib(),
+        ("itinerary", Some(tour))
+        | ("markdown", Some(tour))
+        | ("csv", Some(tour))
+        | ("ics", Some(tour)) => {
+            let (route, itinerary) = itinerary(options, &instance, &tour.order)?;
+            render_itinerary(format, &route, &itinerary, title)?
+        }
+        ("geojson", None)
+        | ("gpx", None)
+        | ("kml", None)
+        | ("html", None)
+        | ("tour", None)
+        | ("itinerary", None)
+        | ("markdown", None)
+        | ("csv", None)
+        | ("ics", None) => {
+            eprintln!("No tour found ({}).", outcome.status);
+            return Ok(exit_code(outcome.status));
+        }
+        (other, _) => return Err(format!("unknown format {:?}", other)),
+    };
+    emit(options, out, &text)?;
+    Ok(exit_code(outcome.status))
+}
+
+/// Writes output to `--output` if given, otherwise to `out`.
+fn emit(options: &Options, out: &mut dyn Write, text: &str) -> Result<(), String> {
+    match options.get("output") {
+        Some(path) => std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e)),
+        None => out.write_all(text.as_bytes()).map_err(|e| e.to_string()),
+    }
+}
+
+fn plan(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let path = options
+        .get("spec")
+        .ok_or("plan needs a trip spec; give one with --spec FILE")?;
+    let spec = TripSpec::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let trip = spec.resolve().map_err(|e| format!("{}: {}", path, e))?;
+    let limits = limits(options)?;
+    if let Some(against) = options.get("pareto") {
+        return pareto(options, out, &spec, &trip, against, &limits);
+    }
+    if let Some(k) = options.parse("alternatives")? {
+        return alternatives(options, out, &spec, &trip, k, &limits);
+    }
+
+    let start = Instant::now();
+    let outcome = trip
+        .roadtrip
+        .solve_checked(&limits, &mut ())
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let elapsed = start.elapsed();
+    let name = |v: &usize| trip.names[*v].as_str();
+
+    let text = match options.get("format").unwrap_or("text") {
+        "text" => {
+            let mut text = String::new();
+            if let Some(title) = &spec.name {
+                text.push_str(&format!("{}\n", title));
+            }
+            match &outcome.plan {
+                Some(plan) => {
+                    text.push_str("Visit the locations in this order:\n");
+                    for (i, v) in plan.order.iter().enumerate() {
+                        text.push_str(&format!("{:>4}. {}", i + 1, name(v)));
+                        if let Some(arrivals) = &plan.arrivals {
+                            let a = arrivals[i];
+                            text.push_str(&format!(
+                                " (day {}, {} in)",
+                                a.day + 1,
+                                report::format_duration(a.time as u64)
+                            ));
+                        }
+                        text.push('\n');
+                        for charge in plan.charges.iter().filter(|c| c.leg == i) {
+                            text.push_str(&format!(
+                                "      charge at {} for {}\n",
+                                name(&charge.vertex),
+                                report::format_duration(charge.seconds as u64)
+                            ));
+                        }
+                    }
+                    if !plan.skipped.is_empty() {
+                        let skipped: Vec<_> = plan.skipped.iter().map(name).collect();
+                        text.push_str(&format!("Skipped: {}\n", skipped.join("; ")));
+                    }
+                    text.push_str(&format!(
+                        "Total {} is {} {}\n",
+                        trip.objective,
+                        plan.cost,
+                        unit(trip.objective)
+                    ));
+                    if let Some(b) = &plan.breakdown {
+                        text.push_str(&format!(
+                            "Fuel {}, tolls {}, lodging {} for {} nights\n",
+                            dollars(b.fuel),
+                            dollars(b.tolls),
+                            dollars(b.lodging),
+                            b.nights
+                        ));
+                    }
+                }
+                None => text.push_str("No trip follows every rule.\n"),
+            }
+            text.push_str(&format!(
+                "Status: {} ({} nodes, {:?})\n",
+                outcome.status, outcome.nodes, elapsed
+            ));
+            text
+        }
+        "json" => {
+            let mut fields = Vec::new();
+            if let Some(title) = &spec.name {
+                fields.push(("name", Json::str(title)));
+            }
+            fields.extend(trip.outcome_fields(&outcome));
+            fields.push(("seconds", elapsed.as_secs_f64().into()));
+            Json::object(fields).pretty() + "\n"
+        }
+        format @ ("itinerary" | "markdown" | "csv" | "ics") => {
+            let start = start_time(options)?;
+            let plan = match &outcome.plan {
+                Some(plan) => plan,
+                None => {
+                    eprintln!("No trip follows every rule ({}).", outcome.status);
+                    return Ok(exit_code(outcome.status));
+                }
+            };
+            let route = trip
+                .route(&plan.order)
+                .ok_or("some waypoints have no known coordinates")?;
+            // the plan's own arrivals know about nights and waiting
+            let itinerary = match &plan.arrivals {
+                Some(arrivals) => {
+                    let times: Vec<_> = arrivals.iter().map(|a| a.time).collect();
+                    Itinerary::scheduled(&route, start, &times)
+                }
+                None => Itinerary::new(&route, start, &[]),
+            };
+            let title = options
+                .get("title")
+                .or(spec.name.as_deref())
+                .unwrap_or("Road trip");
+            render_itinerary(format, &route, &itinerary, title)?
+        }
+        other => {
+            return Err(format!(
+                "unknown format {:?} (expected text, json, itinerary, markdown, csv or ics)",
+                other
+            ))
+        }
+    };
+    emit(options, out, &text)?;
+    Ok(exit_code(outcome.status))
+}
+
+/// `plan --alternatives`: the `k` best plans that differ enough.
+fn alternatives(
+    options: &Options,
+    out: &mut dyn Write,
+    spec: &TripSpec,
+    trip: &Trip,
+    k: usize,
+    limits: &Limits,
+) -> Result<i32, String> {
+    let min_different = options.parse("min-different")?.unwrap_or(1);
+    let start = Instant::now();
+    let alternatives = trip
+        .roadtrip
+        .alternatives(k, min_different, limits)
+        .map_err(|e| e.to_string())?;
+    let elapsed = start.elapsed();
+    let name = |v: &usize| trip.names[*v].as_str();
+
+    let text = match options.get("format").unwrap_or("text") {
+        "text" => {
+            let mut text = String::new();
+            if let Some(title) = &spec.name {
+                text.push_str(&format!("{}\n", title));
+            }
+            if alternatives.plans.is_empty() {
+                text.push_str("No trip follows every rule.\n");
+            }
+            for (i, plan) in alternatives.plans.iter().enumerate() {
+                let order: Vec<_> = plan.order.iter().map(name).collect();
+                text.push_str(&format!(
+                    "{:>4}. {} {}: {}\n",
+                    i + 1,
+                    plan.cost,
+                    unit(trip.objective),
+                    order.join(" -> ")
+                ));
+                if !plan.skipped.is_empty() {
+                    let skipped: Vec<_> = plan.skipped.iter().map(name).collect();
+                    text.push_str(&format!("      skipping {}\n", skipped.join("; ")));
+                }
+            }
+            text.push_str(&format!(
+                "Status: {} ({} nodes, {:?})\n",
+                alternatives.status, alternatives.nodes, elapsed
+            ));
+            text
+        }
+        "json" => {
+            let mut fields = Vec::new();
+            if let Some(title) = &spec.name {
+                fields.push(("name", Json::str(title)));
+            }
+            fields.extend(trip.alternatives_fields(&alternatives));
+            fields.push(("seconds", elapsed.as_secs_f64().into()));
+            Json::object(fields).pretty() + "\n"
+        }
+        other => {
+            return Err(format!(
+                "unknown format {:?} (expected text or json)",
+                other
+            ))
+        }
+    };
+    emit(options, out, &text)?;
+    Ok(exit_code(alternatives.status))
+}
+
+/// `plan --pareto`: every trade-off between the objective and `against`.
+fn pareto(
+    options: &Options,
+    out: &mut dyn Write,
+    spec: &TripSpec,
+    trip: &Trip,
+    against: &str,
+    limits: &Limits,
+) -> Result<i32, String> {
+    let tradeoff = trip
+        .tradeoff(against)
+        .map_err(|e| format!("--pareto: {}", e))?;
+    let start = Instant::now();
+    let front = trip
+        .roadtrip
+        .pareto(&tradeoff, limits)
+        .map_err(|e| e.to_string())?;
+    let elapsed = start.elapsed();
+    let name = |v: &usize| trip.names[*v].as_str();
+
+    let text = match options.get("format").unwrap_or("text") {
+        "text" => {
+            let mut text = String::new();
+            if let Some(title) = &spec.name {
+                text.push_str(&format!("{}\n", title));
+            }
+            if front.plans.is_empty() {
+                text.push_str("No trip follows every rule.\n");
+            } else {
+                text.push_str(&format!(
+                    "Trade-offs between {} and {}:\n",
+                    trip.objective, against
+                ));
+            }
+            for (i, p) in front.plans.iter().enumerate() {
+                let traded = match tradeoff {
+                    Tradeoff::Stops => format!("{} skipped", p.against),
+                    Tradeoff::Legs(_) if against == "distance" => format!("{} meters", p.against),
+                    Tradeoff::Legs(_) => format!("{} seconds", p.against),
+                };
+                let order: Vec<_> = p.plan.order.iter().map(name).collect();
+                text.push_str(&format!(
+                    "{:>4}. {} {}, {}: {}\n",
+                    i + 1,
+                    p.cost,
+                    unit(trip.objective),
+                    traded,
+                    order.join(" -> ")
+                ));
+            }
+            text.push_str(&format!(
+                "Status: {} ({} nodes, {:?})\n",
+                front.status, front.nodes, elapsed
+            ));
+            text
+        }
+        "json" => {
+            let mut fields = Vec::new();
+            if let Some(title) = &spec.name {
+                fields.push(("name", Json::str(title)));
+            }
+            fields.extend(trip.front_fields(&front, against));
+            fields.push(("seconds", elapsed.as_secs_f64().into()));
+            Json::object(fields).pretty() + "\n"
+        }
+        other => {
+            return Err(format!(
+                "unknown format {:?} (expected text or json)",
+                other
+            ))
+        }
+    };
+    emit(options, out, &text)?;
+    Ok(exit_code(front.status))
+}
+
+fn compare(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let instance = load_instance(options)?;
+    let limits = limits(options)?;
+    let solvers = solvers(options)?;
+
+    let mut results: Vec<(Solver, TourOutcome, Duration)> = Vec::new();
+    for solver in solvers {
+        let start = Instant::now();
+        let outcome = solver.solve(instance.selection.len(), &instance.edges, &limits);
+        results.push((solver, outcome, start.elapsed()));
+    }
+    let best = results
+        .iter()
+        .filter_map(|(_, o, _)| o.tour.as_ref().map(|t| t.cost))
+        .min();
+
+    let mut text = format!(
+        "{} waypoints, minimizing {}\n{:<10} {:<11} {:>14} {:>9} {:>10} {:>12}\n",
+        instance.selection.len(),
+        instance.objective,
+        "solver",
+        "status",
+        "cost",
+        "gap",
+        "nodes",
+        "time"
+    );
+    for (solver, outcome, elapsed) in results.iter() {
+        let (cost, gap) = match (&outcome.tour, best) {
+            (Some(t), Some(b)) => (
+                t.cost.to_string(),
+                format!("{:.2}%", 100.0 * (t.cost - b) as f64 / b.max(1) as f64),
+            ),
+            _ => ("-".to_string(), "-".to_string()),
+        };
+        text.push_str(&format!(
+            "{:<10} {:<11} {:>14} {:>9} {:>10} {:>12}\n",
+            solver.to_string(),
+            outcome.status,
+            cost,
+            gap,
+            outcome.nodes,
+            format!("{:.3?}", elapsed)
+        ));
+    }
+    out.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+
+    let statuses: Vec<_> = results.iter().map(|(_, o, _)| o.status).collect();
+    Ok(if statuses.contains(&SearchStatus::Optimal) {
+        EXIT_OPTIMAL
+    } else if statuses.contains(&SearchStatus::Feasible) {
+        EXIT_FEASIBLE
+    } else {
+        EXIT_INFEASIBLE
+    })
+}
+
+fn run_bench(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let sizes = match options.get("sizes") {
+        Some(list) => list
+            .split(',')
+            .map(|s| match s.trim().parse::<usize>() {
+                Ok(n) if n >= 2 => Ok(n),
+                _ => Err(format!("--sizes: {:?} is not a size of 2 or more", s)),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => bench::SIZES.to_vec(),
+    };
+    let mut limits = limits(options)?;
+    if limits == Limits::default() {
+        limits.time_limit = Some(Duration::from_secs(10));
+    }
+    let instances = bench::suite(
+        &sizes,
+        options.parse("random")?.unwrap_or(1),
+        options.parse("seed")?.unwrap_or(1),
+    )
+    .map_err(|e| format!("--sizes: {}", e))?;
+    let runs = bench::run(&instances, &solvers(options)?, &limits);
+    let text = match options.get("format").unwrap_or("markdown") {
+        "csv" => bench::to_csv(&runs),
+        "markdown" | "md" => bench::to_markdown(&runs),
+        other => {
+            return Err(format!(
+                "unknown format {:?} (expected csv or markdown)",
+                other
+            ))
+        }
+    };
+    emit(options, out, &text)?;
+    Ok(EXIT_OPTIMAL)
+}
+
+fn validate(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let matrix = load_matrix(options)?;
+    let mut objectives = vec![Objective::Distance];
+    if matrix.has_durations() {
+        objectives.push(Objective::Duration);
+    }
+    let mut issues = Vec::new();
+    for &objective in objectives.iter() {
+        issues.extend(
+            matrix
+                .diagnose(objective)
+                .into_iter()
+                .map(|d| (objective, d)),
+        );
+    }
+    let errors = issues.iter().filter(|(_, d)| d.is_error()).count();
+    let mut text = String::new();
+    for (objective, issue) in issues.iter() {
+        text.push_str(&format!("{}: {}\n", objective, issue));
+    }
+    text.push_str(&format!(
+        "{} waypoints, {} errors, {} warnings{}\n",
+        matrix.len(),
+        errors,
+        issues.len() - errors,
+        if matrix.has_durations() {
+            ""
+        } else {
+            " (no driving times)"
+        }
+    ));
+    if let Some(path) = options.get("tour") {
+        let tour = TourFile::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        let order = tour
+            .roadtrip_order()
+            .filter(|order| order.len() == matrix.len() + 1)
+            .ok_or_else(|| format!("{}: the tour doesn't visit every waypoint", path))?;
+        let all: Vec<usize> = (0..matrix.len()).collect();
+        let costs = matrix.dense(&all, Objective::Distance);
+        let order = &order[..matrix.len()];
+        let cost = cycle_cost(&costs, order).ok_or_else(|| {
+            let closed = order.iter().zip(order.iter().cycle().skip(1));
+            if closed.clone().all(|(&a, &b)| costs.get(a, b).is_some()) {
+                format!("{}: {}", path, CostOverflow)
+            } else {
+                format!("{}: the tour uses a missing leg", path)
+            }
+        })?;
+        text.push_str(&format!("Tour {} has distance {}\n", tour.name, cost));
+    }
+    out.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(if errors == 0 {
+        EXIT_OPTIMAL
+    } else {
+        EXIT_ERROR
+    })
+}
+
+fn convert(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let instance = load_instance(options)?;
+    let mut problem = Problem::from_edges(
+        options.get("title").unwrap_or("roadtrip"),
+        instance.selection.len(),
+        &instance.edges,
+    )
+    .map_err(|e| e.to_string())?;
+    let names: Vec<_> = instance
+        .selection
+        .iter()
+        .map(|&i| instance.matrix.names()[i].as_str())
+        .collect();
+    problem.comment = Some(format!(
+        "{} in {}: {}",
+        instance.objective,
+        unit(instance.objective),
+        names.join("; ")
+    ));
+    emit(options, out, &problem.to_tsplib())?;
+    Ok(EXIT_OPTIMAL)
+}
+
+fn list(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let filter = Filter {
+        search: options.get("search").map(str::to_string),
+        state: options.get("state").map(str::to_string),
+        category: options.parse("category")?,
+    };
+    let mut text = String::new();
+    for w in gazetteer().iter().filter(|w| filter.matches(w)) {
+        text.push_str(&format!(
+            "{:<45} {:<5} {:>8.3} {:>9.3}\n",
+            w.label(),
+            w.category.to_string(),
+            w.lat,
+            w.lon
+        ));
+    }
+    out.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(EXIT_OPTIMAL)
+}
+
+fn pick(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let matrix = match options.get("matrix") {
+        Some(_) => Some(load_matrix(options)?),
+        None => None,
+    };
+    let mut limits = limits(options)?;
+    if limits == Limits::default() {
+        limits.time_limit = Some(Duration::from_secs(30));
+    }
+    let stdin = std::io::stdin();
+    Picker::new(matrix, limits)
+        .run(&mut stdin.lock(), out)
+        .map_err(|e| e.to_string())?;
+    Ok(EXIT_OPTIMAL)
+}
+
+fn serve(options: &Options, out: &mut dyn Write) -> Result<i32, String> {
+    let defaults = ServerConfig::default();
+    let mut limits = limits(options)?;
+    if limits == Limits::default() {
+        limits = defaults.limits;
+    }
+    let config = ServerConfig {
+        workers: options.parse("workers")?.unwrap_or(defaults.workers),
+        queue: options.parse("queue")?.unwrap_or(defaults.queue),
+        limits,
+        keep: options.parse("keep")?.unwrap_or(defaults.keep),
+    };
+    let port: u16 = options.parse("port")?.unwrap_or(8080);
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| e.to_string())?;
+    writeln!(
+        out,
+        "planning service listening on http://{}",
+        listener.local_addr().map_err(|e| e.to_string())?
+    )
+    .map_err(|e| e.to_string())?;
+    out.flush().map_err(|e| e.to_string())?;
+    server::serve(listener, &config).map_err(|e| e.to_string())?;
+    Ok(EXIT_OPTIMAL)
+}
+
+#[cfg(test)]
+mod pub_tests;
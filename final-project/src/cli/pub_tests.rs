@@ -0,0 +1,332 @@
+// tests
+
+use super::*;
+
+fn run_args(args: &[&str]) -> (i32, String) {
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let mut out = Vec::new();
+    let code = run(&args, &mut out);
+    (code, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn test_solve_small_trip() {
+    let (code, out) = run_args(&[
+        "solve",
+        "--waypoints",
+        "Joshua Tree;Big Bend;Mount Rainier;Badlands",
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.starts_with("Visit the locations in this order:\n   1. Joshua Tree, California\n"));
+    assert!(out.contains("   5. Joshua Tree, California\n"));
+    assert!(out.contains("Status: optimal (bnb solver"));
+
+    let (code, out) = run_args(&[
+        "solve",
+        "--waypoint",
+        "Acadia",
+        "--waypoint",
+        "Mammoth Cave",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.contains("\"status\": \"optimal\""));
+    assert!(out.contains("\"order\": ["));
+}
+
+#[test]
+fn test_solve_closures() {
+    let path =
+        std::env::temp_dir().join(format!("final-project-closures-{}.tsv", std::process::id()));
+    let solve = |closures: &str| {
+        std::fs::write(
+            &path,
+            format!("waypoint1\twaypoint2\tchange\tone_way\n{}", closures),
+        )
+        .unwrap();
+        run_args(&[
+            "solve",
+            "--waypoints",
+            "Joshua Tree;Big Bend;Badlands",
+            "--closures",
+            path.to_str().unwrap(),
+        ])
+    };
+    // with the drive straight to Big Bend closed, the trip goes the other way round
+    let (code, out) = solve("Joshua Tree\tBig Bend\tclosed\tyes\n");
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.contains("   2. Badlands, South Dakota\n   3. Big Bend, Texas\n"));
+    let (code, out) = solve("Joshua Tree\tBadlands\tclosed\tyes\n");
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.contains("   2. Big Bend, Texas\n   3. Badlands, South Dakota\n"));
+    // both ways closed leaves no round trip through three stops
+    let (code, _) = solve("Joshua Tree\tBig Bend\tclosed\tno\n");
+    assert_eq!(code, EXIT_INFEASIBLE);
+    let (code, out) = solve("Joshua Tree\tYosemite\tclosed\tno\n");
+    assert_eq!(code, EXIT_ERROR);
+    assert!(out.is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_exit_codes() {
+    // too few nodes to reach a single tour
+    let (code, out) = run_args(&["solve", "--node-limit", "3"]);
+    assert_eq!(code, EXIT_INFEASIBLE);
+    assert!(out.contains("No tour found.\n"));
+    // heuristics never prove optimality
+    let (code, _) = run_args(&["solve", "--solver", "nearest"]);
+    assert_eq!(code, EXIT_FEASIBLE);
+    assert_eq!(run_args(&["solve", "--waypoint", "Zion"]).0, EXIT_ERROR);
+    assert_eq!(run_args(&["solve", "--frobnicate"]).0, EXIT_ERROR);
+    assert_eq!(run_args(&["launch"]).0, EXIT_ERROR);
+}
+
+#[test]
+fn test_validate_and_list() {
+    let (code, out) = run_args(&["validate"]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    // road distances don't always follow the triangle inequality
+    assert!(out.ends_with("10 waypoints, 0 errors, 12 warnings (no driving times)\n"));
+    assert!(out.starts_with(
+        "distance: warning: Mount Rainier, Washington to Acadia, Maine costs 5419755, \
+         but only 5400935 by way of Badlands, South Dakota\n"
+    ));
+
+    let (code, out) = run_args(&["list", "--state", "utah", "--category", "park"]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert_eq!(out.lines().count(), 5);
+    assert!(out.starts_with("Arches, Utah"));
+}
+
+#[test]
+fn test_plan_spec() {
+    let (code, out) = run_args(&["plan", "--spec", "example-trip.json", "--format", "json"]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.contains("\"name\": \"Southwest parks\""));
+    assert!(out.contains("\"skipped\": ["));
+    assert!(out.contains("\"arrivals\": ["));
+    assert_eq!(run_args(&["plan"]).0, EXIT_ERROR);
+    assert_eq!(
+        run_args(&["plan", "--spec", "no-such-trip.json"]).0,
+        EXIT_ERROR
+    );
+}
+
+#[test]
+fn test_plan_pareto() {
+    let (code, out) = run_args(&["plan", "--spec", "example-trip.json", "--pareto", "stops"]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.contains("Trade-offs between distance and stops:\n"));
+    assert!(out.contains("   1. 1777861 meters, 2 skipped: Joshua Tree"));
+    let (code, out) = run_args(&[
+        "plan",
+        "--spec",
+        "example-trip.json",
+        "--pareto",
+        "duration",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.contains("\"against\": \"duration\""));
+    assert_eq!(
+        run_args(&[
+            "plan",
+            "--spec",
+            "example-trip.json",
+            "--pareto",
+            "distance"
+        ])
+        .0,
+        EXIT_ERROR
+    );
+}
+
+#[test]
+fn test_plan_alternatives() {
+    let (code, out) = run_args(&[
+        "plan",
+        "--spec",
+        "example-trip.json",
+        "--alternatives",
+        "3",
+        "--min-different",
+        "2",
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.contains("\n   1. "));
+    assert!(out.contains("\n   3. "));
+    let (code, out) = run_args(&[
+        "plan",
+        "--spec",
+        "example-trip.json",
+        "--alternatives",
+        "2",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert_eq!(out.matches("\"order\": [").count(), 2);
+}
+
+#[test]
+fn test_bench() {
+    let (code, out) = run_args(&[
+        "bench",
+        "--sizes",
+        "4",
+        "--solvers",
+        "bnb,2opt",
+        "--node-limit",
+        "100000",
+        "--format",
+        "csv",
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    let rows: Vec<_> = out
+        .lines()
+        .skip(1)
+        .map(|l| l.split(',').take(4).collect::<Vec<_>>())
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ["ten-parks", "10", "bnb", "unknown"],
+            ["ten-parks", "10", "2opt", "feasible"],
+            ["parks-4", "4", "bnb", "optimal"],
+            ["parks-4", "4", "2opt", "feasible"],
+            ["random-4-s1", "4", "bnb", "optimal"],
+            ["random-4-s1", "4", "2opt", "feasible"],
+        ]
+    );
+    assert_eq!(run_args(&["bench", "--sizes", "4,1"]).0, EXIT_ERROR);
+    assert_eq!(run_args(&["bench", "--sizes", "99"]).0, EXIT_ERROR);
+}
+
+#[test]
+fn test_tsplib_round_trip() {
+    let dir = std::env::temp_dir().join(format!("final-project-tsplib-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let problem = dir.join("four.tsp");
+    let tour = dir.join("four.tour");
+    let path = |p: &std::path::Path| p.to_str().unwrap().to_string();
+
+    let (code, _) = run_args(&[
+        "convert",
+        "--waypoints",
+        "Joshua Tree;Big Bend;Mount Rainier;Badlands",
+        "--output",
+        &path(&problem),
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    let text = std::fs::read_to_string(&problem).unwrap();
+    assert!(text.contains("DIMENSION: 4\nEDGE_WEIGHT_TYPE: EXPLICIT\n"));
+
+    // the same trip solved from the TSPLIB file costs the same
+    let (_, original) = run_args(&[
+        "solve",
+        "--waypoints",
+        "Joshua Tree;Big Bend;Mount Rainier;Badlands",
+    ]);
+    let (code, solved) = run_args(&["solve", "--matrix", &path(&problem)]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    let total = |out: &str| {
+        out.lines()
+            .find(|l| l.starts_with("Total"))
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(total(&solved), total(&original));
+
+    let (code, _) = run_args(&[
+        "solve",
+        "--matrix",
+        &path(&problem),
+        "--format",
+        "tour",
+        "--output",
+        &path(&tour),
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    let (code, out) = run_args(&[
+        "validate",
+        "--matrix",
+        &path(&problem),
+        "--tour",
+        &path(&tour),
+    ]);
+    assert_eq!(code, EXIT_OPTIMAL);
+    let cost = total(&original)
+        .split_whitespace()
+        .nth(3)
+        .unwrap()
+        .to_string();
+    assert!(out.ends_with(&format!("Tour Road trip has distance {}\n", cost)));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_solve_itinerary() {
+    let args = [
+        "solve",
+        "--waypoints",
+        "Joshua Tree;Death Valley;Big Bend",
+        "--start",
+        "2024-06-01 08:00",
+        "--stay",
+        "3",
+    ];
+    let (code, out) = run_args(&[&args[..], &["--format", "itinerary"]].concat());
+    assert_eq!(code, EXIT_OPTIMAL);
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("Leg  From"));
+    assert!(lines[2].starts_with("1    Joshua Tree, California"));
+    assert!(lines[2].contains("2024-06-01 08:00"));
+    assert!(lines[2].ends_with("3h 00m"));
+    assert!(lines[4].ends_with("-"));
+
+    let (_, out) = run_args(&[&args[..], &["--format", "markdown"]].concat());
+    assert!(out.starts_with("| Leg | From | To |"));
+    let (_, out) = run_args(&[&args[..], &["--format", "csv"]].concat());
+    assert!(out.starts_with("leg,from,to,meters,seconds,"));
+    assert!(out.lines().nth(1).unwrap().ends_with(",10800"));
+
+    let (code, _) = run_args(&[
+        "solve",
+        "--waypoints",
+        "Joshua Tree;Saguaro",
+        "--format",
+        "csv",
+    ]);
+    assert_eq!(code, EXIT_ERROR);
+}
+
+#[test]
+fn test_plan_calendar() {
+    let args = [
+        "plan",
+        "--spec",
+        "example-trip.json",
+        "--start",
+        "2024-06-01T08:00",
+    ];
+    let (code, out) = run_args(&[&args[..], &["--format", "ics"]].concat());
+    assert_eq!(code, EXIT_OPTIMAL);
+    assert!(out.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(out.contains("X-WR-CALNAME:Southwest parks\r\n"));
+    assert!(out.contains("TZID:America/Phoenix\r\n"));
+    // Death Valley to Zion leaves in Pacific time and arrives in Mountain
+    assert!(out.contains("DTSTART;TZID=America/Los_Angeles:20240602T080000\r\n"));
+    assert!(out.contains("SUMMARY:Drive to Zion\r\n"));
+
+    // the nights the daily limit calls for show up as stays
+    let (_, out) = run_args(&[&args[..], &["--format", "itinerary"]].concat());
+    assert!(out.contains("2024-06-02 08:00"));
+    assert!(out.contains("20h 26m"));
+
+    let (code, _) = run_args(&["plan", "--spec", "example-trip.json", "--format", "ics"]);
+    assert_eq!(code, EXIT_ERROR);
+}
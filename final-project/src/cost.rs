@@ -0,0 +1,112 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A leg cost that `roadtrip` can add up and compare.  Costs are never
+/// negative, so a partial tour never costs more than the tours it grows
+/// into.
+pub trait Cost: Copy + Ord + fmt::Debug {
+    /// What an empty tour costs.
+    const ZERO: Self;
+    /// Returns the sum, or `None` if it doesn't fit in the type.
+    fn checked_add(self, other: Self) -> Option<Self>;
+    /// Returns how much more this costs than `other`, or `None` if it
+    /// costs less.
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    /// Returns the cost as a float, for statistics like the median.
+    fn to_f64(self) -> f64;
+}
+
+impl Cost for u32 {
+    const ZERO: Self = 0;
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u32::checked_add(self, other)
+    }
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        u32::checked_sub(self, other)
+    }
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Cost for u64 {
+    const ZERO: Self = 0;
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u64::checked_add(self, other)
+    }
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        u64::checked_sub(self, other)
+    }
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+/// A finite, non-negative `f64` that can be ordered, for costs that
+/// aren't whole numbers.  A sum that would be infinite overflows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrderedFloat(f64);
+
+impl OrderedFloat {
+    /// Wraps a number, or returns `None` if it is negative, infinite or NaN.
+    pub fn new(value: f64) -> Option<Self> {
+        if value.is_finite() && value >= 0.0 {
+            // -0.0 would order before 0.0
+            Some(Self(value.abs()))
+        } else {
+            None
+        }
+    }
+    /// Returns the number.
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl Eq for OrderedFloat {}
+
+impl PartialOrd for OrderedFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl fmt::Display for OrderedFloat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Cost for OrderedFloat {
+    const ZERO: Self = OrderedFloat(0.0);
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Self::new(self.0 + other.0)
+    }
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        Self::new(self.0 - other.0)
+    }
+    fn to_f64(self) -> f64 {
+        self.0
+    }
+}
+
+/// Every tour there is costs more than the cost type can hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostOverflow;
+
+impl fmt::Display for CostOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "every tour costs more than the cost type can hold")
+    }
+}
+
+impl std::error::Error for CostOverflow {}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,42 @@
+// tests
+
+use super::*;
+
+#[test]
+fn test_checked_add() {
+    assert_eq!(Cost::checked_add(u32::MAX - 1, 1), Some(u32::MAX));
+    assert_eq!(Cost::checked_add(u32::MAX, 1), None);
+    assert_eq!(Cost::checked_add(u32::MAX as u64, 1), Some(1 << 32));
+    assert_eq!(Cost::checked_add(u64::MAX, 1), None);
+
+    let float = |x| OrderedFloat::new(x).unwrap();
+    assert_eq!(float(1.5).checked_add(float(2.25)), Some(float(3.75)));
+    assert_eq!(float(f64::MAX).checked_add(float(f64::MAX)), None);
+}
+
+#[test]
+fn test_checked_sub() {
+    assert_eq!(Cost::checked_sub(5u32, 5), Some(0));
+    assert_eq!(Cost::checked_sub(4u32, 5), None);
+    assert_eq!(Cost::checked_sub(1u64 << 32, 1), Some(u32::MAX as u64));
+
+    let float = |x| OrderedFloat::new(x).unwrap();
+    assert_eq!(float(3.75).checked_sub(float(1.5)), Some(float(2.25)));
+    assert_eq!(float(1.5).checked_sub(float(3.75)), None);
+}
+
+#[test]
+fn test_ordered_float() {
+    assert_eq!(OrderedFloat::new(-1.0), None);
+    assert_eq!(OrderedFloat::new(f64::NAN), None);
+    assert_eq!(OrderedFloat::new(f64::INFINITY), None);
+    assert_eq!(OrderedFloat::new(-0.0), Some(OrderedFloat::ZERO));
+    let mut costs: Vec<_> = [2.5, 0.5, 10.0, 1.0]
+        .iter()
+        .map(|&x| OrderedFloat::new(x).unwrap())
+        .collect();
+    costs.sort();
+    let sorted: Vec<f64> = costs.iter().map(|c| c.get()).collect();
+    assert_eq!(sorted, vec![0.5, 1.0, 2.5, 10.0]);
+    assert_eq!(costs[2].to_string(), "2.5");
+}
@@ -1,5 +1,93 @@
 use super::{Constraint, Domain, PartialValuation, Valuation, ValueType, Var};
 use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Bounds on how much work `CSP::bnb_limited` may do.  A search that runs
+/// into one returns its best solution so far without proving it optimal.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Stop after expanding this many search nodes.
+    pub max_nodes: Option<usize>,
+    /// Stop after this much wall-clock time.
+    pub time_limit: Option<Duration>,
+}
+
+/// How a branch and bound search ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchStatus {
+    /// The search space was exhausted, so the solution is optimal.
+    Optimal,
+    /// A limit was hit after finding a solution that may not be optimal.
+    Feasible,
+    /// The search space was exhausted without finding any solution.
+    Infeasible,
+    /// A limit was hit before any solution was found.
+    Unknown,
+}
+
+impl std::fmt::Display for SearchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(match self {
+            SearchStatus::Optimal => "optimal",
+            SearchStatus::Feasible => "feasible",
+            SearchStatus::Infeasible => "infeasible",
+            SearchStatus::Unknown => "unknown",
+        })
+    }
+}
+
+/// The result of `CSP::bnb_limited`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome<Val: ValueType> {
+    pub solution: Option<Valuation<Val>>,
+    pub status: SearchStatus,
+    /// How many search nodes were expanded.
+    pub nodes: usize,
+}
+
+/// The result of `CSP::bnb_k_best`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ranked<Val: ValueType> {
+    /// The solutions found, best first.
+    pub solutions: Vec<Valuation<Val>>,
+    /// `Optimal` if these are the best there are, even if fewer than asked
+    /// for.
+    pub status: SearchStatus,
+    /// How many search nodes were expanded, over all the searches.
+    pub nodes: usize,
+}
+
+/// The result of `CSP::pareto`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParetoOutcome<Val: ValueType, Cost> {
+    /// Solutions that no other solution beats in every cost, with their
+    /// costs, ordered by cost.  One solution is kept for each set of costs.
+    pub front: Vec<(Vec<Cost>, Valuation<Val>)>,
+    /// `Optimal` if the front is complete.
+    pub status: SearchStatus,
+    /// How many search nodes were expanded.
+    pub nodes: usize,
+}
+
+/// Whether `a` is at least as good as `b` in every cost.
+fn covers<Cost: Ord>(a: &[Cost], b: &[Cost]) -> bool {
+    a.iter().zip(b.iter()).all(|(x, y)| x <= y)
+}
+
+/// Lets the caller of `CSP::bnb_observed` watch the search as it runs and
+/// stop it early, e.g. from another thread.
+pub trait Observer<Cost> {
+    /// Called with the cost of each solution better than the last.
+    fn improved(&mut self, cost: &Cost) {}
+    /// Called before each node is expanded, with the number expanded so
+    /// far.  Returning true stops the search.
+    fn should_stop(&mut self, nodes: usize) -> bool {
+        false
+    }
+}
+
+/// Watches nothing and never stops the search.
+impl<Cost> Observer<Cost> for () {}
 
 /// A constraint satisfaction problem, parameterized on a value type and constraint theory.
 pub struct CSP<Val: ValueType, Con: Constraint<Val>> {
@@ -86,8 +174,8 @@ impl<Val: ValueType, Con: Constraint<Val>> CSP<Val, Con> {
                 .filter(|con| con.vars().contains(&v))
             {
                 match vals.propagate(v, c) {
-                    Some(vs) => vstack.extend(vs.into_iter()), // getting the list of changed variables, so enqueue, may need to filter out anything that's on the stack already
-                    None => return false,                      // propagation failed, got a conflict
+                    Some(vs) => vstack.extend(vs), // getting the list of changed variables, so enqueue, may need to filter out anything that's on the stack already
+                    None => return false,          // propagation failed, got a conflict
                 }
             }
         }
@@ -100,26 +188,138 @@ impl<Val: ValueType, Con: Constraint<Val>> CSP<Val, Con> {
         cost: impl Fn(&PartialValuation<Val>) -> Cost,
         limit: Cost,
     ) -> Option<Valuation<Val>> {
+        self.bnb_limited(cost, limit, &Limits::default()).solution
+    }
+
+    /// Like `bnb`, but gives up once the search runs into one of the given
+    /// limits, returning the best solution seen so far.
+    pub fn bnb_limited<Cost: Ord>(
+        &self,
+        cost: impl Fn(&PartialValuation<Val>) -> Cost,
+        limit: Cost,
+        limits: &Limits,
+    ) -> Outcome<Val> {
+        self.bnb_observed(cost, limit, limits, &mut ())
+    }
+
+    /// Like `bnb_limited`, but reports each improved solution to `observer`
+    /// and stops when it asks to, as if a limit had been reached.
+    pub fn bnb_observed<Cost: Ord>(
+        &self,
+        cost: impl Fn(&PartialValuation<Val>) -> Cost,
+        limit: Cost,
+        limits: &Limits,
+        observer: &mut dyn Observer<Cost>,
+    ) -> Outcome<Val> {
+        self.search(&cost, limit, limits, observer, &|_| true)
+    }
+
+    /// Like `bnb`, but finds up to `k` different solutions in cost order:
+    /// the best, then the best of the rest that `differs` from it, and so
+    /// on, each by a search of its own.  `differs(vals, earlier)` tells
+    /// whether a partial valuation can still turn out different enough
+    /// from an earlier solution; a repeat of one is never allowed.  The
+    /// limits apply to all the searches together.
+    pub fn bnb_k_best<Cost: Ord + Clone>(
+        &self,
+        cost: impl Fn(&PartialValuation<Val>) -> Cost,
+        limit: Cost,
+        k: usize,
+        differs: impl Fn(&PartialValuation<Val>, &Valuation<Val>) -> bool,
+        limits: &Limits,
+    ) -> Ranked<Val> {
+        let start = Instant::now();
+        let mut solutions: Vec<Valuation<Val>> = Vec::new();
+        let mut nodes = 0;
+        let mut status = SearchStatus::Optimal;
+        while solutions.len() < k {
+            let remaining = Limits {
+                max_nodes: limits.max_nodes.map(|max| max.saturating_sub(nodes)),
+                time_limit: limits.time_limit.map(|t| t.saturating_sub(start.elapsed())),
+            };
+            let allowed = |vals: &PartialValuation<Val>| {
+                solutions.iter().all(|earlier| {
+                    let same =
+                        (0..earlier.len()).all(|i| vals.get_value(Var(i)) == Some(earlier[i]));
+                    !same && differs(vals, earlier)
+                })
+            };
+            let outcome = self.search(&cost, limit.clone(), &remaining, &mut (), &allowed);
+            nodes += outcome.nodes;
+            let exhausted = matches!(
+                outcome.status,
+                SearchStatus::Optimal | SearchStatus::Infeasible
+            );
+            match outcome.solution {
+                Some(solution) if exhausted => solutions.push(solution),
+                Some(solution) => {
+                    solutions.push(solution);
+                    status = SearchStatus::Feasible;
+                    break;
+                }
+                None if exhausted => break,
+                None => {
+                    status = SearchStatus::Feasible;
+                    break;
+                }
+            }
+        }
+        if solutions.is_empty() && k > 0 {
+            status = match status {
+                SearchStatus::Optimal => SearchStatus::Infeasible,
+                _ => SearchStatus::Unknown,
+            };
+        }
+        Ranked {
+            solutions,
+            status,
+            nodes,
+        }
+    }
+
+    /// The branch and bound behind `bnb_observed` and `bnb_k_best`, which
+    /// also prunes any partial valuation that isn't `allowed`.
+    fn search<Cost: Ord>(
+        &self,
+        cost: &dyn Fn(&PartialValuation<Val>) -> Cost,
+        limit: Cost,
+        limits: &Limits,
+        observer: &mut dyn Observer<Cost>,
+        allowed: &dyn Fn(&PartialValuation<Val>) -> bool,
+    ) -> Outcome<Val> {
         // You can use the given initial limit and cost function
         // rather than using a fixed bound or a fixed `score()`
         // function.  Note that this works with any Ordered thing as
         // the type of cost!  So you can do scalars or lexicographic
         // orderings (the template from the slides won't exactly
-        // work for Pareto optimality).
+        // work for Pareto optimality; `pareto` below handles that).
         //todo!()
+        let deadline = limits.time_limit.map(|t| Instant::now() + t);
         let mut queue = VecDeque::new();
         queue.push_back(PartialValuation::new(self.domains.clone()));
         let mut best = None;
         let mut limit = limit;
+        let mut nodes = 0;
+        let mut exhausted = true;
 
         while let Some(vals) = queue.pop_front() {
+            // stop early if we've used up our budget
+            if limits.max_nodes.is_some_and(|max| nodes >= max)
+                || deadline.is_some_and(|d| Instant::now() >= d)
+                || observer.should_stop(nodes)
+            {
+                exhausted = false;
+                break;
+            }
+            nodes += 1;
             let score = cost(&vals);
             // bounding step
-            if score >= limit {
+            if score >= limit || !allowed(&vals) {
                 continue;
             }
             if self.is_solution(&vals) {
                 // update limit and best-seen solution
+                observer.improved(&score);
                 limit = score;
                 best = Some(vals);
                 continue;
@@ -139,6 +339,85 @@ impl<Val: ValueType, Con: Constraint<Val>> CSP<Val, Con> {
                 }
             }
         }
-        best.and_then(|v| v.finalize())
+        let solution = best.and_then(|v| v.finalize());
+        let status = match (&solution, exhausted) {
+            (Some(_), true) => SearchStatus::Optimal,
+            (Some(_), false) => SearchStatus::Feasible,
+            (None, true) => SearchStatus::Infeasible,
+            (None, false) => SearchStatus::Unknown,
+        };
+        Outcome {
+            solution,
+            status,
+            nodes,
+        }
+    }
+
+    /// Branch and bound over several costs at once, finding every
+    /// trade-off between them.  `cost` gives a lower bound on each cost,
+    /// and `limit` acts like a solution already found: a partial valuation
+    /// is pruned when `limit` or a solution on the front is at least as
+    /// good in every cost.
+    pub fn pareto<Cost: Ord + Clone>(
+        &self,
+        cost: impl Fn(&PartialValuation<Val>) -> Vec<Cost>,
+        limit: Vec<Cost>,
+        limits: &Limits,
+    ) -> ParetoOutcome<Val, Cost> {
+        let deadline = limits.time_limit.map(|t| Instant::now() + t);
+        let mut queue = VecDeque::new();
+        queue.push_back(PartialValuation::new(self.domains.clone()));
+        let mut front: Vec<(Vec<Cost>, PartialValuation<Val>)> = Vec::new();
+        let mut nodes = 0;
+        let mut exhausted = true;
+
+        while let Some(vals) = queue.pop_front() {
+            if limits.max_nodes.is_some_and(|max| nodes >= max)
+                || deadline.is_some_and(|d| Instant::now() >= d)
+            {
+                exhausted = false;
+                break;
+            }
+            nodes += 1;
+            let score = cost(&vals);
+            // bounding step: prune against the limit and the whole front
+            if covers(&limit, &score) || front.iter().any(|(f, _)| covers(f, &score)) {
+                continue;
+            }
+            if self.is_solution(&vals) {
+                // the new solution pushes out any it beats
+                front.retain(|(f, _)| !covers(&score, f));
+                front.push((score, vals));
+                continue;
+            }
+            if let Some((var, dom)) = self.pick_variable(&vals) {
+                for choice in dom.clone().into_iter() {
+                    let mut par_val = vals.clone();
+                    par_val.assign(var, choice);
+                    if self.propagate(var, &mut par_val) {
+                        queue.push_back(par_val);
+                    }
+                }
+            }
+        }
+        let mut front: Vec<_> = front
+            .into_iter()
+            .filter_map(|(score, vals)| Some((score, vals.finalize()?)))
+            .collect();
+        front.sort_by(|a, b| a.0.cmp(&b.0));
+        let status = match (front.is_empty(), exhausted) {
+            (false, true) => SearchStatus::Optimal,
+            (false, false) => SearchStatus::Feasible,
+            (true, true) => SearchStatus::Infeasible,
+            (true, false) => SearchStatus::Unknown,
+        };
+        ParetoOutcome {
+            front,
+            status,
+            nodes,
+        }
     }
 }
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,304 @@
+// tests
+
+use super::*;
+use crate::fd::FD;
+use crate::rng::Rng;
+
+/// Small random CSPs, each rebuilt from its seed so a failure names the
+/// seed that reproduces it.
+const SEEDS: std::ops::Range<u64> = 1..400;
+
+/// A random CSP over up to four variables with domains drawn from 0..=4,
+/// a few FD constraints, and a weight per variable for the cost.
+fn random_csp(seed: u64) -> (Vec<Domain<i16>>, Vec<FD>, Vec<i32>) {
+    let mut rng = Rng::new(seed);
+    let n = rng.range(2, 4) as usize;
+    let domains: Vec<_> = (0..n)
+        .map(|_| {
+            let mut values: Vec<i16> = (0..=4).filter(|_| rng.unit() < 0.6).collect();
+            if values.is_empty() {
+                values.push(rng.below(5) as i16);
+            }
+            Domain::new(values)
+        })
+        .collect();
+    let mut vars: Vec<Var> = (0..n).map(Var).collect();
+    let constraints = (0..rng.range(0, 2))
+        .map(|_| {
+            rng.shuffle(&mut vars);
+            let some = vars[..rng.range(2, n as u64) as usize].to_vec();
+            match rng.below(4) {
+                0 => FD::Eq(some),
+                1 => FD::AllDiff(some),
+                2 => FD::Inc(some),
+                _ => FD::Sum(some[0], some[1..].to_vec()),
+            }
+        })
+        .collect();
+    let weights = (0..n).map(|_| rng.range(1, 5) as i32).collect();
+    (domains, constraints, weights)
+}
+
+fn build(seed: u64) -> CSP<i16, FD> {
+    let (domains, constraints, _) = random_csp(seed);
+    let mut csp = CSP::new();
+    for d in domains {
+        csp.add_variable(d);
+    }
+    for c in constraints {
+        csp.add_constraint(c);
+    }
+    csp
+}
+
+/// The weighted sum of the fixed variables.  Values are never negative,
+/// so it never overestimates a partial valuation's best completion.
+fn cost(weights: &[i32]) -> impl Fn(&PartialValuation<i16>) -> i32 + '_ {
+    move |vals| {
+        vals.value_iter()
+            .map(|(var, value)| weights[var.0] * value as i32)
+            .sum()
+    }
+}
+
+/// Whether a full assignment satisfies every constraint.
+fn satisfies(constraints: &[FD], domains: &[Domain<i16>], values: &[i16]) -> bool {
+    let mut vals = PartialValuation::new(domains.to_vec());
+    for (i, &value) in values.iter().enumerate() {
+        if !domains[i].contains(&value) {
+            return false;
+        }
+        vals.assign(Var(i), value);
+    }
+    constraints.iter().all(|c| c.is_satisfied(&vals))
+}
+
+/// Every solution, found by trying every assignment.
+fn solutions(seed: u64) -> Vec<Vec<i16>> {
+    let (domains, constraints, _) = random_csp(seed);
+    let mut found = Vec::new();
+    let mut values = vec![0i16; domains.len()];
+    let mut index = vec![0usize; domains.len()];
+    loop {
+        for (i, d) in domains.iter().enumerate() {
+            values[i] = *d.iter().nth(index[i]).unwrap();
+        }
+        if satisfies(&constraints, &domains, &values) {
+            found.push(values.clone());
+        }
+        // count through the assignments like an odometer
+        let mut i = 0;
+        while i < index.len() && index[i] + 1 == domains[i].len() {
+            index[i] = 0;
+            i += 1;
+        }
+        if i == index.len() {
+            return found;
+        }
+        index[i] += 1;
+    }
+}
+
+/// The cheapest solution's cost, found by trying every assignment.
+fn exhaustive(seed: u64) -> Option<i32> {
+    let weights = random_csp(seed).2;
+    solutions(seed)
+        .iter()
+        .map(|values| {
+            values
+                .iter()
+                .zip(&weights)
+                .map(|(&v, w)| w * v as i32)
+                .sum()
+        })
+        .min()
+}
+
+/// Checks a solution against the constraints and returns its cost.
+fn check(seed: u64, solution: &Valuation<i16>) -> i32 {
+    let (domains, constraints, weights) = random_csp(seed);
+    let values: Vec<i16> = solution.iter().cloned().collect();
+    assert!(
+        satisfies(&constraints, &domains, &values),
+        "seed {}: {:?} breaks a constraint",
+        seed,
+        values
+    );
+    values
+        .iter()
+        .zip(&weights)
+        .map(|(&v, w)| w * v as i32)
+        .sum()
+}
+
+#[test]
+fn test_bnb_matches_exhaustive_search() {
+    for seed in SEEDS {
+        let weights = random_csp(seed).2;
+        let expected = exhaustive(seed);
+        let found = build(seed).bnb(cost(&weights), i32::MAX);
+        assert_eq!(
+            found.as_ref().map(|s| check(seed, s)),
+            expected,
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn test_limited_search_is_sound() {
+    for seed in SEEDS {
+        let weights = random_csp(seed).2;
+        let expected = exhaustive(seed);
+        let limits = Limits {
+            max_nodes: Some(1 + (seed % 7) as usize),
+            time_limit: None,
+        };
+        let outcome = build(seed).bnb_limited(cost(&weights), i32::MAX, &limits);
+        assert!(outcome.nodes <= limits.max_nodes.unwrap(), "seed {}", seed);
+        let found = outcome.solution.as_ref().map(|s| check(seed, s));
+        match outcome.status {
+            SearchStatus::Optimal => assert_eq!(found, expected, "seed {}", seed),
+            SearchStatus::Infeasible => assert_eq!(expected, None, "seed {}", seed),
+            SearchStatus::Feasible => assert!(found >= expected, "seed {}", seed),
+            SearchStatus::Unknown => assert_eq!(found, None, "seed {}", seed),
+        }
+    }
+}
+
+/// Records every improvement and stops after a fixed number of nodes.
+struct Recorder {
+    costs: Vec<i32>,
+    stop_at: usize,
+}
+
+impl Observer<i32> for Recorder {
+    fn improved(&mut self, cost: &i32) {
+        self.costs.push(*cost);
+    }
+    fn should_stop(&mut self, nodes: usize) -> bool {
+        nodes >= self.stop_at
+    }
+}
+
+#[test]
+fn test_observer_sees_every_improvement() {
+    for seed in SEEDS {
+        let weights = random_csp(seed).2;
+        let mut recorder = Recorder {
+            costs: Vec::new(),
+            stop_at: usize::MAX,
+        };
+        let outcome =
+            build(seed).bnb_observed(cost(&weights), i32::MAX, &Limits::default(), &mut recorder);
+        assert!(
+            recorder.costs.windows(2).all(|w| w[0] > w[1]),
+            "seed {}",
+            seed
+        );
+        let found = outcome.solution.as_ref().map(|s| check(seed, s));
+        assert_eq!(recorder.costs.last().cloned(), found, "seed {}", seed);
+        assert_eq!(found, exhaustive(seed), "seed {}", seed);
+
+        // stopping by the observer is reported like hitting a node limit
+        let mut recorder = Recorder {
+            costs: Vec::new(),
+            stop_at: 3,
+        };
+        let stopped =
+            build(seed).bnb_observed(cost(&weights), i32::MAX, &Limits::default(), &mut recorder);
+        let limits = Limits {
+            max_nodes: Some(3),
+            time_limit: None,
+        };
+        let limited = build(seed).bnb_limited(cost(&weights), i32::MAX, &limits);
+        assert_eq!(stopped, limited, "seed {}", seed);
+    }
+}
+
+/// The weighted sum and, pulling the other way, how far the fixed
+/// variables are below 4.
+fn costs(weights: &[i32]) -> impl Fn(&PartialValuation<i16>) -> Vec<i32> + '_ {
+    move |vals| {
+        let below: i32 = vals.value_iter().map(|(_, v)| 4 - v as i32).sum();
+        vec![cost(weights)(vals), below]
+    }
+}
+
+#[test]
+fn test_pareto_matches_exhaustive_search() {
+    for seed in SEEDS {
+        let weights = random_csp(seed).2;
+        let points: Vec<Vec<i32>> = solutions(seed)
+            .iter()
+            .map(|values| {
+                vec![
+                    values
+                        .iter()
+                        .zip(&weights)
+                        .map(|(&v, w)| w * v as i32)
+                        .sum(),
+                    values.iter().map(|&v| 4 - v as i32).sum(),
+                ]
+            })
+            .collect();
+        let mut expected: Vec<_> = points
+            .iter()
+            .filter(|p| {
+                !points
+                    .iter()
+                    .any(|q| q != *p && q[0] <= p[0] && q[1] <= p[1])
+            })
+            .cloned()
+            .collect();
+        expected.sort();
+        expected.dedup();
+
+        let outcome = build(seed).pareto(costs(&weights), vec![i32::MAX; 2], &Limits::default());
+        let found: Vec<_> = outcome.front.iter().map(|(c, _)| c.clone()).collect();
+        assert_eq!(found, expected, "seed {}", seed);
+        for (c, solution) in outcome.front.iter() {
+            assert_eq!(check(seed, solution), c[0], "seed {}", seed);
+        }
+        let status = if expected.is_empty() {
+            SearchStatus::Infeasible
+        } else {
+            SearchStatus::Optimal
+        };
+        assert_eq!(outcome.status, status, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_k_best_matches_exhaustive_search() {
+    for seed in SEEDS {
+        let weights = random_csp(seed).2;
+        let mut expected: Vec<i32> = solutions(seed)
+            .iter()
+            .map(|values| {
+                values
+                    .iter()
+                    .zip(&weights)
+                    .map(|(&v, w)| w * v as i32)
+                    .sum()
+            })
+            .collect();
+        expected.sort();
+        expected.truncate(3);
+
+        let ranked =
+            build(seed).bnb_k_best(cost(&weights), i32::MAX, 3, |_, _| true, &Limits::default());
+        let found: Vec<_> = ranked.solutions.iter().map(|s| check(seed, s)).collect();
+        assert_eq!(found, expected, "seed {}", seed);
+        for (i, a) in ranked.solutions.iter().enumerate() {
+            assert!(!ranked.solutions[..i].contains(a), "seed {}", seed);
+        }
+        let status = if expected.is_empty() {
+            SearchStatus::Infeasible
+        } else {
+            SearchStatus::Optimal
+        };
+        assert_eq!(ranked.status, status, "seed {}", seed);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::cost::Cost;
+use std::fmt;
+use std::ops::Index;
+
+/// Leg costs between `len()` vertices in one flat table, so looking up a
+/// leg is an index rather than a hash.  A missing leg is `None`; the
+/// diagonal starts out at zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceMatrix<C = u32> {
+    n: usize,
+    names: Vec<String>,
+    cells: Vec<Option<C>>,
+}
+
+/// A problem found by `DistanceMatrix::diagnose`.  Vertices are given by
+/// name, or by number if the matrix has no names.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diagnostic<C> {
+    /// There is no leg between two different vertices.  An error, since
+    /// a tour may need any leg.
+    Missing { from: String, to: String },
+    /// A leg between two different vertices costs nothing, which usually
+    /// means a failed lookup was written as 0.  Also an error.
+    Zero { from: String, to: String },
+    /// Staying put costs something.  Also an error.
+    NonZeroDiagonal { vertex: String, cost: C },
+    /// A leg costs different amounts each way, as one-way roads can.
+    Asymmetric {
+        from: String,
+        to: String,
+        there: C,
+        back: C,
+    },
+    /// Going by way of another vertex is cheaper than the direct leg, so
+    /// the direct leg is probably wrong or takes a long way round.
+    Triangle {
+        from: String,
+        via: String,
+        to: String,
+        direct: C,
+        detour: C,
+    },
+    /// A leg costs far more, or far less, than the legs usually do, like a
+    /// distance written in the wrong unit.
+    Outlier {
+        from: String,
+        to: String,
+        cost: C,
+        median: C,
+    },
+}
+
+/// An edge names a vertex that a matrix doesn't have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoVertex(pub usize);
+
+impl fmt::Display for NoVertex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no vertex {}", self.0)
+    }
+}
+
+impl std::error::Error for NoVertex {}
+
+impl<C> Diagnostic<C> {
+    /// Returns whether this problem stops the matrix from being used.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Diagnostic::Missing { .. }
+                | Diagnostic::Zero { .. }
+                | Diagnostic::NonZeroDiagonal { .. }
+        )
+    }
+}
+
+impl<C: fmt::Display> fmt::Display for Diagnostic<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::Missing { from, to } => write!(f, "error: no leg from {} to {}", from, to),
+            Diagnostic::Zero { from, to } => write!(f, "error: {} to {} costs nothing", from, to),
+            Diagnostic::NonZeroDiagonal { vertex, cost } => {
+                write!(f, "error: {} to itself costs {}", vertex, cost)
+            }
+            Diagnostic::Asymmetric {
+                from,
+                to,
+                there,
+                back,
+            } => write!(
+                f,
+                "warning: {} to {} costs {} but {} on the way back",
+                from, to, there, back
+            ),
+            Diagnostic::Triangle {
+                from,
+                via,
+                to,
+                direct,
+                detour,
+            } => write!(
+                f,
+                "warning: {} to {} costs {}, but only {} by way of {}",
+                from, to, direct, detour, via
+            ),
+            Diagnostic::Outlier {
+                from,
+                to,
+                cost,
+                median,
+            } => write!(
+                f,
+                "warning: {} to {} costs {}, far from the typical leg of {}",
+                from, to, cost, median
+            ),
+        }
+    }
+}
+
+/// How many median absolute deviations from the median, scaled as in
+/// Iglewicz and Hoaglin's modified z-score, make a leg an outlier.
+pub const OUTLIER_SCORE: f64 = 3.5;
+
+impl<C: Cost> DistanceMatrix<C> {
+    /// Creates a matrix over `n` vertices with every leg between different
+    /// vertices missing.  Panics if `n * n` overflows.
+    pub fn new(n: usize) -> Self {
+        let size = n.checked_mul(n).expect("too many vertices for a matrix");
+        let mut cells = vec![None; size];
+        for v in 0..n {
+            cells[v * n + v] = Some(C::ZERO);
+        }
+        Self {
+            n,
+            names: Vec::new(),
+            cells,
+        }
+    }
+    /// Makes a matrix over `n` vertices from `roadtrip`-style edges, or
+    /// fails if an edge names a vertex past them.
+    pub fn from_edges(n: usize, edges: &[(usize, usize, C)]) -> Result<Self, NoVertex> {
+        match edges.iter().find(|&&(a, b, _)| a >= n || b >= n) {
+            Some(&(a, b, _)) => Err(NoVertex(if a >= n { a } else { b })),
+            None => Ok(Self::from_edges_within(n, edges)),
+        }
+    }
+    /// Like `from_edges`, but leaves out the edges that name a vertex past
+    /// the first `n`, as `roadtrip` does.
+    pub fn from_edges_within(n: usize, edges: &[(usize, usize, C)]) -> Self {
+        let mut matrix = Self::new(n);
+        for &(a, b, c) in edges.iter() {
+            if a < n && b < n {
+                matrix.set(a, b, Some(c));
+            }
+        }
+        matrix
+    }
+    /// Makes a matrix over named vertices, asking `cost` for the leg
+    /// between each two different ones by their positions in `names`.
+    pub fn from_names(names: Vec<String>, cost: impl Fn(usize, usize) -> Option<C>) -> Self {
+        let mut matrix = Self::new(names.len());
+        for from in 0..names.len() {
+            for to in 0..names.len() {
+                if from != to {
+                    matrix.set(from, to, cost(from, to));
+                }
+            }
+        }
+        matrix.names = names;
+        matrix
+    }
+
+    /// Returns the number of vertices.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+    /// Returns whether there are no vertices.
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+    /// Returns the vertex names, or nothing if the matrix has none.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+    /// Finds a vertex by its name, ignoring case.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        let name = name.trim().to_lowercase();
+        self.names.iter().position(|n| n.to_lowercase() == name)
+    }
+    /// Returns the cost of a leg, or `None` if it is missing or either
+    /// vertex is out of range.
+    #[inline]
+    pub fn get(&self, from: usize, to: usize) -> Option<C> {
+        if from < self.n && to < self.n {
+            self.cells[from * self.n + to]
+        } else {
+            None
+        }
+    }
+    /// Sets the cost of a leg; `None` removes it.
+    pub fn set(&mut self, from: usize, to: usize, cost: Option<C>) {
+        assert!(from < self.n && to < self.n, "no vertex {}", from.max(to));
+        self.cells[from * self.n + to] = cost;
+    }
+    /// Returns the legs between different vertices as `roadtrip`-style
+    /// edges.
+    pub fn edges(&self) -> Vec<(usize, usize, C)> {
+        let mut edges = Vec::new();
+        for from in 0..self.n {
+            for to in 0..self.n {
+                if let (true, Some(c)) = (from != to, self.get(from, to)) {
+                    edges.push((from, to, c));
+                }
+            }
+        }
+        edges
+    }
+
+    fn label(&self, v: usize) -> String {
+        self.names.get(v).cloned().unwrap_or_else(|| v.to_string())
+    }
+
+    /// Checks for missing legs, legs between different vertices that cost
+    /// nothing, a diagonal that isn't zero, legs that cost more one way
+    /// than the other, legs that a detour through one other vertex beats
+    /// (only the cheapest detour is reported) and outlying leg costs.
+    pub fn diagnose(&self) -> Vec<Diagnostic<C>> {
+        self.diagnose_except(|_, _| false)
+    }
+    /// Like `diagnose`, but leaves the legs that `closed` picks out, such
+    /// as roads closed on purpose, unreported when they are missing.
+    pub fn diagnose_except(&self, closed: impl Fn(usize, usize) -> bool) -> Vec<Diagnostic<C>> {
+        let mut found = Vec::new();
+        for v in 0..self.n {
+            match self.get(v, v) {
+                Some(cost) if cost == C::ZERO => {}
+                Some(cost) => found.push(Diagnostic::NonZeroDiagonal {
+                    vertex: self.label(v),
+                    cost,
+                }),
+                None => found.push(Diagnostic::Missing {
+                    from: self.label(v),
+                    to: self.label(v),
+                }),
+            }
+        }
+        for from in 0..self.n {
+            for to in (0..self.n).filter(|&to| to != from) {
+                let direct = match self.get(from, to) {
+                    Some(direct) => direct,
+                    None => {
+                        if !closed(from, to) {
+                            found.push(Diagnostic::Missing {
+                                from: self.label(from),
+                                to: self.label(to),
+                            });
+                        }
+                        continue;
+                    }
+                };
+                if direct == C::ZERO {
+                    found.push(Diagnostic::Zero {
+                        from: self.label(from),
+                        to: self.label(to),
+                    });
+                }
+                match self.get(to, from) {
+                    Some(back) if from < to && back != direct => {
+                        found.push(Diagnostic::Asymmetric {
+                            from: self.label(from),
+                            to: self.label(to),
+                            there: direct,
+                            back,
+                        })
+                    }
+                    _ => {}
+                }
+                let detour = (0..self.n)
+                    .filter(|&via| via != from && via != to)
+                    .filter_map(|via| {
+                        let cost = self.get(from, via)?.checked_add(self.get(via, to)?)?;
+                        Some((cost, via))
+                    })
+                    .min();
+                if let Some((detour, via)) = detour.filter(|&(d, _)| d < direct) {
+                    found.push(Diagnostic::Triangle {
+                        from: self.label(from),
+                        via: self.label(via),
+                        to: self.label(to),
+                        direct,
+                        detour,
+                    });
+                }
+            }
+        }
+        found.extend(self.outliers());
+        found
+    }
+
+    /// Legs whose modified z-score is above `OUTLIER_SCORE`.  Needs some
+    /// spread among the leg costs to say anything.
+    fn outliers(&self) -> Vec<Diagnostic<C>> {
+        let mut legs = self.edges();
+        legs.sort_by_key(|&(_, _, c)| c);
+        if legs.is_empty() {
+            return Vec::new();
+        }
+        let median = legs[legs.len() / 2].2;
+        let mut deviations: Vec<f64> = legs
+            .iter()
+            .map(|&(_, _, c)| (c.to_f64() - median.to_f64()).abs())
+            .collect();
+        deviations.sort_by(f64::total_cmp);
+        let spread = deviations[deviations.len() / 2];
+        if spread == 0.0 {
+            return Vec::new();
+        }
+        legs.iter()
+            .filter(|&&(_, _, c)| {
+                0.6745 * (c.to_f64() - median.to_f64()).abs() / spread > OUTLIER_SCORE
+            })
+            .map(|&(from, to, cost)| Diagnostic::Outlier {
+                from: self.label(from),
+                to: self.label(to),
+                cost,
+                median,
+            })
+            .collect()
+    }
+}
+
+impl<C> Index<(usize, usize)> for DistanceMatrix<C> {
+    type Output = Option<C>;
+    fn index(&self, (from, to): (usize, usize)) -> &Self::Output {
+        assert!(from < self.n && to < self.n, "no vertex {}", from.max(to));
+        &self.cells[from * self.n + to]
+    }
+}
+
+#[cfg(test)]
+mod pub_tests;
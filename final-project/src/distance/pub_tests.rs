@@ -0,0 +1,133 @@
+// tests
+
+use super::*;
+
+#[test]
+fn test_from_edges() {
+    let edges = [(0, 1, 7), (1, 2, 5)];
+    assert_eq!(
+        DistanceMatrix::<u32>::from_edges(2, &edges),
+        Err(NoVertex(2))
+    );
+    assert_eq!(NoVertex(2).to_string(), "no vertex 2");
+    let stray = [(0, usize::MAX, 1), (200000, 1, 1)];
+    assert_eq!(
+        DistanceMatrix::<u32>::from_edges(2, &stray),
+        Err(NoVertex(usize::MAX))
+    );
+    assert_eq!(
+        DistanceMatrix::<u32>::from_edges_within(2, &stray).edges(),
+        vec![]
+    );
+    let mut matrix: DistanceMatrix = DistanceMatrix::from_edges(3, &edges).unwrap();
+    assert_eq!(matrix.len(), 3);
+    assert_eq!(matrix.get(0, 1), Some(7));
+    assert_eq!(matrix.get(1, 0), None);
+    assert_eq!(matrix.get(2, 2), Some(0));
+    assert_eq!(matrix.get(0, 3), None);
+    assert_eq!(matrix[(1, 2)], Some(5));
+    matrix.set(1, 2, None);
+    matrix.set(2, 0, Some(4));
+    assert_eq!(matrix.edges(), vec![(0, 1, 7), (2, 0, 4)]);
+    assert!(DistanceMatrix::<u64>::new(0).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "too many vertices")]
+fn test_too_many_vertices() {
+    DistanceMatrix::<u32>::new(usize::MAX);
+}
+
+#[test]
+#[should_panic(expected = "no vertex 3")]
+fn test_index_out_of_range() {
+    let matrix: DistanceMatrix = DistanceMatrix::new(3);
+    let _ = matrix[(0, 3)];
+}
+
+#[test]
+fn test_from_names() {
+    let names = vec!["Zion".to_string(), "Arches".to_string()];
+    let matrix = DistanceMatrix::from_names(names, |i, j| Some(10 * i as u32 + j as u32 + 1));
+    assert_eq!(matrix.index_of(" arches"), Some(1));
+    assert_eq!(matrix.index_of("Bryce"), None);
+    assert_eq!(matrix.get(0, 1), Some(2));
+    assert_eq!(matrix.get(1, 0), Some(11));
+    assert_eq!(matrix.get(1, 1), Some(0));
+}
+
+#[test]
+fn test_diagnose_errors() {
+    let mut matrix: DistanceMatrix =
+        DistanceMatrix::from_edges(3, &[(0, 1, 4), (1, 0, 4), (1, 2, 4)]).unwrap();
+    matrix.set(2, 2, Some(3));
+    let found = matrix.diagnose();
+    assert!(found.iter().all(|d| d.is_error()));
+    assert_eq!(found.len(), 4);
+    assert_eq!(
+        found[0],
+        Diagnostic::NonZeroDiagonal {
+            vertex: "2".to_string(),
+            cost: 3
+        }
+    );
+    assert_eq!(found[0].to_string(), "error: 2 to itself costs 3");
+    assert_eq!(found[1].to_string(), "error: no leg from 0 to 2");
+    assert!(DistanceMatrix::<u32>::new(1).diagnose().is_empty());
+
+    let mut matrix: DistanceMatrix = DistanceMatrix::new(2);
+    matrix.set(0, 1, Some(0));
+    let found = matrix.diagnose_except(|from, to| (from, to) == (1, 0));
+    assert_eq!(
+        found,
+        vec![Diagnostic::Zero {
+            from: "0".to_string(),
+            to: "1".to_string()
+        }]
+    );
+    assert_eq!(found[0].to_string(), "error: 0 to 1 costs nothing");
+    assert!(found[0].is_error());
+    assert_eq!(matrix.diagnose().len(), 2);
+}
+
+#[test]
+fn test_diagnose_warnings() {
+    let names: Vec<String> = ["A", "B", "C", "D", "E"]
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    // legs cost a little more the higher the vertices, except A to E
+    let matrix = DistanceMatrix::from_names(names, |i, j| {
+        Some(if (i, j) == (0, 4) {
+            1000
+        } else {
+            100 + i as u32 + j as u32
+        })
+    });
+    let found = matrix.diagnose();
+    assert!(found.iter().all(|d| !d.is_error()));
+    assert!(found.contains(&Diagnostic::Asymmetric {
+        from: "A".to_string(),
+        to: "E".to_string(),
+        there: 1000,
+        back: 104,
+    }));
+    // B is the cheapest way round
+    assert!(found.contains(&Diagnostic::Triangle {
+        from: "A".to_string(),
+        via: "B".to_string(),
+        to: "E".to_string(),
+        direct: 1000,
+        detour: 206,
+    }));
+    let outliers: Vec<_> = found
+        .iter()
+        .filter(|d| matches!(d, Diagnostic::Outlier { .. }))
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        outliers,
+        vec!["warning: A to E costs 1000, far from the typical leg of 104"]
+    );
+    assert_eq!(found.len(), 3);
+}
@@ -0,0 +1,84 @@
+use crate::json::Json;
+use crate::route::Route;
+use std::path::Path;
+
+/// Converts a route to a GeoJSON `FeatureCollection`.  Every position of the
+/// route becomes a `Point` feature (a closed route's return home is left
+/// out, since it is the same place as the start) and every leg becomes a
+/// `LineString`, drawn along its road geometry when the route has one.
+pub fn feature_collection(route: &Route) -> Json {
+    let arrivals = route.arrivals();
+    let mut features = Vec::new();
+    for (position, &(distance, duration)) in arrivals.iter().enumerate().take(route.stop_count()) {
+        let stop = route.stop_at(position);
+        features.push(feature(
+            Json::object(vec![
+                ("type", Json::str("Point")),
+                ("coordinates", point(stop.coords())),
+            ]),
+            vec![
+                ("kind", Json::str("stop")),
+                ("name", Json::str(&stop.label())),
+                ("order", position.into()),
+                ("category", Json::str(&stop.category.to_string())),
+                ("arrival_distance_m", (distance as f64).into()),
+                ("arrival_duration_s", optional(duration)),
+            ],
+        ));
+    }
+    for (i, leg) in route.legs.iter().enumerate() {
+        features.push(feature(
+            Json::object(vec![
+                ("type", Json::str("LineString")),
+                (
+                    "coordinates",
+                    Json::Array(route.leg_geometry(leg).into_iter().map(point).collect()),
+                ),
+            ]),
+            vec![
+                ("kind", Json::str("leg")),
+                ("leg", i.into()),
+                ("from", Json::str(&route.stops[leg.from].label())),
+                ("to", Json::str(&route.stops[leg.to].label())),
+                ("distance_m", leg.distance.into()),
+                ("duration_s", optional(leg.duration.map(u64::from))),
+                ("road_geometry", Json::Bool(leg.geometry.is_some())),
+            ],
+        ));
+    }
+
+    Json::object(vec![
+        ("type", Json::str("FeatureCollection")),
+        ("features", Json::Array(features)),
+    ])
+}
+
+/// Renders a route as a GeoJSON document.
+pub fn to_geojson(route: &Route) -> String {
+    feature_collection(route).pretty()
+}
+
+/// Writes a route to a `.geojson` file.
+pub fn write_geojson(route: &Route, path: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::write(path, to_geojson(route))
+}
+
+fn feature(geometry: Json, properties: Vec<(&str, Json)>) -> Json {
+    Json::object(vec![
+        ("type", Json::str("Feature")),
+        ("geometry", geometry),
+        ("properties", Json::object(properties)),
+    ])
+}
+
+/// GeoJSON positions are `[lon, lat]`, the other way round from ours.
+fn point((lat, lon): (f64, f64)) -> Json {
+    Json::Array(vec![lon.into(), lat.into()])
+}
+
+fn optional(n: Option<u64>) -> Json {
+    n.map_or(Json::Null, |n| Json::Number(n as f64))
+}
+
+#[cfg(test)]
+mod pub_tests;
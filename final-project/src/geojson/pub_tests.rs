@@ -0,0 +1,85 @@
+// tests
+
+use super::*;
+use crate::json::Json;
+use crate::opt::roadtrip;
+use crate::roadgraph::{RoadGraph, ShortestPath};
+use crate::waypoint::lookup;
+
+fn stops() -> Vec<crate::waypoint::Waypoint> {
+    ["Joshua Tree", "Death Valley", "White Sands"]
+        .iter()
+        .map(|n| lookup(n).unwrap())
+        .collect()
+}
+
+fn symmetric(costs: &[(usize, usize, u32)]) -> Vec<(usize, usize, u32)> {
+    costs
+        .iter()
+        .flat_map(|&(a, b, c)| vec![(a, b, c), (b, a, c)])
+        .collect()
+}
+
+#[test]
+fn test_points_and_lines() {
+    let distances = symmetric(&[(0, 1, 418564), (0, 2, 1143520), (1, 2, 1354266)]);
+    let durations = symmetric(&[(0, 1, 15000), (0, 2, 40000), (1, 2, 48000)]);
+    let (order, cost) = roadtrip(3, &distances);
+    let mut route = Route::new(stops(), order, &distances);
+    route.add_durations(&durations);
+    assert_eq!(route.total_distance(), cost as u64);
+
+    let doc = feature_collection(&route);
+    assert_eq!(doc.get("type"), Some(&Json::str("FeatureCollection")));
+    let features = match doc.get("features") {
+        Some(Json::Array(f)) => f.clone(),
+        other => panic!("no features: {:?}", other),
+    };
+    // three stops and three legs back home
+    assert_eq!(features.len(), 6);
+
+    let first = &features[0];
+    let coords = first.get("geometry").unwrap().get("coordinates").unwrap();
+    assert_eq!(coords, &Json::Array(vec![(-115.90).into(), 33.79.into()]));
+    let props = first.get("properties").unwrap();
+    assert_eq!(
+        props.get("name"),
+        Some(&Json::str("Joshua Tree, California"))
+    );
+    assert_eq!(props.get("arrival_duration_s"), Some(&Json::Number(0.0)));
+
+    let last_leg = features[5].get("properties").unwrap();
+    assert_eq!(
+        last_leg.get("to"),
+        Some(&Json::str("Joshua Tree, California"))
+    );
+    assert_eq!(last_leg.get("road_geometry"), Some(&Json::Bool(false)));
+    assert!(to_geojson(&route).starts_with("{\n  \"type\": \"FeatureCollection\""));
+}
+
+#[test]
+fn test_road_geometry() {
+    let mut graph = RoadGraph::new();
+    let a = graph.add_node(33.79, -115.90);
+    let mid = graph.add_node(35.0, -116.5);
+    let b = graph.add_node(36.24, -116.82);
+    graph.add_road(a, mid, 200000).unwrap();
+    graph.add_road(mid, b, 230000).unwrap();
+    let table = graph.legs(&[a, b], ShortestPath::Dijkstra).unwrap();
+
+    let mut route = Route::new(stops()[..2].to_vec(), vec![0, 1], &table.edges());
+    route.add_geometry(&graph, &table);
+    let doc = feature_collection(&route);
+    let leg = match doc.get("features") {
+        Some(Json::Array(f)) => f[2].clone(),
+        other => panic!("no features: {:?}", other),
+    };
+    match leg.get("geometry").unwrap().get("coordinates") {
+        Some(Json::Array(line)) => assert_eq!(line.len(), 3),
+        other => panic!("no line: {:?}", other),
+    }
+    assert_eq!(
+        leg.get("properties").unwrap().get("duration_s"),
+        Some(&Json::Null)
+    );
+}
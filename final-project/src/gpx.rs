@@ -0,0 +1,88 @@
+use crate::report::escape_markup;
+use crate::route::Route;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Renders a route as a GPX 1.1 document for GPS units.  Stops appear both
+/// as `wpt` waypoints and, in order, as the `rtept`s of a single `rte` so the
+/// unit can navigate it.  When any leg has road geometry the route is also
+/// written as a `trk` per leg, which units display as the exact road to drive.
+pub fn to_gpx(route: &Route, name: &str) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<gpx version=\"1.1\" creator=\"final-project\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+    writeln!(
+        out,
+        "  <metadata>\n    <name>{}</name>\n  </metadata>",
+        escape_markup(name)
+    )
+    .unwrap();
+
+    for position in 0..route.stop_count() {
+        let stop = route.stop_at(position);
+        writeln!(
+            out,
+            "  <wpt lat=\"{:.6}\" lon=\"{:.6}\">\n    <name>{}</name>\n    <desc>{}</desc>\n    <type>{}</type>\n  </wpt>",
+            stop.lat,
+            stop.lon,
+            escape_markup(&stop.label()),
+            escape_markup(&route.describe_stop(position)),
+            stop.category
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "  <rte>\n    <name>{}</name>", escape_markup(name)).unwrap();
+    for position in 0..route.order.len() {
+        let stop = route.stop_at(position);
+        // Each route point after the first carries the leg that leads to it
+        let comment = match position {
+            0 => "Start".to_string(),
+            _ => format!(
+                "Leg {}: {}",
+                position,
+                route.describe_leg(&route.legs[position - 1])
+            ),
+        };
+        writeln!(
+            out,
+            "    <rtept lat=\"{:.6}\" lon=\"{:.6}\">\n      <name>{}</name>\n      <cmt>{}</cmt>\n      <desc>{}</desc>\n    </rtept>",
+            stop.lat,
+            stop.lon,
+            escape_markup(&stop.label()),
+            escape_markup(&comment),
+            escape_markup(&route.describe_stop(position))
+        )
+        .unwrap();
+    }
+    out.push_str("  </rte>\n");
+
+    if route.legs.iter().any(|l| l.geometry.is_some()) {
+        for (i, leg) in route.legs.iter().enumerate() {
+            writeln!(
+                out,
+                "  <trk>\n    <name>Leg {}</name>\n    <desc>{}</desc>\n    <number>{}</number>\n    <trkseg>",
+                i + 1,
+                escape_markup(&route.describe_leg(leg)),
+                i + 1
+            )
+            .unwrap();
+            for (lat, lon) in route.leg_geometry(leg) {
+                writeln!(out, "      <trkpt lat=\"{:.6}\" lon=\"{:.6}\"/>", lat, lon).unwrap();
+            }
+            out.push_str("    </trkseg>\n  </trk>\n");
+        }
+    }
+    out.push_str("</gpx>\n");
+    out
+}
+
+/// Writes a route to a `.gpx` file.
+pub fn write_gpx(route: &Route, name: &str, path: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::write(path, to_gpx(route, name))
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,54 @@
+// tests
+
+use super::*;
+use crate::roadgraph::{RoadGraph, ShortestPath};
+use crate::waypoint::lookup;
+
+fn sample_route() -> Route {
+    let stops = vec![lookup("Arches").unwrap(), lookup("Canyonlands").unwrap()];
+    let distances = &[(0, 1, 47000), (1, 0, 47000)];
+    let mut route = Route::new(stops, vec![0, 1, 0], distances);
+    route.add_durations(&[(0, 1, 2700), (1, 0, 2700)]);
+    route
+}
+
+#[test]
+fn test_route_points() {
+    let gpx = to_gpx(&sample_route(), "Moab & around");
+    assert!(gpx.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx version=\"1.1\""));
+    assert!(gpx.contains("<name>Moab &amp; around</name>"));
+    assert_eq!(gpx.matches("<wpt ").count(), 2);
+    assert_eq!(gpx.matches("<rtept ").count(), 3);
+    assert!(gpx.contains("<rtept lat=\"38.680000\" lon=\"-109.570000\">"));
+    assert!(gpx.contains(
+        "<cmt>Leg 1: Arches, Utah to Canyonlands, Utah: 47.0 km (29.2 mi), 0h 45m</cmt>"
+    ));
+    assert!(gpx.contains("<desc>Stop 2 of 2, a park in Utah."));
+    let home = gpx.rfind("<rtept ").unwrap();
+    assert!(gpx[home..].contains(
+        "<desc>Back home at Arches, Utah. Arrive after 94.0 km (58.4 mi), 1h 30m.</desc>"
+    ));
+    // no leg geometry, so no track
+    assert!(!gpx.contains("<trk>"));
+    assert!(gpx.ends_with("</gpx>\n"));
+}
+
+#[test]
+fn test_tracks_from_geometry() {
+    let mut graph = RoadGraph::new();
+    let arches = graph.add_node(38.68, -109.57);
+    let junction = graph.add_node(38.57, -109.62);
+    let canyonlands = graph.add_node(38.20, -109.93);
+    graph.add_road(arches, junction, 15000).unwrap();
+    graph.add_road(junction, canyonlands, 32000).unwrap();
+    let table = graph
+        .legs(&[arches, canyonlands], ShortestPath::AStar)
+        .unwrap();
+    let mut route = sample_route();
+    route.add_geometry(&graph, &table);
+
+    let gpx = to_gpx(&route, "Moab");
+    assert_eq!(gpx.matches("<trk>").count(), 2);
+    assert_eq!(gpx.matches("<trkpt ").count(), 6);
+    assert!(gpx.contains("<trkpt lat=\"38.570000\" lon=\"-109.620000\"/>"));
+}
@@ -0,0 +1,350 @@
+use crate::itinerary::{DateTime, Itinerary};
+use crate::route::Route;
+use crate::waypoint::Waypoint;
+use std::fmt::Write;
+use std::path::Path;
+
+/// A time zone of the lower 48, with United States daylight saving time:
+/// from 2:00 on the second Sunday in March to 2:00 on the first Sunday in
+/// November, as it has been since 2007.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeZone {
+    /// The IANA name, like `America/Denver`.
+    pub name: &'static str,
+    /// Minutes east of UTC outside daylight saving time.
+    pub offset: i32,
+    /// Whether the clocks go forward in summer.
+    pub daylight: bool,
+    /// Abbreviations for standard and daylight time.
+    pub abbreviations: (&'static str, &'static str),
+}
+
+pub const EASTERN: TimeZone = TimeZone {
+    name: "America/New_York",
+    offset: -300,
+    daylight: true,
+    abbreviations: ("EST", "EDT"),
+};
+pub const CENTRAL: TimeZone = TimeZone {
+    name: "America/Chicago",
+    offset: -360,
+    daylight: true,
+    abbreviations: ("CST", "CDT"),
+};
+pub const MOUNTAIN: TimeZone = TimeZone {
+    name: "America/Denver",
+    offset: -420,
+    daylight: true,
+    abbreviations: ("MST", "MDT"),
+};
+/// Mountain time without daylight saving, as Arizona keeps it.
+pub const ARIZONA: TimeZone = TimeZone {
+    name: "America/Phoenix",
+    offset: -420,
+    daylight: false,
+    abbreviations: ("MST", "MST"),
+};
+pub const PACIFIC: TimeZone = TimeZone {
+    name: "America/Los_Angeles",
+    offset: -480,
+    daylight: true,
+    abbreviations: ("PST", "PDT"),
+};
+
+/// Most of each state keeps this time.
+const STATE_ZONES: &[(&str, TimeZone)] = &[
+    ("Alabama", CENTRAL),
+    ("Arizona", ARIZONA),
+    ("Arkansas", CENTRAL),
+    ("California", PACIFIC),
+    ("Colorado", MOUNTAIN),
+    ("Connecticut", EASTERN),
+    ("Delaware", EASTERN),
+    ("Florida", EASTERN),
+    ("Georgia", EASTERN),
+    ("Idaho", MOUNTAIN),
+    ("Illinois", CENTRAL),
+    ("Indiana", EASTERN),
+    ("Iowa", CENTRAL),
+    ("Kansas", CENTRAL),
+    ("Kentucky", EASTERN),
+    ("Louisiana", CENTRAL),
+    ("Maine", EASTERN),
+    ("Maryland", EASTERN),
+    ("Massachusetts", EASTERN),
+    ("Michigan", EASTERN),
+    ("Minnesota", CENTRAL),
+    ("Mississippi", CENTRAL),
+    ("Missouri", CENTRAL),
+    ("Montana", MOUNTAIN),
+    ("Nebraska", CENTRAL),
+    ("Nevada", PACIFIC),
+    ("New Hampshire", EASTERN),
+    ("New Jersey", EASTERN),
+    ("New Mexico", MOUNTAIN),
+    ("New York", EASTERN),
+    ("North Carolina", EASTERN),
+    ("North Dakota", CENTRAL),
+    ("Ohio", EASTERN),
+    ("Oklahoma", CENTRAL),
+    ("Oregon", PACIFIC),
+    ("Pennsylvania", EASTERN),
+    ("Rhode Island", EASTERN),
+    ("South Carolina", EASTERN),
+    ("South Dakota", CENTRAL),
+    ("Tennessee", CENTRAL),
+    ("Texas", CENTRAL),
+    ("Utah", MOUNTAIN),
+    ("Vermont", EASTERN),
+    ("Virginia", EASTERN),
+    ("Washington", PACIFIC),
+    ("West Virginia", EASTERN),
+    ("Wisconsin", CENTRAL),
+    ("Wyoming", MOUNTAIN),
+];
+
+/// Gazetteer waypoints in the part of a split state that keeps the other
+/// time.
+const ZONE_EXCEPTIONS: &[(&str, TimeZone)] = &[
+    ("Badlands, South Dakota", MOUNTAIN),
+    ("Guadalupe Mountains, Texas", MOUNTAIN),
+    ("Indiana Dunes, Indiana", CENTRAL),
+    ("Mammoth Cave, Kentucky", CENTRAL),
+    ("Theodore Roosevelt, North Dakota", MOUNTAIN),
+    ("Wind Cave, South Dakota", MOUNTAIN),
+];
+
+/// Returns the time zone a waypoint keeps, or `None` for a state outside
+/// the lower 48.
+pub fn zone_of(stop: &Waypoint) -> Option<TimeZone> {
+    let label = stop.label();
+    ZONE_EXCEPTIONS
+        .iter()
+        .find(|(l, _)| *l == label)
+        .or_else(|| STATE_ZONES.iter().find(|(s, _)| *s == stop.state))
+        .map(|&(_, zone)| zone)
+}
+
+/// Day of the month of the `nth` Sunday of a month, or `None` if the
+/// month is too far off for `DateTime`.
+fn nth_sunday(year: i64, month: u32, nth: u32) -> Option<u32> {
+    let first = DateTime::new(year, month, 1, 0, 0)?;
+    // 1970-01-01 was a Thursday
+    let weekday = (first.seconds().div_euclid(86400) + 4).rem_euclid(7) as u32;
+    Some(1 + (7 - weekday) % 7 + 7 * (nth - 1))
+}
+
+impl TimeZone {
+    /// Returns whether daylight saving time is in force at a moment given
+    /// in UTC.
+    pub fn is_daylight(&self, utc: DateTime) -> bool {
+        if !self.daylight {
+            return false;
+        }
+        let standard = utc.plus_signed(self.offset as i64 * 60);
+        let (year, _, _) = standard.date();
+        // both changes happen at 2:00 on the clock then showing, which is
+        // 1:00 standard time in November; past the end of `DateTime` the
+        // clocks stay on standard time
+        let begins = nth_sunday(year, 3, 2).and_then(|day| DateTime::new(year, 3, day, 2, 0));
+        let ends = nth_sunday(year, 11, 1).and_then(|day| DateTime::new(year, 11, day, 1, 0));
+        match (begins, ends) {
+            (Some(begins), Some(ends)) => begins <= standard && standard < ends,
+            _ => false,
+        }
+    }
+
+    /// Returns the minutes east of UTC at a moment given in UTC.
+    pub fn offset_at(&self, utc: DateTime) -> i32 {
+        if self.is_daylight(utc) {
+            self.offset + 60
+        } else {
+            self.offset
+        }
+    }
+
+    /// Returns the local time at a moment given in UTC.
+    pub fn local(&self, utc: DateTime) -> DateTime {
+        utc.plus_signed(self.offset_at(utc) as i64 * 60)
+    }
+
+    /// Returns the moment in UTC the clocks here show `local`.  A time
+    /// skipped in spring is read as standard time, and a time repeated in
+    /// autumn as the first of the two.
+    pub fn utc(&self, local: DateTime) -> DateTime {
+        let standard = local.plus_signed(-self.offset as i64 * 60);
+        let daylight = standard.plus_signed(-3600);
+        if self.is_daylight(daylight) {
+            daylight
+        } else {
+            standard
+        }
+    }
+
+    /// Writes the VTIMEZONE component describing the zone.
+    fn vtimezone(&self) -> String {
+        let hhmm = |minutes: i32| {
+            let sign = if minutes < 0 { '-' } else { '+' };
+            format!("{}{:02}{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+        };
+        let (standard, daylight) = (hhmm(self.offset), hhmm(self.offset + 60));
+        let mut text = format!("BEGIN:VTIMEZONE\r\nTZID:{}\r\n", self.name);
+        if self.daylight {
+            write!(
+                text,
+                "BEGIN:DAYLIGHT\r\nTZOFFSETFROM:{s}\r\nTZOFFSETTO:{d}\r\nTZNAME:{dn}\r\n\
+                 DTSTART:19700308T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\n\
+                 END:DAYLIGHT\r\n\
+                 BEGIN:STANDARD\r\nTZOFFSETFROM:{d}\r\nTZOFFSETTO:{s}\r\nTZNAME:{sn}\r\n\
+                 DTSTART:19701101T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\n\
+                 END:STANDARD\r\n",
+                s = standard,
+                d = daylight,
+                sn = self.abbreviations.0,
+                dn = self.abbreviations.1,
+            )
+            .unwrap();
+        } else {
+            write!(
+                text,
+                "BEGIN:STANDARD\r\nTZOFFSETFROM:{s}\r\nTZOFFSETTO:{s}\r\nTZNAME:{sn}\r\n\
+                 DTSTART:19700101T000000\r\nEND:STANDARD\r\n",
+                s = standard,
+                sn = self.abbreviations.0,
+            )
+            .unwrap();
+        }
+        text.push_str("END:VTIMEZONE\r\n");
+        text
+    }
+}
+
+/// Formats a date and time the way iCalendar writes them.
+fn ics_time(t: DateTime) -> String {
+    let (year, month, day) = t.date();
+    let (hour, minute, second) = t.time();
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Escapes text for an iCalendar TEXT value.
+fn escape_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Ends a content line, folding it so no line is longer than 75 octets.
+fn push_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+/// Renders an itinerary of a route as an iCalendar file: an event for each
+/// drive and for each stay, with its location and a description.  The
+/// itinerary's times are read as clock time at home; each event is written
+/// in the time zone of where it happens, so a drive can start in one zone
+/// and end in another.  Legs whose times aren't known are left out.
+/// Returns an error naming a stop with no known time zone.
+pub fn to_ics(route: &Route, itinerary: &Itinerary, title: &str) -> Result<String, String> {
+    let zones = route
+        .stops
+        .iter()
+        .map(|s| zone_of(s).ok_or_else(|| format!("no time zone known for {}", s.label())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let home = zones[route.order[0]];
+    let start = home.utc(itinerary.start);
+    let at = |stop: usize, local: DateTime| {
+        let zone = zones[stop];
+        format!(
+            "TZID={}:{}",
+            zone.name,
+            ics_time(zone.local(home.utc(local)))
+        )
+    };
+
+    let mut events = Vec::new();
+    for (i, (leg, entry)) in route.legs.iter().zip(&itinerary.entries).enumerate() {
+        let (depart, arrive) = match (entry.depart, entry.arrive) {
+            (Some(depart), Some(arrive)) => (depart, arrive),
+            _ => continue,
+        };
+        events.push(vec![
+            format!("UID:drive-{}-{}@final-project", i + 1, start.seconds()),
+            format!("DTSTART;{}", at(leg.from, depart)),
+            format!("DTEND;{}", at(leg.to, arrive)),
+            format!(
+                "SUMMARY:{}",
+                escape_text(&format!("Drive to {}", route.stops[leg.to].name))
+            ),
+            format!("LOCATION:{}", escape_text(&route.stops[leg.from].label())),
+            format!("DESCRIPTION:{}", escape_text(&route.describe_leg(leg))),
+        ]);
+        if entry.stay > 0 {
+            let stop = &route.stops[leg.to];
+            let (lat, lon) = stop.coords();
+            events.push(vec![
+                format!("UID:stay-{}-{}@final-project", i + 1, start.seconds()),
+                format!("DTSTART;{}", at(leg.to, arrive)),
+                format!("DTEND;{}", at(leg.to, arrive.plus(entry.stay as u64))),
+                format!("SUMMARY:{}", escape_text(&stop.name)),
+                format!("LOCATION:{}", escape_text(&stop.label())),
+                format!("GEO:{:.6};{:.6}", lat, lon),
+                format!("DESCRIPTION:{}", escape_text(&route.describe_stop(i + 1))),
+            ]);
+        }
+    }
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//final-project//road trip planner//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape_text(title)));
+    let mut used: Vec<TimeZone> = Vec::new();
+    for &stop in &route.order {
+        if !used.contains(&zones[stop]) {
+            used.push(zones[stop]);
+        }
+    }
+    for zone in used {
+        ics.push_str(&zone.vtimezone());
+    }
+    for event in events {
+        push_line(&mut ics, "BEGIN:VEVENT");
+        // a fixed stamp keeps the file the same each time it is written
+        push_line(&mut ics, &format!("DTSTAMP:{}Z", ics_time(start)));
+        for line in event {
+            push_line(&mut ics, &line);
+        }
+        push_line(&mut ics, "END:VEVENT");
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    Ok(ics)
+}
+
+/// Writes the iCalendar file for an itinerary to a file.
+pub fn write_ics(
+    route: &Route,
+    itinerary: &Itinerary,
+    title: &str,
+    path: impl AsRef<Path>,
+) -> std::io::Result<()> {
+    let ics = to_ics(route, itinerary, title)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::write(path, ics)
+}
+
+#[cfg(test)]
+mod pub_tests;
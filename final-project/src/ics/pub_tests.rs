@@ -0,0 +1,89 @@
+// tests
+
+use super::*;
+use crate::waypoint::lookup;
+
+/// Zion to the Grand Canyon's North Rim and on to Petrified Forest: Utah
+/// keeps daylight saving time and Arizona doesn't.
+fn sample_route() -> Route {
+    let stops = vec![
+        lookup("Zion").unwrap(),
+        lookup("Grand Canyon").unwrap(),
+        lookup("Petrified Forest").unwrap(),
+    ];
+    let distances = &[(0, 1, 190000), (1, 2, 320000)];
+    let mut route = Route::new(stops, vec![0, 1, 2], distances);
+    route.add_durations(&[(0, 1, 3 * 3600), (1, 2, 4 * 3600)]);
+    route
+}
+
+fn at(text: &str) -> DateTime {
+    text.parse().unwrap()
+}
+
+#[test]
+fn test_daylight_saving() {
+    assert_eq!(nth_sunday(2024, 3, 2), Some(10));
+    assert_eq!(nth_sunday(2024, 11, 1), Some(3));
+    // the clocks go from 1:59 to 3:00 and later from 1:59 back to 1:00
+    assert_eq!(MOUNTAIN.utc(at("2024-03-10 01:59")), at("2024-03-10 08:59"));
+    assert_eq!(MOUNTAIN.utc(at("2024-03-10 03:00")), at("2024-03-10 09:00"));
+    assert_eq!(MOUNTAIN.utc(at("2024-11-03 01:30")), at("2024-11-03 07:30"));
+    assert_eq!(
+        MOUNTAIN.local(at("2024-11-03 08:30")),
+        at("2024-11-03 01:30")
+    );
+    assert_eq!(MOUNTAIN.offset_at(at("2024-07-01 12:00")), -360);
+    assert_eq!(ARIZONA.offset_at(at("2024-07-01 12:00")), -420);
+    // at the very end of time the clocks keep standard time
+    let last = at("2024-07-01 12:00").plus(u64::MAX);
+    assert_eq!(MOUNTAIN.offset_at(last), -420);
+    assert_eq!(
+        EASTERN.local(at("2024-01-15 17:00")),
+        at("2024-01-15 12:00")
+    );
+    for (stop, zone) in [
+        ("Big Bend", CENTRAL),
+        ("Guadalupe Mountains", MOUNTAIN),
+        ("Mammoth Cave", CENTRAL),
+        ("Louisville", EASTERN),
+        ("Saguaro", ARIZONA),
+    ] {
+        assert_eq!(zone_of(&lookup(stop).unwrap()), Some(zone), "{}", stop);
+    }
+}
+
+#[test]
+fn test_calendar() {
+    let route = sample_route();
+    let itinerary = Itinerary::new(&route, at("2024-06-01 08:00"), &[0, 5 * 3600]);
+    let ics = to_ics(&route, &itinerary, "Canyon country").unwrap();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(ics.lines().all(|l| l.len() <= 76));
+    assert_eq!(ics.matches("BEGIN:VTIMEZONE").count(), 2);
+    assert!(ics.contains("TZID:America/Phoenix\r\nBEGIN:STANDARD\r\nTZOFFSETFROM:-0700"));
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 3);
+    assert!(ics.contains("DTSTAMP:20240601T140000Z"));
+
+    // 8:00 in Utah is 7:00 in Arizona, so the first drive ends at 10:00
+    assert!(ics.contains("DTSTART;TZID=America/Denver:20240601T080000\r\n"));
+    assert!(ics.contains("DTEND;TZID=America/Phoenix:20240601T100000\r\n"));
+    assert!(ics.contains("SUMMARY:Drive to Grand Canyon\r\n"));
+    assert!(ics.contains("LOCATION:Zion\\, Utah\r\n"));
+    assert!(ics.contains(
+        "SUMMARY:Grand Canyon\r\nLOCATION:Grand Canyon\\, Arizona\r\nGEO:36.060000;-112.140000\r\n"
+    ));
+    assert!(ics.contains("DTSTART;TZID=America/Phoenix:20240601T150000\r\nDTEND;TZID=America/Phoenix:20240601T190000\r\n"));
+}
+
+#[test]
+fn test_calendar_folds_long_lines() {
+    let mut ics = String::new();
+    push_line(&mut ics, &format!("DESCRIPTION:{}", "é".repeat(60)));
+    let lines: Vec<_> = ics.split("\r\n").collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|l| l.len() <= 75));
+    assert!(lines[1].starts_with(' '));
+    assert_eq!(lines[2], "");
+}
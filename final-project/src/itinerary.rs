@@ -0,0 +1,361 @@
+use crate::report::{format_distance, format_duration};
+use crate::route::Route;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A date and time of day, to the second, on whatever clock the trip
+/// keeps; no time zone is attached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    /// Seconds since 1970-01-01 00:00.
+    seconds: i64,
+}
+
+impl DateTime {
+    /// Creates a date and time, or returns `None` if there is no such day
+    /// or time of day, or it is too far off to count in seconds.
+    pub fn new(year: i64, month: u32, day: u32, hour: u32, minute: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || hour > 23 || minute > 59 {
+            return None;
+        }
+        let days = days_from_civil(year, month, day)?;
+        if civil_from_days(days) != (year, month, day) {
+            return None;
+        }
+        let seconds = days
+            .checked_mul(86400)?
+            .checked_add((hour * 3600 + minute * 60) as i64)?;
+        Some(Self { seconds })
+    }
+    /// Returns the moment `seconds` after this one, or the last moment
+    /// there is.
+    pub fn plus(self, seconds: u64) -> Self {
+        let seconds = i64::try_from(seconds).unwrap_or(i64::MAX);
+        self.plus_signed(seconds)
+    }
+    /// Returns the moment `seconds` after this one, or before it if
+    /// negative, stopping at the first or last moment there is.
+    pub fn plus_signed(self, seconds: i64) -> Self {
+        Self {
+            seconds: self.seconds.saturating_add(seconds),
+        }
+    }
+    /// Returns `(year, month, day)`.
+    pub fn date(self) -> (i64, u32, u32) {
+        civil_from_days(self.seconds.div_euclid(86400))
+    }
+    /// Returns `(hour, minute, second)`.
+    pub fn time(self) -> (u32, u32, u32) {
+        let s = self.seconds.rem_euclid(86400) as u32;
+        (s / 3600, s / 60 % 60, s % 60)
+    }
+    /// Returns the seconds since 1970-01-01 00:00.
+    pub fn seconds(self) -> i64 {
+        self.seconds
+    }
+}
+
+/// Writes `2024-06-01 08:00`, with the seconds only when there are some.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.date();
+        let (hour, minute, second) = self.time();
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            year, month, day, hour, minute
+        )?;
+        if second > 0 {
+            write!(f, ":{:02}", second)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads `2024-06-01 08:00` or `2024-06-01T08:00`.
+impl FromStr for DateTime {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || {
+            format!(
+                "expected a date and time like 2024-06-01 08:00, not {:?}",
+                s
+            )
+        };
+        let (date, time) = s.split_once([' ', 'T']).ok_or_else(bad)?;
+        let date: Vec<_> = date.split('-').collect();
+        let time: Vec<_> = time.split(':').collect();
+        if date.len() != 3 || time.len() != 2 {
+            return Err(bad());
+        }
+        let number = |text: &str| text.parse::<u32>().map_err(|_| bad());
+        let year = date[0].parse::<i64>().map_err(|_| bad())?;
+        Self::new(
+            year,
+            number(date[1])?,
+            number(date[2])?,
+            number(time[0])?,
+            number(time[1])?,
+        )
+        .ok_or_else(bad)
+    }
+}
+
+/// Days from 1970-01-01 to a date of the proleptic Gregorian calendar, or
+/// `None` if there are more than an `i64` holds.
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    let year = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    // days since March 1st, which puts leap days at the end of the year
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era.checked_mul(146097)?
+        .checked_add(day_of_era)?
+        .checked_sub(719468)
+}
+
+/// The date `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let march_based = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * march_based + 2) / 5 + 1) as u32;
+    let month = if march_based < 10 {
+        march_based + 3
+    } else {
+        march_based - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// One leg of an itinerary, with the running totals and times at its end.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Label of the stop the leg leaves.
+    pub from: String,
+    /// Label of the stop it reaches.
+    pub to: String,
+    /// Length in meters.
+    pub distance: u32,
+    /// Driving time in seconds, if known.
+    pub duration: Option<u32>,
+    /// Meters driven so far, this leg included.
+    pub total_distance: u64,
+    /// Seconds driven so far, if every leg's time is known.
+    pub total_duration: Option<u64>,
+    /// When the leg starts, if every earlier leg's time is known.
+    pub depart: Option<DateTime>,
+    /// When it ends, likewise.
+    pub arrive: Option<DateTime>,
+    /// Seconds spent at `to` before the next leg.  Nothing is spent at the
+    /// end of the trip.
+    pub stay: u32,
+}
+
+/// A route laid out in time: per-leg distances and driving times, running
+/// totals, and when each leg leaves and arrives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Itinerary {
+    /// When the trip leaves home.
+    pub start: DateTime,
+    pub entries: Vec<Entry>,
+}
+
+/// Column headings shared by the table formats.
+const COLUMNS: [&str; 10] = [
+    "Leg",
+    "From",
+    "To",
+    "Distance",
+    "Drive",
+    "Total distance",
+    "Total drive",
+    "Depart",
+    "Arrive",
+    "Stay",
+];
+
+impl Itinerary {
+    /// Lays a route out in time from `start`.  `stays` gives the seconds
+    /// spent at each of the route's stops, by index into `Route::stops`;
+    /// stops past its end take no time, and so does leaving home.
+    pub fn new(route: &Route, start: DateTime, stays: &[u32]) -> Self {
+        let stay_at = |stop: usize| stays.get(stop).cloned().unwrap_or(0);
+        let mut entries = Vec::new();
+        let mut total_distance = 0;
+        let mut total_duration = Some(0);
+        let mut clock = Some(start);
+        for (i, leg) in route.legs.iter().enumerate() {
+            total_distance += leg.distance as u64;
+            total_duration = total_duration.zip(leg.duration).map(|(t, d)| t + d as u64);
+            let depart = clock;
+            let arrive = depart.zip(leg.duration).map(|(t, d)| t.plus(d as u64));
+            let stay = if i + 1 < route.legs.len() {
+                stay_at(leg.to)
+            } else {
+                0
+            };
+            clock = arrive.map(|t| t.plus(stay as u64));
+            entries.push(Entry {
+                from: route.stops[leg.from].label(),
+                to: route.stops[leg.to].label(),
+                distance: leg.distance,
+                duration: leg.duration,
+                total_distance,
+                total_duration,
+                depart,
+                arrive,
+                stay,
+            });
+        }
+        Self { start, entries }
+    }
+
+    /// Lays a route out to a schedule worked out already, such as a
+    /// `TripPlan`'s arrivals: `arrivals` holds the seconds after `start` at
+    /// which each position of the route is reached.  Each leg leaves as late
+    /// as it can and still arrive on time, so waiting, charging and nights
+    /// on the road all count as stays.
+    pub fn scheduled(route: &Route, start: DateTime, arrivals: &[u32]) -> Self {
+        let mut itinerary = Self::new(route, start, &[]);
+        let mut ready = start;
+        for (i, entry) in itinerary.entries.iter_mut().enumerate() {
+            let arrive = start.plus(arrivals[i + 1] as u64);
+            let depart = match entry.duration {
+                Some(d) => ready.max(arrive.plus_signed(-(d as i64))),
+                None => ready,
+            };
+            entry.depart = Some(depart);
+            entry.arrive = Some(arrive);
+            ready = arrive;
+        }
+        let departures: Vec<_> = itinerary.entries.iter().map(|e| e.depart).collect();
+        for (i, entry) in itinerary.entries.iter_mut().enumerate() {
+            entry.stay = match (entry.arrive, departures.get(i + 1)) {
+                (Some(arrive), Some(Some(depart))) => (depart.seconds() - arrive.seconds()) as u32,
+                _ => 0,
+            };
+        }
+        itinerary
+    }
+
+    /// Returns when the trip ends, if every leg's time is known.
+    pub fn end(&self) -> Option<DateTime> {
+        match self.entries.last() {
+            Some(entry) => entry.arrive,
+            None => Some(self.start),
+        }
+    }
+
+    /// Formatted cells of each row, for the text and Markdown tables.
+    fn cells(&self) -> Vec<Vec<String>> {
+        let or_dash = |cell: Option<String>| cell.unwrap_or_else(|| "-".to_string());
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                vec![
+                    (i + 1).to_string(),
+                    e.from.clone(),
+                    e.to.clone(),
+                    format_distance(e.distance as u64),
+                    or_dash(e.duration.map(|d| format_duration(d as u64))),
+                    format_distance(e.total_distance),
+                    or_dash(e.total_duration.map(format_duration)),
+                    or_dash(e.depart.map(|t| t.to_string())),
+                    or_dash(e.arrive.map(|t| t.to_string())),
+                    or_dash(
+                        Some(e.stay)
+                            .filter(|&s| s > 0)
+                            .map(|s| format_duration(s as u64)),
+                    ),
+                ]
+            })
+            .collect()
+    }
+
+    /// Writes the itinerary as a plain-text table with aligned columns.
+    pub fn to_text(&self) -> String {
+        let mut rows = vec![COLUMNS.iter().map(|c| c.to_string()).collect::<Vec<_>>()];
+        rows.extend(self.cells());
+        let widths: Vec<_> = (0..COLUMNS.len())
+            .map(|c| rows.iter().map(|r| r[c].chars().count()).max().unwrap_or(0))
+            .collect();
+        let line = |row: &[String]| {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &w)| format!("{:<w$}", cell, w = w))
+                .collect();
+            cells.join("  ").trim_end().to_string() + "\n"
+        };
+        let mut text = line(&rows[0]);
+        let rule: Vec<_> = widths.iter().map(|&w| "-".repeat(w)).collect();
+        text.push_str(&line(&rule));
+        for row in &rows[1..] {
+            text.push_str(&line(row));
+        }
+        text
+    }
+
+    /// Writes the itinerary as a Markdown table.
+    pub fn to_markdown(&self) -> String {
+        let mut text = format!("| {} |\n", COLUMNS.join(" | "));
+        text.push_str(&format!("|{}\n", "---|".repeat(COLUMNS.len())));
+        for row in self.cells() {
+            let row: Vec<_> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+            text.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        text
+    }
+
+    /// Writes the itinerary as CSV with a header row.  Distances are in
+    /// meters and times in seconds, so the numbers can be summed; unknown
+    /// times are left empty.
+    pub fn to_csv(&self) -> String {
+        let mut text = String::from(
+            "leg,from,to,meters,seconds,total_meters,total_seconds,depart,arrive,stay_seconds\n",
+        );
+        let or_empty = |cell: Option<String>| cell.unwrap_or_default();
+        for (i, e) in self.entries.iter().enumerate() {
+            let cells = [
+                (i + 1).to_string(),
+                csv_quote(&e.from),
+                csv_quote(&e.to),
+                e.distance.to_string(),
+                or_empty(e.duration.map(|d| d.to_string())),
+                e.total_distance.to_string(),
+                or_empty(e.total_duration.map(|d| d.to_string())),
+                or_empty(e.depart.map(|t| t.to_string())),
+                or_empty(e.arrive.map(|t| t.to_string())),
+                e.stay.to_string(),
+            ];
+            text.push_str(&cells.join(","));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Quotes a CSV cell if it holds a comma, quote or line break.
+fn csv_quote(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,161 @@
+// tests
+
+use super::*;
+use crate::waypoint::lookup;
+
+fn sample_route() -> Route {
+    let stops: Vec<_> = ["Joshua Tree", "Death Valley", "White Sands"]
+        .iter()
+        .map(|n| lookup(n).unwrap())
+        .collect();
+    let distances = &[(0, 1, 418564), (1, 2, 1354266), (2, 0, 1143520)];
+    let mut route = Route::new(stops, vec![0, 1, 2, 0], distances);
+    route.add_durations(&[(0, 1, 15000), (1, 2, 46800), (2, 0, 39600)]);
+    route
+}
+
+#[test]
+fn test_date_time() {
+    let t: DateTime = "2024-02-28 22:30".parse().unwrap();
+    assert_eq!(t, DateTime::new(2024, 2, 28, 22, 30).unwrap());
+    assert_eq!(t.plus(86400).to_string(), "2024-02-29 22:30");
+    assert_eq!(t.plus(2 * 86400 + 5).to_string(), "2024-03-01 22:30:05");
+    assert_eq!("1970-01-01T00:00".parse::<DateTime>().unwrap().seconds(), 0);
+    assert_eq!(
+        DateTime::new(2000, 3, 1, 0, 0).unwrap().seconds(),
+        951868800
+    );
+    assert_eq!(DateTime::new(2023, 2, 29, 0, 0), None);
+    assert!("2024-06-01".parse::<DateTime>().is_err());
+    assert!("2024-06-01 24:00".parse::<DateTime>().is_err());
+    // years too far off to count in seconds
+    assert_eq!(DateTime::new(i64::MAX, 6, 1, 0, 0), None);
+    assert_eq!(DateTime::new(i64::MIN, 1, 1, 0, 0), None);
+    assert_eq!(DateTime::new(1_000_000_000_000_000, 6, 1, 0, 0), None);
+    assert!("9223372036854775807-06-01 08:00"
+        .parse::<DateTime>()
+        .is_err());
+    let last = DateTime::new(200_000_000_000, 12, 31, 23, 59).unwrap();
+    assert_eq!(last.plus(u64::MAX).seconds(), i64::MAX);
+}
+
+#[test]
+fn test_itinerary() {
+    let start = "2024-06-01 08:00".parse().unwrap();
+    // an afternoon at Death Valley and a night at White Sands
+    let itinerary = Itinerary::new(&sample_route(), start, &[7200, 4 * 3600, 14 * 3600]);
+    let times: Vec<_> = itinerary
+        .entries
+        .iter()
+        .map(|e| {
+            (
+                e.depart.unwrap().to_string(),
+                e.arrive.unwrap().to_string(),
+                e.stay,
+            )
+        })
+        .collect();
+    assert_eq!(
+        times,
+        vec![
+            (
+                "2024-06-01 08:00".to_string(),
+                "2024-06-01 12:10".to_string(),
+                14400
+            ),
+            (
+                "2024-06-01 16:10".to_string(),
+                "2024-06-02 05:10".to_string(),
+                50400
+            ),
+            (
+                "2024-06-02 19:10".to_string(),
+                "2024-06-03 06:10".to_string(),
+                0
+            ),
+        ]
+    );
+    let last = itinerary.entries.last().unwrap();
+    assert_eq!(
+        (last.total_distance, last.total_duration),
+        (2916350, Some(101400))
+    );
+    assert_eq!(itinerary.end(), last.arrive);
+
+    let text = itinerary.to_text();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("Leg  From"));
+    assert!(lines[2].contains("418.6 km (260.1 mi)   4h 10m"));
+    assert!(lines[2].ends_with("2024-06-01 12:10  4h 00m"));
+
+    let markdown = itinerary.to_markdown();
+    assert_eq!(markdown.lines().count(), 5);
+    assert!(markdown.contains("| 3 | White Sands, New Mexico | Joshua Tree, California |"));
+
+    let csv = itinerary.to_csv();
+    assert_eq!(
+        csv.lines().nth(1),
+        Some("1,\"Joshua Tree, California\",\"Death Valley, California\",418564,15000,418564,15000,2024-06-01 08:00,2024-06-01 12:10,14400")
+    );
+}
+
+#[test]
+fn test_itinerary_without_durations() {
+    let mut route = sample_route();
+    route.legs[1].duration = None;
+    let start = "2024-06-01 08:00".parse().unwrap();
+    let itinerary = Itinerary::new(&route, start, &[]);
+    let arrivals: Vec<_> = itinerary.entries.iter().map(|e| e.arrive).collect();
+    assert_eq!(arrivals[0], DateTime::new(2024, 6, 1, 12, 10));
+    assert_eq!(&arrivals[1..], &[None, None]);
+    assert_eq!(itinerary.entries[2].total_duration, None);
+    assert_eq!(itinerary.end(), None);
+    assert!(itinerary
+        .to_csv()
+        .lines()
+        .nth(2)
+        .unwrap()
+        .contains(",1354266,,"));
+}
+
+#[test]
+fn test_scheduled_itinerary() {
+    // the night after the first leg is spent at Death Valley, and the
+    // second day's drive starts at 8:00 again
+    let start = "2024-06-01 08:00".parse().unwrap();
+    let day = 86400;
+    let arrivals = [0, 15000, day + 46800, day + 46800 + 39600];
+    let itinerary = Itinerary::scheduled(&sample_route(), start, &arrivals);
+    let times: Vec<_> = itinerary
+        .entries
+        .iter()
+        .map(|e| {
+            (
+                e.depart.unwrap().to_string(),
+                e.arrive.unwrap().to_string(),
+                e.stay,
+            )
+        })
+        .collect();
+    assert_eq!(
+        times,
+        vec![
+            (
+                "2024-06-01 08:00".to_string(),
+                "2024-06-01 12:10".to_string(),
+                71400
+            ),
+            (
+                "2024-06-02 08:00".to_string(),
+                "2024-06-02 21:00".to_string(),
+                0
+            ),
+            (
+                "2024-06-02 21:00".to_string(),
+                "2024-06-03 08:00".to_string(),
+                0
+            ),
+        ]
+    );
+}
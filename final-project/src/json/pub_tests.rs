@@ -0,0 +1,73 @@
+// tests
+
+use super::*;
+
+#[test]
+fn test_parse() {
+    let doc = Json::parse(
+        r#" {"name": "Zion \"NP\"", "stops": [1, -2.5e3, true, null], "nested": {"aé": "🏞"}} "#,
+    )
+    .unwrap();
+    assert_eq!(doc.get("name"), Some(&Json::str("Zion \"NP\"")));
+    assert_eq!(
+        doc.get("stops"),
+        Some(&Json::Array(vec![
+            Json::Number(1.0),
+            Json::Number(-2500.0),
+            Json::Bool(true),
+            Json::Null
+        ]))
+    );
+    assert_eq!(
+        doc.get("nested").and_then(|n| n.get("aé")),
+        Some(&Json::str("🏞"))
+    );
+    // what we write, we can read back
+    assert_eq!(Json::parse(&doc.pretty()), Ok(doc.clone()));
+    assert_eq!(Json::parse(&doc.to_string()), Ok(doc));
+}
+
+#[test]
+fn test_parse_errors() {
+    let err = |text: &str| Json::parse(text).unwrap_err().to_string();
+    assert_eq!(
+        err("{\n  \"a\": 1,\n  \"b\" 2\n}"),
+        "line 3, column 7: expected ':'"
+    );
+    assert_eq!(err("[1, 2"), "line 1, column 6: expected ',' or ']'");
+    assert_eq!(
+        err("{\"a\": 1, \"a\": 2}"),
+        "line 1, column 10: duplicate key \"a\""
+    );
+    assert_eq!(
+        err("[1] 2"),
+        "line 1, column 5: unexpected text after the document"
+    );
+    assert_eq!(err("[01]"), "line 1, column 2: bad number \"01\"");
+    for bad in ["-", "1.", ".5", "1e", "1e+", "-01", "1.5e3.2", "2-1"] {
+        assert!(Json::parse(bad).is_err(), "{}", bad);
+    }
+    for good in ["0", "-0", "10", "0.5", "-1.25e-3", "1E+2"] {
+        assert!(Json::parse(good).is_ok(), "{}", good);
+    }
+    // a high surrogate needs a low one after it
+    assert_eq!(
+        Json::parse("\"\\ud83d\\ude00\""),
+        Ok(Json::str("\u{1f600}"))
+    );
+    assert!(Json::parse("\"\\ud83d\\u0041\"").is_err());
+    assert!(Json::parse("\"\\ud83d\"").is_err());
+    assert_eq!(err("nope"), "line 1, column 1: expected a value");
+    assert_eq!(err(""), "line 1, column 1: unexpected end of document");
+}
+
+#[test]
+fn test_nesting_limit() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+    assert_eq!(
+        Json::parse(&nested(MAX_DEPTH + 1)).unwrap_err().to_string(),
+        "line 1, column 129: nested more than 128 deep"
+    );
+    assert!(Json::parse(&"{\"a\": [".repeat(100_000)).is_err());
+}
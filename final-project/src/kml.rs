@@ -0,0 +1,85 @@
+use crate::report::escape_markup;
+use crate::route::Route;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Renders a route as a KML 2.2 document for Google Earth: a folder of stop
+/// placemarks and a folder of leg line strings.  Per-leg distance and
+/// duration are kept as `ExtendedData` so they show in the balloon.
+pub fn to_kml(route: &Route, name: &str) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    writeln!(out, "  <name>{}</name>", escape_markup(name)).unwrap();
+    out.push_str(
+        "  <Style id=\"leg\">\n    <LineStyle>\n      <color>ff2b39c0</color>\n      <width>4</width>\n    </LineStyle>\n  </Style>\n",
+    );
+
+    out.push_str("  <Folder>\n    <name>Stops</name>\n");
+    for position in 0..route.stop_count() {
+        let stop = route.stop_at(position);
+        writeln!(
+            out,
+            "    <Placemark>\n      <name>{}. {}</name>\n      <description>{}</description>\n      <Point>\n        <coordinates>{}</coordinates>\n      </Point>\n    </Placemark>",
+            position + 1,
+            escape_markup(&stop.label()),
+            escape_markup(&route.describe_stop(position)),
+            coordinates(&[stop.coords()])
+        )
+        .unwrap();
+    }
+    out.push_str("  </Folder>\n");
+
+    out.push_str("  <Folder>\n    <name>Legs</name>\n");
+    for (i, leg) in route.legs.iter().enumerate() {
+        writeln!(
+            out,
+            "    <Placemark>\n      <name>Leg {}</name>\n      <description>{}</description>\n      <styleUrl>#leg</styleUrl>\n      <ExtendedData>",
+            i + 1,
+            escape_markup(&route.describe_leg(leg))
+        )
+        .unwrap();
+        let mut data = vec![
+            ("from", route.stops[leg.from].label()),
+            ("to", route.stops[leg.to].label()),
+            ("distance_m", leg.distance.to_string()),
+        ];
+        if let Some(d) = leg.duration {
+            data.push(("duration_s", d.to_string()));
+        }
+        for (key, value) in data {
+            writeln!(
+                out,
+                "        <Data name=\"{}\"><value>{}</value></Data>",
+                key,
+                escape_markup(&value)
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "      </ExtendedData>\n      <LineString>\n        <tessellate>1</tessellate>\n        <coordinates>{}</coordinates>\n      </LineString>\n    </Placemark>",
+            coordinates(&route.leg_geometry(leg))
+        )
+        .unwrap();
+    }
+    out.push_str("  </Folder>\n</Document>\n</kml>\n");
+    out
+}
+
+/// Writes a route to a `.kml` file.
+pub fn write_kml(route: &Route, name: &str, path: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::write(path, to_kml(route, name))
+}
+
+/// KML coordinates are space-separated `lon,lat,alt` tuples.
+fn coordinates(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(lat, lon)| format!("{:.6},{:.6},0", lon, lat))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,20 @@
+// tests
+
+use super::*;
+use crate::waypoint::lookup;
+
+#[test]
+fn test_placemarks() {
+    let stops = vec![lookup("Zion").unwrap(), lookup("Bryce Canyon").unwrap()];
+    let route = Route::new(stops, vec![0, 1], &[(0, 1, 116000)]);
+    let kml = to_kml(&route, "Utah <day trip>");
+    assert!(kml.contains("<name>Utah &lt;day trip&gt;</name>"));
+    assert_eq!(kml.matches("<Point>").count(), 2);
+    assert_eq!(kml.matches("<LineString>").count(), 1);
+    assert!(kml.contains("<name>2. Bryce Canyon, Utah</name>"));
+    assert!(
+        kml.contains("<coordinates>-113.050000,37.300000,0 -112.180000,37.570000,0</coordinates>")
+    );
+    assert!(kml.contains("<Data name=\"distance_m\"><value>116000</value></Data>"));
+    assert!(!kml.contains("duration_s"));
+}
@@ -60,7 +60,7 @@ impl<Val: ValueType> Domain<Val> {
         }
     }
     /// Creates an iterator over the domain's values.
-    pub fn iter(&self) -> std::slice::Iter<Val> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Val> {
         self.values.iter()
     }
     /// Returns the number of values in the domain.
@@ -72,7 +72,7 @@ impl<Val: ValueType> Domain<Val> {
     pub fn narrow(&mut self, dom: &Domain<Val>) -> Vec<Val> {
         let mut result = Vec::new();
         self.values.retain(|v| {
-            if dom.contains(&v) {
+            if dom.contains(v) {
                 true
             } else {
                 result.push(*v);
@@ -130,7 +130,7 @@ impl<Val: ValueType> Valuation<Val> {
     /// that when not using `Var` indexing, the caller relies on
     /// knowledge of the order in which variables were added to the
     /// constraint problem.
-    pub fn iter(&self) -> std::slice::Iter<Val> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Val> {
         self.values.iter()
     }
 
@@ -174,7 +174,7 @@ pub struct PartialValuation<Val: ValueType> {
 
 impl<Val: ValueType> std::cmp::PartialOrd for PartialValuation<Val> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(std::cmp::Ordering::Equal)
+        Some(self.cmp(other))
     }
 }
 impl<Val: ValueType> std::cmp::Ord for PartialValuation<Val> {
@@ -309,7 +309,29 @@ impl<Val: ValueType> std::ops::Index<usize> for PartialValuation<Val> {
     }
 }
 
+pub mod bench;
+pub mod cli;
+pub mod cost;
 pub mod counting_sat;
 pub mod csp;
+pub mod distance;
 pub mod fd;
+pub mod geojson;
+pub mod gpx;
+pub mod ics;
+pub mod itinerary;
+pub mod json;
+pub mod kml;
+pub mod matrix;
 pub mod opt;
+pub mod picker;
+pub mod report;
+pub mod rng;
+pub mod roadgraph;
+pub mod route;
+pub mod server;
+pub mod spec;
+pub mod states;
+pub mod tour;
+pub mod tsplib;
+pub mod waypoint;
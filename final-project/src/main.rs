@@ -1,118 +1,7 @@
-use final_project::opt::roadtrip;
-use std::time::Instant;
+use final_project::cli;
 
 fn main() {
-    let start = Instant::now();
-
-    // 0: Joshua Tree, CA
-    // 1: Mount Rainier, WA
-    // 2: Big Bend, TX
-    // 3: Acadia, Maine
-    // 4: Yellowstone, WY
-    // 5: Badlands, South Dakota
-    // 6: Death Valley, CA
-    // 7: Indiana Dunes, Indiana,
-    // 8: Mammoth Cave, Kentucky
-    // 9: White Sands, New Mexico
-
-    let vcount: usize = 10;
-    let edges: &[(usize, usize, u32)] = &[
-        (0, 1, 1968322),
-        (0, 2, 1628566),
-        (0, 3, 5112437),
-        (0, 4, 1505777),
-        (0, 5, 2018763),
-        (0, 6, 418564),
-        (0, 7, 3178396),
-        (0, 8, 3193609),
-        (0, 9, 1143520),
-        (1, 0, 1968322),
-        (1, 2, 3142137),
-        (1, 3, 5419755),
-        (1, 4, 1309183),
-        (1, 5, 2039012),
-        (1, 6, 1656253),
-        (1, 7, 3480732),
-        (1, 8, 3870772),
-        (1, 9, 2705997),
-        (2, 0, 1628566),
-        (2, 1, 3142137),
-        (2, 3, 4137151),
-        (2, 4, 2194450),
-        (2, 5, 1784503),
-        (2, 6, 1839604),
-        (2, 7, 2307545),
-        (2, 8, 2071110),
-        (2, 9, 582719),
-        (3, 0, 5112437),
-        (3, 1, 5419755),
-        (3, 2, 4137151),
-        (3, 4, 4166428),
-        (3, 5, 3361923),
-        (3, 6, 5022145),
-        (3, 7, 1943926),
-        (3, 8, 2145457),
-        (3, 9, 4180868),
-        (4, 0, 1505777),
-        (4, 1, 1309183),
-        (4, 2, 2194450),
-        (4, 3, 4166428),
-        (4, 5, 784687),
-        (4, 6, 1304514),
-        (4, 7, 2226406),
-        (4, 8, 2520499),
-        (4, 9, 1776705),
-        (5, 0, 2018763),
-        (5, 1, 2039012),
-        (5, 2, 1784503),
-        (5, 3, 3361923),
-        (5, 4, 784687),
-        (5, 6, 1933093),
-        (5, 7, 1425501),
-        (5, 8, 1677934),
-        (5, 9, 1479752),
-        (6, 0, 418564),
-        (6, 1, 1656253),
-        (6, 2, 1839604),
-        (6, 3, 5022145),
-        (6, 4, 1304514),
-        (6, 5, 1933093),
-        (6, 7, 3086599),
-        (6, 8, 3225667),
-        (6, 9, 1354266),
-        (7, 0, 3178396),
-        (7, 1, 3480732),
-        (7, 2, 2307545),
-        (7, 3, 1943926),
-        (7, 4, 2226406),
-        (7, 5, 1425501),
-        (7, 6, 3086599),
-        (7, 8, 593395),
-        (7, 9, 2373768),
-        (8, 0, 3193609),
-        (8, 1, 3870772),
-        (8, 2, 2071110),
-        (8, 3, 2145457),
-        (8, 4, 2520499),
-        (8, 5, 1677934),
-        (8, 6, 3225667),
-        (8, 7, 593395),
-        (8, 9, 2287913),
-        (9, 0, 1143520),
-        (9, 1, 2705997),
-        (9, 2, 582719),
-        (9, 3, 4180868),
-        (9, 4, 1776705),
-        (9, 5, 1479752),
-        (9, 6, 1354266),
-        (9, 7, 2373768),
-        (9, 8, 2287913),
-    ];
-
-    println!("Calculating the route...");
-    let (order, total_cost) = roadtrip(vcount, edges);
-    println!("Visit the locations in this order: {:?}", order);
-    println!("Total distance is {:?} meters", total_cost);
-    let duration = start.elapsed();
-    println!("Time elapsed to calculate the roadtrip is: {:?}", duration);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = cli::run(&args, &mut std::io::stdout());
+    std::process::exit(code);
 }
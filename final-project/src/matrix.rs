@@ -0,0 +1,461 @@
+use crate::distance::{Diagnostic, DistanceMatrix};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Which leg cost a trip should minimize.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    /// Meters driven.
+    Distance,
+    /// Seconds behind the wheel.
+    Duration,
+    /// Cents spent on fuel, tolls and lodging, from a trip spec's cost
+    /// model.  Travel matrices have no such column.
+    Cost,
+    /// Seconds from leaving home to the end of the trip, with stays at
+    /// stops, waiting and nights included.  Planned from driving times.
+    Elapsed,
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::Distance => write!(f, "distance"),
+            Objective::Duration => write!(f, "duration"),
+            Objective::Cost => write!(f, "cost"),
+            Objective::Elapsed => write!(f, "elapsed"),
+        }
+    }
+}
+
+impl FromStr for Objective {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "distance" => Ok(Objective::Distance),
+            "duration" | "time" => Ok(Objective::Duration),
+            "cost" | "money" => Ok(Objective::Cost),
+            "elapsed" => Ok(Objective::Elapsed),
+            _ => Err(format!(
+                "unknown objective {:?} (expected distance, duration, cost or elapsed)",
+                s
+            )),
+        }
+    }
+}
+
+/// Something went wrong loading a travel matrix or selecting from it.
+#[derive(Debug, PartialEq)]
+pub enum MatrixError {
+    /// A line of the file could not be understood.
+    Parse { line: usize, message: String },
+    /// No waypoint in the matrix goes by this name.
+    UnknownWaypoint(String),
+    /// More than one waypoint in the matrix goes by this name.
+    AmbiguousWaypoint(String),
+    /// The matrix has no entry for this leg.
+    Missing {
+        from: String,
+        to: String,
+        objective: Objective,
+    },
+    /// The file could not be read.
+    Io(String),
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatrixError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            MatrixError::UnknownWaypoint(name) => write!(f, "no waypoint named {:?}", name),
+            MatrixError::AmbiguousWaypoint(name) => {
+                write!(f, "{:?} matches more than one waypoint", name)
+            }
+            MatrixError::Missing {
+                from,
+                to,
+                objective,
+            } => write!(f, "no {} from {} to {}", objective, from, to),
+            MatrixError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+/// A tab-separated file whose header names its columns, as both the
+/// matrix and overlay files are.  Blank lines are skipped.
+struct Tsv<'a> {
+    columns: Vec<&'a str>,
+    required: Vec<usize>,
+    lines: Vec<(usize, &'a str)>,
+}
+
+impl<'a> Tsv<'a> {
+    /// Splits off the header, which must name every `required` column.
+    fn parse(text: &'a str, required: &[&str]) -> Result<Self, MatrixError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim_end_matches('\r')))
+            .filter(|(_, l)| !l.trim().is_empty());
+        let (_, header) = lines.next().ok_or(MatrixError::Parse {
+            line: 1,
+            message: "empty file".to_string(),
+        })?;
+        let columns: Vec<_> = header.split('\t').map(str::trim).collect();
+        let positions: Option<Vec<_>> = required
+            .iter()
+            .map(|name| columns.iter().position(|c| c == name))
+            .collect();
+        let required = positions.ok_or_else(|| {
+            let (last, rest) = required.split_last().unwrap();
+            MatrixError::Parse {
+                line: 1,
+                message: format!("header needs {} and {} columns", rest.join(", "), last),
+            }
+        })?;
+        Ok(Self {
+            columns,
+            required,
+            lines: lines.collect(),
+        })
+    }
+    /// Where the header puts the `i`-th required column.
+    fn at(&self, i: usize) -> usize {
+        self.required[i]
+    }
+    /// Where the header puts an optional column, if it has it.
+    fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| *c == name)
+    }
+    /// Each row's line number and fields, or an error for a row without a
+    /// field for every column.
+    fn rows(&self) -> impl Iterator<Item = Result<(usize, Vec<&'a str>), MatrixError>> + '_ {
+        self.lines.iter().map(move |&(line, row)| {
+            let fields: Vec<_> = row.split('\t').map(str::trim).collect();
+            if fields.len() == self.columns.len() {
+                Ok((line, fields))
+            } else {
+                Err(MatrixError::Parse {
+                    line,
+                    message: format!(
+                        "expected {} fields but found {}",
+                        self.columns.len(),
+                        fields.len()
+                    ),
+                })
+            }
+        })
+    }
+}
+
+/// What an overlay does to a leg.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LegChange {
+    /// The leg can't be driven.
+    Closed,
+    /// The leg's distance and driving time are multiplied by this, as for
+    /// a detour or roadworks.
+    Scaled(f64),
+}
+
+/// A leg changed by an overlay, both ways unless `one_way`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Closure {
+    pub from: String,
+    pub to: String,
+    pub change: LegChange,
+    pub one_way: bool,
+}
+
+/// Changes to a travel matrix that only hold for a while, like closed
+/// roads, kept out of the matrix file so it can be reused as is.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overlay {
+    pub legs: Vec<Closure>,
+}
+
+impl Overlay {
+    /// Parses a tab-separated overlay with a header naming the columns
+    /// `waypoint1`, `waypoint2`, `change` and optionally `one_way`.  A
+    /// change is `closed` or a factor like `1.5`; `one_way` is `yes` or
+    /// `no`, and `no` when missing.
+    pub fn from_tsv(text: &str) -> Result<Self, MatrixError> {
+        let tsv = Tsv::parse(text, &["waypoint1", "waypoint2", "change"])?;
+        let (from_col, to_col, change_col) = (tsv.at(0), tsv.at(1), tsv.at(2));
+        let one_way_col = tsv.column("one_way");
+
+        let mut legs = Vec::new();
+        for row in tsv.rows() {
+            let (line, fields) = row?;
+            let err = |message: String| MatrixError::Parse { line, message };
+            let change = match fields[change_col] {
+                "closed" => LegChange::Closed,
+                factor => match factor.parse::<f64>() {
+                    Ok(f) if f > 0.0 && f.is_finite() => LegChange::Scaled(f),
+                    _ => {
+                        return Err(err(format!(
+                            "bad change {:?} (expected closed or a factor above 0)",
+                            factor
+                        )))
+                    }
+                },
+            };
+            let one_way = match one_way_col.map(|col| fields[col]) {
+                None | Some("no") | Some("") => false,
+                Some("yes") => true,
+                Some(other) => return Err(err(format!("bad one_way {:?}", other))),
+            };
+            legs.push(Closure {
+                from: fields[from_col].to_string(),
+                to: fields[to_col].to_string(),
+                change,
+                one_way,
+            });
+        }
+        Ok(Self { legs })
+    }
+    /// Reads an overlay file from disk; see `from_tsv`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, MatrixError> {
+        let text = std::fs::read_to_string(path).map_err(|e| MatrixError::Io(e.to_string()))?;
+        Self::from_tsv(&text)
+    }
+}
+
+/// Matrices bundled with the planner, by name.  `ten-parks` holds the ten
+/// national parks from the original hard-coded `main.rs`.
+pub const BUILTIN: &[(&str, &str)] = &[("ten-parks", include_str!("../ten-parks.tsv"))];
+
+/// Distances and driving times between named waypoints, as written by
+/// `trip.py` to its waypoints file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TravelMatrix {
+    names: Vec<String>,
+    distances: HashMap<(usize, usize), u32>, // meters
+    durations: HashMap<(usize, usize), u32>, // seconds
+    closed: HashSet<(usize, usize)>,
+}
+
+impl TravelMatrix {
+    /// Parses a tab-separated matrix with a header naming the columns
+    /// `waypoint1`, `waypoint2`, `distance_m` and optionally `duration_s`,
+    /// in any order.  Each row gives a leg; the reverse leg gets the same
+    /// costs unless it has a row of its own, since `trip.py` writes each
+    /// pair only once.
+    pub fn from_tsv(text: &str) -> Result<Self, MatrixError> {
+        let tsv = Tsv::parse(text, &["waypoint1", "waypoint2", "distance_m"])?;
+        let (from_col, to_col, dist_col) = (tsv.at(0), tsv.at(1), tsv.at(2));
+        let dur_col = tsv.column("duration_s");
+
+        let mut matrix = Self {
+            names: Vec::new(),
+            distances: HashMap::new(),
+            durations: HashMap::new(),
+            closed: HashSet::new(),
+        };
+        let mut explicit = Vec::new();
+        for row in tsv.rows() {
+            let (line, fields) = row?;
+            let err = |message: String| MatrixError::Parse { line, message };
+            let from = matrix.intern(fields[from_col]);
+            let to = matrix.intern(fields[to_col]);
+            let cost = |col: usize| {
+                // trip.py writes costs through pandas, so allow "1234.0"
+                fields[col]
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|c| *c >= 0.0 && *c <= u32::MAX as f64)
+                    .map(|c| c.round() as u32)
+                    .ok_or_else(|| err(format!("bad cost {:?}", fields[col])))
+            };
+            matrix.distances.insert((from, to), cost(dist_col)?);
+            if let Some(col) = dur_col {
+                matrix.durations.insert((from, to), cost(col)?);
+            }
+            explicit.push((from, to));
+        }
+        for (from, to) in explicit {
+            for map in [&mut matrix.distances, &mut matrix.durations].iter_mut() {
+                if let Some(&c) = map.get(&(from, to)) {
+                    map.entry((to, from)).or_insert(c);
+                }
+            }
+        }
+        Ok(matrix)
+    }
+    /// Makes a matrix of distances from `roadtrip`-style edges between the
+    /// named waypoints.
+    pub fn from_edges(names: Vec<String>, edges: &[(usize, usize, u32)]) -> Self {
+        Self {
+            names,
+            distances: edges.iter().map(|&(a, b, c)| ((a, b), c)).collect(),
+            durations: HashMap::new(),
+            closed: HashSet::new(),
+        }
+    }
+    /// Adds driving times in seconds from `roadtrip`-style edges, as
+    /// numbered for `from_edges`.
+    pub fn add_durations(&mut self, edges: &[(usize, usize, u32)]) {
+        self.durations
+            .extend(edges.iter().map(|&(a, b, c)| ((a, b), c)));
+    }
+    /// Reads a matrix file from disk; see `from_tsv`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, MatrixError> {
+        let text = std::fs::read_to_string(path).map_err(|e| MatrixError::Io(e.to_string()))?;
+        Self::from_tsv(&text)
+    }
+
+    /// Returns one of the `BUILTIN` matrices.
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, text)| Self::from_tsv(text).expect("bundled matrix parses"))
+    }
+
+    fn intern(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        }
+    }
+
+    /// Returns the number of waypoints.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    /// Returns the waypoint names in the order they first appear in the file.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+    /// Returns whether the matrix has driving times.
+    pub fn has_durations(&self) -> bool {
+        !self.durations.is_empty()
+    }
+    /// Returns a copy with an overlay's changes made: closed legs have no
+    /// cost, but unlike missing ones are left out of `edges` and
+    /// `diagnose`, and scaled legs cost more or less.
+    pub fn with_overlay(&self, overlay: &Overlay) -> Result<Self, MatrixError> {
+        let mut matrix = self.clone();
+        for closure in overlay.legs.iter() {
+            let from = self.index_of(&closure.from)?;
+            let to = self.index_of(&closure.to)?;
+            let mut legs = vec![(from, to)];
+            if !closure.one_way {
+                legs.push((to, from));
+            }
+            for leg in legs {
+                match closure.change {
+                    LegChange::Closed => {
+                        matrix.closed.insert(leg);
+                    }
+                    LegChange::Scaled(factor) => {
+                        for map in [&mut matrix.distances, &mut matrix.durations].iter_mut() {
+                            if let Some(c) = map.get_mut(&leg) {
+                                *c = (*c as f64 * factor).round().min(u32::MAX as f64) as u32;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(matrix)
+    }
+    /// Returns whether an overlay closed the leg between two waypoint
+    /// indices.
+    pub fn is_closed(&self, from: usize, to: usize) -> bool {
+        self.closed.contains(&(from, to))
+    }
+    /// Returns the cost of a leg between two waypoint indices, or `None`
+    /// if it is missing or closed.
+    pub fn cost(&self, from: usize, to: usize, objective: Objective) -> Option<u32> {
+        if self.is_closed(from, to) {
+            return None;
+        }
+        match objective {
+            Objective::Distance => self.distances.get(&(from, to)).cloned(),
+            Objective::Duration => self.durations.get(&(from, to)).cloned(),
+            Objective::Cost | Objective::Elapsed => None,
+        }
+    }
+    /// Finds a waypoint by its full name or, failing that, by the part
+    /// before the first comma (`"Zion"` for `"Zion, Utah"`) if only one
+    /// waypoint has it.  Case is ignored.
+    pub fn index_of(&self, name: &str) -> Result<usize, MatrixError> {
+        let key = name.trim().to_lowercase();
+        if let Some(i) = self.names.iter().position(|n| n.to_lowercase() == key) {
+            return Ok(i);
+        }
+        let short: Vec<_> = (0..self.len())
+            .filter(|&i| {
+                self.names[i]
+                    .split(',')
+                    .next()
+                    .unwrap()
+                    .trim()
+                    .to_lowercase()
+                    == key
+            })
+            .collect();
+        match short.len() {
+            0 => Err(MatrixError::UnknownWaypoint(name.to_string())),
+            1 => Ok(short[0]),
+            _ => Err(MatrixError::AmbiguousWaypoint(name.to_string())),
+        }
+    }
+    /// Looks up several waypoints by name; see `index_of`.
+    pub fn select<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<usize>, MatrixError> {
+        names.iter().map(|n| self.index_of(n.as_ref())).collect()
+    }
+    /// Returns `roadtrip`-style edges over the selected waypoints, numbered
+    /// by their position in `selection` so that the first one is home.
+    /// Closed legs are left out, so no tour can use them.
+    pub fn edges(
+        &self,
+        selection: &[usize],
+        objective: Objective,
+    ) -> Result<Vec<(usize, usize, u32)>, MatrixError> {
+        let mut edges = Vec::new();
+        for (i, &from) in selection.iter().enumerate() {
+            for (j, &to) in selection.iter().enumerate() {
+                if i == j || self.is_closed(from, to) {
+                    continue;
+                }
+                let cost = self
+                    .cost(from, to, objective)
+                    .ok_or_else(|| MatrixError::Missing {
+                        from: self.names[from].clone(),
+                        to: self.names[to].clone(),
+                        objective,
+                    })?;
+                edges.push((i, j, cost));
+            }
+        }
+        Ok(edges)
+    }
+    /// Returns the legs over the selected waypoints as a `DistanceMatrix`
+    /// named and numbered like `selection`.  Unlike `edges`, a missing leg
+    /// is left for `DistanceMatrix::diagnose` to report.
+    pub fn dense(&self, selection: &[usize], objective: Objective) -> DistanceMatrix {
+        let names = selection.iter().map(|&i| self.names[i].clone()).collect();
+        DistanceMatrix::from_names(names, |i, j| {
+            self.cost(selection[i], selection[j], objective)
+        })
+    }
+    /// Checks the legs between every two waypoints with
+    /// `DistanceMatrix::diagnose`.  Closed legs aren't reported missing.
+    pub fn diagnose(&self, objective: Objective) -> Vec<Diagnostic<u32>> {
+        let all: Vec<usize> = (0..self.len()).collect();
+        self.dense(&all, objective)
+            .diagnose_except(|from, to| self.is_closed(from, to))
+    }
+}
+
+#[cfg(test)]
+mod pub_tests;
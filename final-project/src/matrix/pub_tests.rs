@@ -0,0 +1,173 @@
+// tests
+
+use super::*;
+
+const SAMPLE: &str = "waypoint1\twaypoint2\tdistance_m\tduration_s
+Zion, Utah\tArches, Utah\t510000.0\t17000
+Zion, Utah\tBryce Canyon, Utah\t135000\t6000
+Arches, Utah\tBryce Canyon, Utah\t400000\t15000
+Bryce Canyon, Utah\tArches, Utah\t410000\t15000
+";
+
+#[test]
+fn test_parse_and_select() {
+    let matrix = TravelMatrix::from_tsv(SAMPLE).unwrap();
+    assert_eq!(matrix.len(), 3);
+    assert!(matrix.has_durations());
+    let zion = matrix.index_of("zion").unwrap();
+    let arches = matrix.index_of("Arches, Utah").unwrap();
+    // the reverse leg is filled in
+    assert_eq!(matrix.cost(arches, zion, Objective::Distance), Some(510000));
+    assert_eq!(matrix.cost(zion, arches, Objective::Duration), Some(17000));
+    assert_eq!(
+        matrix.index_of("Yosemite"),
+        Err(MatrixError::UnknownWaypoint("Yosemite".to_string()))
+    );
+
+    let selection = matrix.select(&["Bryce Canyon", "Zion"]).unwrap();
+    let edges = matrix.edges(&selection, Objective::Distance).unwrap();
+    assert_eq!(edges, vec![(0, 1, 135000), (1, 0, 135000)]);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        TravelMatrix::from_tsv("from\tto\tmeters\n"),
+        Err(MatrixError::Parse {
+            line: 1,
+            message: "header needs waypoint1, waypoint2 and distance_m columns".to_string()
+        })
+    );
+    let err = TravelMatrix::from_tsv("waypoint1\twaypoint2\tdistance_m\nA\tB\tfar\n").unwrap_err();
+    assert_eq!(
+        err,
+        MatrixError::Parse {
+            line: 2,
+            message: "bad cost \"far\"".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_diagnose() {
+    let text = format!("{}Zion, Utah\tCapitol Reef, Utah\t0\t0\n", SAMPLE);
+    let matrix = TravelMatrix::from_tsv(&text).unwrap();
+    for &objective in [Objective::Distance, Objective::Duration].iter() {
+        let found = matrix.diagnose(objective);
+        let errors: Vec<_> = found.iter().filter(|d| d.is_error()).collect();
+        // Capitol Reef is missing legs to Arches and Bryce Canyon both ways,
+        // and has zero legs to and from Zion
+        assert_eq!(errors.len(), 4 + 2, "{}", objective);
+        assert!(found.contains(&Diagnostic::Zero {
+            from: "Capitol Reef, Utah".to_string(),
+            to: "Zion, Utah".to_string(),
+        }));
+    }
+    let found = matrix.diagnose(Objective::Distance);
+    assert!(found.contains(&Diagnostic::Asymmetric {
+        from: "Arches, Utah".to_string(),
+        to: "Bryce Canyon, Utah".to_string(),
+        there: 400000,
+        back: 410000,
+    }));
+    assert_eq!(found.len(), 6 + 1);
+}
+
+#[test]
+fn test_dense() {
+    let matrix = TravelMatrix::from_tsv(SAMPLE).unwrap();
+    let dense = matrix.dense(&[2, 0], Objective::Duration);
+    assert_eq!(dense.names(), &["Bryce Canyon, Utah", "Zion, Utah"]);
+    assert_eq!(dense.get(0, 1), Some(6000));
+    assert_eq!(dense.index_of("zion, utah"), Some(1));
+    assert!(dense.diagnose().is_empty());
+}
+
+#[test]
+fn test_overlay() {
+    let overlay = Overlay::from_tsv(
+        "waypoint1\twaypoint2\tchange\tone_way
+Zion\tArches\tclosed\tyes
+Bryce Canyon\tArches\t1.5\tno
+",
+    )
+    .unwrap();
+    assert_eq!(
+        overlay.legs[0],
+        Closure {
+            from: "Zion".to_string(),
+            to: "Arches".to_string(),
+            change: LegChange::Closed,
+            one_way: true
+        }
+    );
+    let base = TravelMatrix::from_tsv(SAMPLE).unwrap();
+    let matrix = base.with_overlay(&overlay).unwrap();
+    let (zion, arches, bryce) = (0, 1, 2);
+    assert!(matrix.is_closed(zion, arches) && !matrix.is_closed(arches, zion));
+    assert_eq!(matrix.cost(zion, arches, Objective::Distance), None);
+    assert_eq!(matrix.cost(arches, zion, Objective::Distance), Some(510000));
+    assert_eq!(
+        matrix.cost(bryce, arches, Objective::Distance),
+        Some(615000)
+    );
+    assert_eq!(matrix.cost(arches, bryce, Objective::Duration), Some(22500));
+    // the base matrix is left alone
+    assert_eq!(base.cost(zion, arches, Objective::Distance), Some(510000));
+
+    // the closed leg is left out rather than reported missing
+    let edges = matrix.edges(&[zion, arches], Objective::Distance).unwrap();
+    assert_eq!(edges, vec![(1, 0, 510000)]);
+    assert!(matrix
+        .diagnose(Objective::Distance)
+        .iter()
+        .all(|d| !d.is_error()));
+
+    // closing a leg one way still checks the other way
+    let names = vec!["A".to_string(), "B".to_string()];
+    let one_way = Overlay {
+        legs: vec![Closure {
+            from: "A".to_string(),
+            to: "B".to_string(),
+            change: LegChange::Closed,
+            one_way: true,
+        }],
+    };
+    let matrix = TravelMatrix::from_edges(names, &[(0, 1, 5)])
+        .with_overlay(&one_way)
+        .unwrap();
+    assert_eq!(
+        matrix.diagnose(Objective::Distance),
+        vec![Diagnostic::Missing {
+            from: "B".to_string(),
+            to: "A".to_string(),
+        }]
+    );
+
+    let unknown = Overlay::from_tsv("waypoint1\twaypoint2\tchange\nZion\tYosemite\tclosed\n");
+    assert_eq!(
+        base.with_overlay(&unknown.unwrap()),
+        Err(MatrixError::UnknownWaypoint("Yosemite".to_string()))
+    );
+    assert_eq!(
+        Overlay::from_tsv("waypoint1\twaypoint2\tchange\nZion\tArches\t0\n"),
+        Err(MatrixError::Parse {
+            line: 2,
+            message: "bad change \"0\" (expected closed or a factor above 0)".to_string()
+        })
+    );
+    assert_eq!(
+        Overlay::from_tsv("waypoint1\tchange\n"),
+        Err(MatrixError::Parse {
+            line: 1,
+            message: "header needs waypoint1, waypoint2 and change columns".to_string()
+        })
+    );
+    assert_eq!(
+        Overlay::from_tsv("waypoint1\twaypoint2\tchange\nZion\tArches\n"),
+        Err(MatrixError::Parse {
+            line: 2,
+            message: "expected 3 fields but found 2".to_string()
+        })
+    );
+}
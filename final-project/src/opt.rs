@@ -1,75 +1,1680 @@
 use super::{Domain, ValueType, Var};
+use crate::cost::{Cost, CostOverflow};
 use crate::counting_sat::{Lit, CSAT};
-use crate::csp::CSP;
+use crate::csp::{Limits, Observer, SearchStatus, CSP};
+use crate::distance::DistanceMatrix;
 use crate::fd::{Reify, FD};
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::fmt;
 use std::ops::Index;
 use std::time::{Duration, Instant};
 
 impl ValueType for usize {}
 
-pub fn roadtrip(vcount: usize, edges: &[(usize, usize, u32)]) -> (Vec<usize>, u32) {
-    let mut csp = CSP::new();
+/// A closed tour through every vertex, as found by one of the solvers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tour<C = u32> {
+    /// Vertices in visiting order, ending back where the tour started.
+    pub order: Vec<usize>,
+    pub cost: C,
+}
+
+/// The result of a bounded tour search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TourOutcome<C = u32> {
+    pub tour: Option<Tour<C>>,
+    pub status: SearchStatus,
+    /// How many search nodes (or, for heuristics, candidate tours) were examined.
+    pub nodes: usize,
+}
+
+/// The cheapest tour through every vertex, starting from vertex 0.  The
+/// costs can be `u32`, `u64` or `OrderedFloat`.  Panics if there is no
+/// tour, or if every tour's cost overflows the cost type.
+pub fn roadtrip<C: Cost>(vcount: usize, edges: &[(usize, usize, C)]) -> (Vec<usize>, C) {
+    let tour = roadtrip_checked(vcount, edges, &Limits::default())
+        .unwrap()
+        .tour
+        .unwrap();
+    (tour.order, tour.cost)
+}
 
-    // collect all the edges and costs into a hash map
-    let edges_map: HashMap<(usize, usize), u32> = edges
+/// The `k` cheapest tours, cheapest first, each differing from every
+/// cheaper one in at least `min_different` legs (either way round).  With
+/// `min_different` at 0 a tour and its reverse both count.  Tours whose
+/// cost overflows are left out.
+pub fn roadtrip_k_best<C: Cost>(
+    vcount: usize,
+    edges: &[(usize, usize, C)],
+    k: usize,
+    min_different: usize,
+) -> Vec<(Vec<usize>, C)> {
+    if vcount == 0 {
+        return Vec::new();
+    }
+    let costs = DistanceMatrix::from_edges_within(vcount, edges);
+    let (csp, choices) = tour_model(vcount);
+    let stops = |get: &dyn Fn(Var) -> Option<usize>| -> Vec<usize> {
+        choices.iter().map_while(|&var| get(var)).collect()
+    };
+    let overflowed = Cell::new(false);
+    let ranked = csp.bnb_k_best(
+        |v| {
+            tour_bound(
+                &costs,
+                vcount,
+                &stops(&|var| v.get_value(var)),
+                None,
+                &overflowed,
+            )
+        },
+        Bound::Broken,
+        k,
+        |v, earlier| {
+            let before = undirected(&tour_legs(&stops(&|var| Some(earlier[var])), vcount));
+            let partial = stops(&|var| v.get_value(var));
+            let driven = undirected(&tour_legs(&partial, vcount));
+            let new = driven.iter().filter(|leg| !before.contains(leg)).count();
+            // legs still to come might all be new
+            let to_come = if partial.len() == vcount {
+                0
+            } else {
+                vcount - driven.len()
+            };
+            new + to_come >= min_different
+        },
+        &Limits::default(),
+    );
+    ranked
+        .solutions
         .iter()
-        .map(|(from, to, cost)| ((*from, *to), *cost))
-        .collect();
+        .filter_map(|soln| {
+            let mut order = stops(&|var| Some(soln[var]));
+            let cost = tour_bound(&costs, vcount, &order, None, &overflowed).cost()?;
+            order.push(order[0]);
+            Some((order, cost))
+        })
+        .collect()
+}
 
-    // create variables for each time choice for vertices
-    let choices: Vec<_> = (0..vcount)
-        .map(|i| {
-            if i == 0 {
-                csp.add_variable(Domain::new(vec![0]))
+/// How far each leg of the `roadtrip` tour, and each of the other `legs`,
+/// can change in cost before the best tour changes.  Empty if there is no
+/// tour; tours whose cost overflows count as missing.
+pub fn roadtrip_sensitivity<C: Cost>(
+    vcount: usize,
+    edges: &[(usize, usize, C)],
+    legs: &[(usize, usize)],
+) -> Vec<LegSensitivity<C>> {
+    let costs = DistanceMatrix::from_edges_within(vcount, edges);
+    let limits = Limits::default();
+    let best = match tour_search(vcount, &costs, None, &limits).0.tour {
+        Some(tour) => tour,
+        None => return Vec::new(),
+    };
+    let driven: Vec<_> = best.order.windows(2).map(|w| (w[0], w[1])).collect();
+    let mut analysed = Vec::new();
+    for leg in undirected(&driven) {
+        if !analysed.contains(&leg) {
+            analysed.push(leg);
+        }
+    }
+    let on_plan = analysed.len();
+    for leg in undirected(legs) {
+        if !analysed.contains(&leg) {
+            analysed.push(leg);
+        }
+    }
+
+    // with three stops or more a tour drives a leg once at most, and with
+    // two the one tour there is drives it both ways
+    analysed
+        .iter()
+        .enumerate()
+        .map(|(i, &(a, b))| {
+            let in_plan = i < on_plan;
+            let other = if in_plan {
+                let mut without = costs.clone();
+                without.set(a, b, None);
+                without.set(b, a, None);
+                tour_search(vcount, &without, None, &limits).0.tour
             } else {
-                csp.add_variable(Domain::new((0..vcount).collect()))
+                tour_search(vcount, &costs, Some((a, b)), &limits).0.tour
+            };
+            let margin = other.and_then(|tour| tour.cost.checked_sub(best.cost));
+            let cheapest = [(a, b), (b, a)]
+                .iter()
+                .filter_map(|&(from, to)| costs.get(from, to))
+                .min()
+                .unwrap_or(C::ZERO);
+            LegSensitivity {
+                leg: (a, b),
+                in_plan,
+                increase: margin.filter(|_| in_plan),
+                decrease: margin.filter(|&m| !in_plan && m <= cheapest),
             }
         })
+        .collect()
+}
+
+/// Like `roadtrip`, but stops once the search runs into one of the given
+/// limits.  Legs missing from `edges` can't be driven, so if no tour exists
+/// the outcome has no tour and an `Infeasible` status instead of panicking.
+/// A tour whose cost overflows counts as missing too; `roadtrip_checked`
+/// tells the two apart.  Edges naming a vertex past `vcount` are left out.
+pub fn roadtrip_limited<C: Cost>(
+    vcount: usize,
+    edges: &[(usize, usize, C)],
+    limits: &Limits,
+) -> TourOutcome<C> {
+    let matrix = DistanceMatrix::from_edges_within(vcount, edges);
+    tour_search(vcount, &matrix, None, limits).0
+}
+
+/// Like `roadtrip_limited`, but fails if the search ran out of tours
+/// because the cheapest ones overflow the cost type.  A tour that is found
+/// is still the cheapest: one that overflows costs more than any that
+/// doesn't.
+pub fn roadtrip_checked<C: Cost>(
+    vcount: usize,
+    edges: &[(usize, usize, C)],
+    limits: &Limits,
+) -> Result<TourOutcome<C>, CostOverflow> {
+    let matrix = DistanceMatrix::from_edges_within(vcount, edges);
+    let (outcome, overflowed) = tour_search(vcount, &matrix, None, limits);
+    checked(outcome.status, overflowed, outcome)
+}
+
+/// Like `roadtrip_checked`, for a tour through every vertex of a matrix
+/// that is already built.
+pub fn roadtrip_matrix<C: Cost>(
+    matrix: &DistanceMatrix<C>,
+    limits: &Limits,
+) -> Result<TourOutcome<C>, CostOverflow> {
+    let (outcome, overflowed) = tour_search(matrix.len(), matrix, None, limits);
+    checked(outcome.status, overflowed, outcome)
+}
+
+/// Fails if a search ran out of solutions only because the rest overflow,
+/// and otherwise passes on what it `found`.
+fn checked<T>(status: SearchStatus, overflowed: bool, found: T) -> Result<T, CostOverflow> {
+    if overflowed && status == SearchStatus::Infeasible {
+        Err(CostOverflow)
+    } else {
+        Ok(found)
+    }
+}
+
+/// What a partial tour costs while searching.  Missing legs and overflow
+/// make it `Broken`, which orders after every real cost, so that it can
+/// be the limit without any tour costing the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Bound<C> {
+    Cost(C),
+    Broken,
+}
+
+impl<C> Bound<C> {
+    fn cost(self) -> Option<C> {
+        match self {
+            Bound::Cost(cost) => Some(cost),
+            Bound::Broken => None,
+        }
+    }
+}
+
+/// Passes the real costs of a search over `Bound`s on to an observer.
+struct Costs<'a>(&'a mut dyn Observer<u32>);
+
+impl Observer<Bound<u32>> for Costs<'_> {
+    fn improved(&mut self, cost: &Bound<u32>) {
+        if let Bound::Cost(cost) = cost {
+            self.0.improved(cost);
+        }
+    }
+    fn should_stop(&mut self, nodes: usize) -> bool {
+        self.0.should_stop(nodes)
+    }
+}
+
+/// The search model behind `roadtrip_limited`: one variable per position
+/// in the tour, with home pinned first.
+fn tour_model(vcount: usize) -> (CSP<usize, FD>, Vec<Var>) {
+    let mut csp = CSP::new();
+    let choices: Vec<_> = (0..vcount)
+        .map(|p| {
+            let values = if p == 0 {
+                vec![0]
+            } else {
+                (0..vcount).collect()
+            };
+            csp.add_variable(Domain::new(values))
+        })
         .collect();
+    csp.add_constraint(FD::AllDiff(choices.clone()));
+    (csp, choices)
+}
 
-    // make the first and last variable be the same so that we create a cycle
-    let mut cycle = choices.clone();
-    cycle.push(choices[0]);
+/// The legs of a (partial) tour through `vcount` vertices, with the drive
+/// home once every vertex is visited.
+fn tour_legs(stops: &[usize], vcount: usize) -> Vec<(usize, usize)> {
+    let mut legs: Vec<_> = stops.windows(2).map(|w| (w[0], w[1])).collect();
+    if stops.len() == vcount && vcount > 1 {
+        legs.push((stops[vcount - 1], stops[0]));
+    }
+    legs
+}
 
-    // we need to visit all vertices, so all variables must be different
-    csp.add_constraint(FD::AllDiff(choices));
+/// Legs with their ends in order, so that either way round is the same.
+fn undirected(legs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    legs.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect()
+}
 
-    // generate solution using branch and bound
-    let soln = csp
-        .bnb(
-            |v| {
-                cycle
-                    .windows(2)
-                    .map(|cs| {
-                        let from = cs[0];
-                        let to = cs[1];
-                        match (v.get_value(from), v.get_value(to)) {
-                            (Some(from), Some(to)) => edges_map[&(from, to)],
-                            _ => (0 as u32),
+/// What a (partial) tour costs, noting in `overflowed` when it is broken
+/// because its cost overflows.  With a `leg`, a tour that can no longer
+/// drive it is broken too.
+fn tour_bound<C: Cost>(
+    costs: &DistanceMatrix<C>,
+    vcount: usize,
+    stops: &[usize],
+    leg: Option<(usize, usize)>,
+    overflowed: &Cell<bool>,
+) -> Bound<C> {
+    if leg.is_some_and(|leg| avoids(stops, stops.len() == vcount, true, leg)) {
+        return Bound::Broken;
+    }
+    let mut total = C::ZERO;
+    for (from, to) in tour_legs(stops, vcount) {
+        let c = match costs.get(from, to) {
+            Some(c) => c,
+            None => return Bound::Broken,
+        };
+        total = match total.checked_add(c) {
+            Some(t) => t,
+            None => {
+                overflowed.set(true);
+                return Bound::Broken;
+            }
+        };
+    }
+    Bound::Cost(total)
+}
+
+/// Whether a (partial) trip can no longer drive the leg between `a` and
+/// `b`, either way round: one of them already has a stop on both sides,
+/// or at the end of an open trip, on its one side.
+fn avoids(stops: &[usize], complete: bool, closed: bool, (a, b): (usize, usize)) -> bool {
+    let mut legs: Vec<_> = stops.windows(2).map(|w| (w[0], w[1])).collect();
+    if complete && closed && stops.len() > 1 {
+        legs.push((stops[stops.len() - 1], stops[0]));
+    }
+    if legs.contains(&(a, b)) || legs.contains(&(b, a)) {
+        return false;
+    }
+    let hemmed_in = |v: usize| {
+        stops
+            .iter()
+            .position(|&s| s == v)
+            .is_some_and(|i| (i > 0 || !closed) && i + 1 < stops.len())
+    };
+    complete || hemmed_in(a) || hemmed_in(b)
+}
+
+/// The branch and bound behind `roadtrip_limited`, through the first
+/// `vcount` vertices of `costs`, with whether any partial tour overflowed.
+/// With a `leg`, only tours that drive it, either way round, count.
+fn tour_search<C: Cost>(
+    vcount: usize,
+    costs: &DistanceMatrix<C>,
+    leg: Option<(usize, usize)>,
+    limits: &Limits,
+) -> (TourOutcome<C>, bool) {
+    if vcount == 0 {
+        // there is no home to start from
+        let outcome = TourOutcome {
+            tour: None,
+            status: SearchStatus::Infeasible,
+            nodes: 0,
+        };
+        return (outcome, false);
+    }
+    let (csp, choices) = tour_model(vcount);
+    let overflowed = Cell::new(false);
+    let outcome = csp.bnb_limited(
+        |v| {
+            let stops: Vec<usize> = choices.iter().map_while(|&var| v.get_value(var)).collect();
+            tour_bound(costs, vcount, &stops, leg, &overflowed)
+        },
+        Bound::Broken,
+        limits,
+    );
+
+    let tour = outcome.solution.and_then(|soln| {
+        let mut order: Vec<usize> = choices.iter().map(|&var| *soln.index(var)).collect();
+        let total = tour_bound(costs, vcount, &order, leg, &overflowed).cost()?;
+        order.push(order[0]);
+        Some(Tour { order, cost: total })
+    });
+    let outcome = TourOutcome {
+        tour,
+        status: outcome.status,
+        nodes: outcome.nodes,
+    };
+    (outcome, overflowed.get())
+}
+
+/// Seconds in a day; with a daily limit, each day's driving starts at the
+/// same time of day as the trip did.
+pub const DAY: u32 = 24 * 60 * 60;
+
+/// Daily driving rules for a trip that takes several days.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DailyLimit {
+    /// Most seconds behind the wheel, or spent at stops, in one day.  Legs
+    /// aren't split, so the day ends at the stop before a leg that would go
+    /// over.
+    pub max_driving: u32,
+    /// Most days the trip may take, if that's limited too.
+    pub max_days: Option<u32>,
+}
+
+/// When a stop is reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Arrival {
+    /// Day of the trip, counting from 0.
+    pub day: u32,
+    /// Seconds since the trip started, including nights and waiting.
+    pub time: u32,
+}
+
+/// Leg weights between vertices.
+type Legs = DistanceMatrix<u32>;
+
+/// An electric vehicle's range and where it can charge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vehicle {
+    /// Meters the vehicle can drive on a full battery.
+    pub range: u32,
+    /// Charging locations and the seconds a full charge takes at each.  A
+    /// location is either one of the trip's vertices or an extra vertex,
+    /// numbered from `vcount` up, that is only driven to for charging.
+    pub chargers: Vec<(usize, u32)>,
+}
+
+/// A charging stop the planner put on the way between two stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Charge {
+    /// The leg it is on: the one leaving `order[leg]` in the plan.
+    pub leg: usize,
+    pub vertex: usize,
+    pub seconds: u32,
+}
+
+/// What a trip costs in money.  Amounts are in cents.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CostModel {
+    /// Miles per gallon.
+    pub mpg: f64,
+    /// Fuel price per gallon by vertex; a leg's fuel is bought at the
+    /// price where it starts.
+    pub fuel_prices: Vec<u32>,
+    /// Tolls by leg.
+    pub tolls: Vec<(usize, usize, u32)>,
+    /// Lodging for each night on the road.
+    pub lodging: u32,
+}
+
+/// A `CostModel`'s miles per gallon isn't a positive, finite number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BadMpg(pub f64);
+
+impl fmt::Display for BadMpg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} miles per gallon isn't a positive number", self.0)
+    }
+}
+
+impl std::error::Error for BadMpg {}
+
+/// Where a plan's money goes, in cents.  Skipped stops' values aren't
+/// included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CostBreakdown {
+    pub fuel: u32,
+    pub tolls: u32,
+    pub lodging: u32,
+    pub nights: u32,
+}
+
+/// The cost model as fuel and tolls by leg.
+#[derive(Clone, Debug)]
+struct Money {
+    fuel: Legs,
+    tolls: Legs,
+    lodging: u32,
+}
+
+/// A trip found by `RoadTrip::solve`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TripPlan {
+    /// Vertices in visiting order, starting at home and, for a closed trip,
+    /// ending there too.
+    pub order: Vec<usize>,
+    /// Cost of the legs driven plus the value of every optional stop skipped.
+    pub cost: u32,
+    /// Optional stops left out.
+    pub skipped: Vec<usize>,
+    /// Arrival at each entry of `order`, when driving times are known.
+    pub arrivals: Option<Vec<Arrival>>,
+    /// Charging stops, in the order they are made, for a vehicle with a
+    /// limited range.
+    pub charges: Vec<Charge>,
+    /// What the trip costs in money, with a cost model.
+    pub breakdown: Option<CostBreakdown>,
+}
+
+impl TripPlan {
+    /// Returns `order` with the charging stops put in where they are made,
+    /// for drawing the trip on a map.
+    pub fn driven_order(&self) -> Vec<usize> {
+        let mut driven = Vec::with_capacity(self.order.len() + self.charges.len());
+        for (i, &v) in self.order.iter().enumerate() {
+            driven.push(v);
+            driven.extend(self.charges.iter().filter(|c| c.leg == i).map(|c| c.vertex));
+        }
+        // charging where a stop already is doesn't add a leg
+        driven.dedup();
+        driven
+    }
+}
+
+/// Why a visiting order can't be followed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Broken {
+    /// It breaks a rule or needs a missing leg.
+    Rule,
+    /// Its cost, or a time along the way, doesn't fit in a `u32`.
+    Overflow,
+}
+
+/// What following a visiting order adds up to.
+struct Walk {
+    cost: u32,
+    /// The part of `cost` that is the value of skipped stops.
+    skipped: u32,
+    arrivals: Vec<Arrival>,
+    charges: Vec<Charge>,
+    nights: u32,
+}
+
+/// The result of `RoadTrip::solve`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TripOutcome {
+    pub plan: Option<TripPlan>,
+    pub status: SearchStatus,
+    pub nodes: usize,
+}
+
+/// How far a trip has got, for replanning the rest of it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Stops visited so far, in order, starting at home.
+    pub visited: Vec<usize>,
+    /// Where the trip is now: the last stop visited, or a vertex between
+    /// it and the next one that only the legs to and from it know.
+    pub position: usize,
+    /// Seconds since the trip started.  With a daily limit, the day's
+    /// driving is taken to start again here.
+    pub elapsed: u32,
+    /// Stops still to visit, home aside.  Stops in neither list are
+    /// dropped; optional ones here may still be skipped.
+    pub remaining: Vec<usize>,
+}
+
+/// Why `RoadTrip::replan` can't pick a trip up from a `Progress`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BadProgress {
+    /// The visited stops don't start at home.
+    NotFromHome,
+    /// A visited stop, or the position, isn't a vertex of the trip.
+    NoVertex(usize),
+    /// A stop is visited more than once.
+    Revisited(usize),
+}
+
+impl fmt::Display for BadProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BadProgress::NotFromHome => write!(f, "the stops visited don't start at home"),
+            BadProgress::NoVertex(v) => write!(f, "no vertex {}", v),
+            BadProgress::Revisited(v) => write!(f, "stop {} is visited twice", v),
+        }
+    }
+}
+
+impl std::error::Error for BadProgress {}
+
+/// The result of `RoadTrip::replan`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replan {
+    /// The whole trip, visited stops first.
+    pub outcome: TripOutcome,
+    /// How much more the new plan costs than the one it replaces.
+    pub change: Option<i64>,
+}
+
+/// The result of `RoadTrip::alternatives`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alternatives {
+    /// The plans found, cheapest first.
+    pub plans: Vec<TripPlan>,
+    /// `Optimal` if no cheaper alternative was missed.
+    pub status: SearchStatus,
+    pub nodes: usize,
+}
+
+/// What `RoadTrip::pareto` trades a trip's cost off against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Tradeoff {
+    /// A second weight for each leg between stops, like driving time when
+    /// the cost is distance.
+    Legs(Vec<(usize, usize, u32)>),
+    /// The number of optional stops skipped.  The cost then leaves out
+    /// their values.
+    Stops,
+}
+
+/// One trade-off on the front found by `RoadTrip::pareto`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TradeoffPlan {
+    pub plan: TripPlan,
+    /// The cost minimized: the plan's cost, less the value of skipped
+    /// stops when trading off against stops.
+    pub cost: u32,
+    /// What it is traded off against: the total of the second leg
+    /// weights, or the number of stops skipped.
+    pub against: u32,
+}
+
+/// The result of `RoadTrip::pareto`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TripFront {
+    /// Every trade-off no other plan beats on both counts, cheapest first.
+    pub plans: Vec<TradeoffPlan>,
+    /// `Optimal` if no trade-off is missing.
+    pub status: SearchStatus,
+    pub nodes: usize,
+}
+
+/// Why `RoadTrip::sensitivity` can't analyse a trip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SensitivityError {
+    /// A plan the analysis needs costs more than a `u32` holds.
+    Overflow,
+    /// The trip minimizes the time elapsed rather than the leg costs.
+    Elapsed,
+}
+
+impl From<CostOverflow> for SensitivityError {
+    fn from(_: CostOverflow) -> Self {
+        SensitivityError::Overflow
+    }
+}
+
+impl fmt::Display for SensitivityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SensitivityError::Overflow => write!(f, "{}", CostOverflow),
+            SensitivityError::Elapsed => write!(
+                f,
+                "sensitivity needs the cost to be the sum of the legs, not the time elapsed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SensitivityError {}
+
+/// How far one leg's cost can move with the best plan staying the best,
+/// as found by `RoadTrip::sensitivity`.  At exactly that much another
+/// plan ties with it, or, where one of them drives the leg there and back
+/// and the other only once, at most one more overtakes it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LegSensitivity<C = u32> {
+    /// The stops at either end, lower first.
+    pub leg: (usize, usize),
+    /// Whether the best plan drives it.
+    pub in_plan: bool,
+    /// How much more it may cost, or None if no rise would change the
+    /// plan.  Only legs in the plan have a limit.
+    pub increase: Option<C>,
+    /// How much less it may cost, or None if no fall would change the
+    /// plan.  Only legs not in the plan have a limit.
+    pub decrease: Option<C>,
+}
+
+/// The result of `RoadTrip::sensitivity`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sensitivity {
+    pub plan: Option<TripPlan>,
+    /// The plan's legs in order, then the other legs asked about.  Empty
+    /// unless the plan is known to be the best.
+    pub legs: Vec<LegSensitivity>,
+    /// `Optimal` if every search behind the analysis finished; otherwise
+    /// the limits may be overstated.
+    pub status: SearchStatus,
+    pub nodes: usize,
+}
+
+/// A road trip with more rules than `roadtrip` knows about.  Vertex 0 is
+/// home.  By default every vertex must be visited and the trip returns
+/// home, which is exactly `roadtrip`.
+///
+/// Rules involving time (windows and daily limits) use the driving times
+/// given to `set_durations`, or the leg costs if there are none.
+#[derive(Clone, Debug)]
+pub struct RoadTrip {
+    vcount: usize,
+    costs: Legs,
+    durations: Option<Legs>,
+    closed: bool,
+    values: Vec<Option<u32>>,
+    windows: Vec<Option<(u32, u32)>>,
+    stays: Vec<u32>,
+    elapsed: bool,
+    precedence: Vec<(usize, usize)>,
+    groups: Vec<(Vec<usize>, usize, usize)>,
+    clusters: Vec<Vec<usize>>,
+    daily: Option<DailyLimit>,
+    vehicle: Option<(Vehicle, Legs)>,
+    money: Option<Money>,
+    progress: Option<Progress>,
+}
+
+impl RoadTrip {
+    /// Creates a trip through `vcount` vertices with the given leg costs.
+    /// Legs may also lead to extra vertices numbered from `vcount` up, the
+    /// charging locations of `set_vehicle`; the other setters leave out
+    /// legs past the highest vertex named here.
+    pub fn new(vcount: usize, edges: &[(usize, usize, u32)]) -> Self {
+        let span = edges
+            .iter()
+            .map(|&(a, b, _)| a.max(b).saturating_add(1))
+            .fold(vcount, usize::max);
+        Self {
+            vcount,
+            costs: DistanceMatrix::from_edges_within(span, edges),
+            durations: None,
+            closed: true,
+            values: vec![None; vcount],
+            windows: vec![None; vcount],
+            stays: vec![0; vcount],
+            elapsed: false,
+            precedence: Vec::new(),
+            groups: Vec::new(),
+            clusters: Vec::new(),
+            daily: None,
+            vehicle: None,
+            money: None,
+            progress: None,
+        }
+    }
+    /// Returns the number of vertices.
+    pub fn len(&self) -> usize {
+        self.vcount
+    }
+    /// Gives the driving time in seconds of each leg, for arrival times.
+    pub fn set_durations(&mut self, edges: &[(usize, usize, u32)]) {
+        self.durations = Some(self.legs(edges));
+    }
+    /// Sets whether the trip returns home at the end.
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+    /// Makes a vertex optional.  Skipping it adds `value` to the cost, so
+    /// it is only skipped if that saves more than `value` in driving.
+    pub fn set_optional(&mut self, vertex: usize, value: u32) {
+        assert!(vertex != 0, "home can't be optional");
+        self.values[vertex] = Some(value);
+    }
+    /// Requires arriving at a vertex between `earliest` and `latest`
+    /// seconds after the start.  Arriving early means waiting.
+    pub fn set_window(&mut self, vertex: usize, earliest: u32, latest: u32) {
+        self.windows[vertex] = Some((earliest, latest));
+    }
+    /// Spends `seconds` at a vertex after arriving, before driving on.  The
+    /// time counts towards the day's limit and delays every later arrival.
+    /// Home takes no time.
+    pub fn set_stay(&mut self, vertex: usize, seconds: u32) {
+        self.stays[vertex] = seconds;
+    }
+    /// Sets whether to minimize the time from leaving home to the end of
+    /// the trip, stays, waiting and nights included, instead of the sum of
+    /// the leg costs.
+    pub fn set_minimize_elapsed(&mut self, elapsed: bool) {
+        self.elapsed = elapsed;
+    }
+    /// Requires visiting `before` earlier than `after`.  If `before` is
+    /// optional or in a group, skipping it is still allowed.
+    pub fn add_precedence(&mut self, before: usize, after: usize) {
+        self.precedence.push((before, after));
+    }
+    /// Requires visiting between `at_least` and `at_most` of `members`,
+    /// such as the stops in one state.  Members may then be skipped at no
+    /// cost, unless they are also optional with a value; home counts as
+    /// visited.  With one group per state and `at_least` 1, this is a
+    /// generalized travelling salesman problem.
+    pub fn add_group(&mut self, members: &[usize], at_least: usize, at_most: usize) {
+        assert!(at_least <= at_most, "at_least is more than at_most");
+        self.groups.push((members.to_vec(), at_least, at_most));
+    }
+    /// Requires visiting the members of a cluster back to back, in any
+    /// order.  Members that are skipped leave no gap.  If home is one of
+    /// them, a closed trip may start and end with the cluster.
+    pub fn add_cluster(&mut self, members: &[usize]) {
+        self.clusters.push(members.to_vec());
+    }
+    /// Splits the trip into days of limited driving.
+    pub fn set_daily_limit(&mut self, limit: DailyLimit) {
+        self.daily = Some(limit);
+    }
+
+    /// Limits the trip to a vehicle's range, charging on the way where
+    /// needed.  `distances` gives leg lengths in meters, including the legs
+    /// to and from charging locations; so must the leg costs and any
+    /// driving times.  Charging time is added to the cost, which should
+    /// then be driving time too.
+    ///
+    /// The battery starts full and every charge fills it.  Between two
+    /// stops the trip takes the quickest way that stays in range, which
+    /// is not always best for the stops after.
+    pub fn set_vehicle(&mut self, vehicle: Vehicle, distances: &[(usize, usize, u32)]) {
+        let distances = self.legs(distances);
+        self.vehicle = Some((vehicle, distances));
+    }
+
+    /// Minimizes money instead: each leg's cost becomes its fuel and tolls
+    /// in cents, and every night on the road adds the lodging.  `distances`
+    /// gives leg lengths in meters.  Nights come from the daily driving
+    /// limit, so there are none without one.  The costs before this call
+    /// still serve as driving times unless `set_durations` gives them.
+    /// Not meant for a vehicle with a range, whose charging time would be
+    /// added to the money.  A leg costing more cents than a `u32` holds
+    /// can't be driven, like a missing one.  Fails, changing nothing, if
+    /// the miles per gallon is zero, negative or not finite.
+    pub fn set_cost_model(
+        &mut self,
+        model: &CostModel,
+        distances: &[(usize, usize, u32)],
+    ) -> Result<(), BadMpg> {
+        if !(model.mpg.is_finite() && model.mpg > 0.0) {
+            return Err(BadMpg(model.mpg));
+        }
+        const METERS_PER_MILE: f64 = 1609.344;
+        let fuel: Vec<_> = distances
+            .iter()
+            .filter_map(|&(a, b, meters)| {
+                let gallons = meters as f64 / METERS_PER_MILE / model.mpg;
+                let price = model.fuel_prices.get(a).cloned().unwrap_or(0);
+                let cents = (gallons * price as f64).round();
+                (cents <= u32::MAX as f64).then_some((a, b, cents as u32))
+            })
+            .collect();
+        let tolls = self.legs(&model.tolls);
+        if self.durations.is_none() {
+            self.durations = Some(self.costs.clone());
+        }
+        let costs: Vec<_> = fuel
+            .iter()
+            .filter_map(|&(a, b, f)| Some((a, b, f.checked_add(tolls.get(a, b).unwrap_or(0))?)))
+            .collect();
+        self.costs = self.legs(&costs);
+        let fuel = self.legs(&fuel);
+        self.money = Some(Money {
+            fuel,
+            tolls,
+            lodging: model.lodging,
+        });
+        Ok(())
+    }
+
+    /// A table of leg weights over the same vertices as the costs.
+    fn legs(&self, edges: &[(usize, usize, u32)]) -> Legs {
+        DistanceMatrix::from_edges_within(self.costs.len(), edges)
+    }
+
+    /// Returns the vehicle set by `set_vehicle`, if any.
+    pub fn vehicle(&self) -> Option<&Vehicle> {
+        self.vehicle.as_ref().map(|(v, _)| v)
+    }
+
+    /// Whether a vertex is still part of the trip; replanning drops the
+    /// stops that are neither visited nor remaining.
+    fn is_kept(&self, v: usize) -> bool {
+        self.progress
+            .as_ref()
+            .is_none_or(|p| v == 0 || p.visited.contains(&v) || p.remaining.contains(&v))
+    }
+
+    /// Whether a trip may leave a vertex out, because it is optional or in
+    /// a group.
+    fn is_skippable(&self, v: usize) -> bool {
+        self.values[v].is_some()
+            || self
+                .groups
+                .iter()
+                .any(|(members, _, _)| members.contains(&v))
+    }
+
+    /// The leg a replanned trip resumes on, leaving the last stop visited,
+    /// with where it resumes from.
+    fn resume(&self) -> Option<(usize, &Progress)> {
+        let progress = self.progress.as_ref()?;
+        Some((progress.visited.len().checked_sub(1)?, progress))
+    }
+
+    fn is_timed(&self) -> bool {
+        self.durations.is_some()
+            || self.daily.is_some()
+            || self.elapsed
+            || self.windows.iter().any(Option::is_some)
+            || self.stays.iter().any(|&s| s > 0)
+    }
+
+    /// The quickest way from `from` to `to` starting with `charge` meters
+    /// of range: the charging stops in between, the cost including
+    /// charging, and the range left on arrival.  More range left breaks
+    /// ties.
+    fn charge_route(&self, from: usize, to: usize, charge: u32) -> Option<(Vec<usize>, u32, u32)> {
+        let (vehicle, distances) = self.vehicle.as_ref()?;
+        let leg = |map: &Legs, a: usize, b: usize| {
+            if a == b {
+                Some(0)
+            } else {
+                map.get(a, b)
+            }
+        };
+        // a way to reach `to` within `range`, if it beats the best so far
+        let mut best: Option<(u32, u32, Vec<usize>)> = None;
+        let mut offer = |at: usize, range: u32, cost: u32, path: Vec<usize>| {
+            let d = leg(distances, at, to).filter(|&d| d <= range);
+            let total = leg(&self.costs, at, to).and_then(|c| cost.checked_add(c));
+            if let (Some(d), Some(total)) = (d, total) {
+                let left = range - d;
+                if best.as_ref().is_none_or(|b| (total, b.1) < (b.0, left)) {
+                    best = Some((total, left, path));
+                }
+            }
+        };
+        offer(from, charge, 0, Vec::new());
+
+        // Dijkstra over the chargers, each reached with a full charge
+        let chargers = &vehicle.chargers;
+        let mut reached: Vec<Option<u32>> = chargers
+            .iter()
+            .map(|&(c, seconds)| {
+                leg(distances, from, c).filter(|&d| d <= charge)?;
+                leg(&self.costs, from, c)?.checked_add(seconds)
+            })
+            .collect();
+        let mut previous = vec![None; chargers.len()];
+        let mut done = vec![false; chargers.len()];
+        while let Some(i) = (0..chargers.len())
+            .filter(|&i| !done[i] && reached[i].is_some())
+            .min_by_key(|&i| reached[i])
+        {
+            done[i] = true;
+            let (at, cost) = (chargers[i].0, reached[i].unwrap());
+            let mut path = vec![i];
+            while let Some(p) = previous[*path.last().unwrap()] {
+                path.push(p);
+            }
+            offer(
+                at,
+                vehicle.range,
+                cost,
+                path.iter().rev().map(|&p| chargers[p].0).collect(),
+            );
+            for (j, &(next, seconds)) in chargers.iter().enumerate() {
+                if done[j] || leg(distances, at, next).is_none_or(|d| d > vehicle.range) {
+                    continue;
+                }
+                let via = leg(&self.costs, at, next)
+                    .and_then(|c| cost.checked_add(c))
+                    .and_then(|c| c.checked_add(seconds));
+                if via.is_some() && reached[j].is_none_or(|r| via < Some(r)) {
+                    reached[j] = via;
+                    previous[j] = Some(i);
+                }
+            }
+        }
+        best.map(|(cost, left, path)| (path, cost, left))
+    }
+
+    /// Follows a visiting order, returning what it costs and, for a timed
+    /// trip, when each stop is reached, or why it can't be followed.  A
+    /// `complete` order is the whole trip, so the drive home and skipped
+    /// stops count; otherwise it is a prefix and only rules that it
+    /// already breaks are reported.
+    fn walk(&self, stops: &[usize], complete: bool) -> Result<Walk, Broken> {
+        if complete && stops.is_empty() {
+            // only a trip through no vertices at all has no home
+            return Err(Broken::Rule);
+        }
+        let mut legs: Vec<(usize, usize)> = stops.windows(2).map(|w| (w[0], w[1])).collect();
+        if complete && self.closed && stops.len() > 1 {
+            legs.push((stops[stops.len() - 1], stops[0]));
+        }
+
+        for &(before, after) in self.precedence.iter() {
+            if !self.is_kept(before) {
+                continue;
+            }
+            if let Some(b) = stops.iter().position(|&v| v == after) {
+                match stops.iter().position(|&v| v == before) {
+                    Some(a) if a > b => return Err(Broken::Rule),
+                    None if !self.is_skippable(before) => return Err(Broken::Rule),
+                    _ => {}
+                }
+            }
+        }
+
+        // a group can't be visited too often, and in the end often enough
+        for (members, at_least, at_most) in self.groups.iter() {
+            let visited = stops.iter().filter(|v| members.contains(v)).count();
+            if visited > *at_most || (complete && visited < *at_least) {
+                return Err(Broken::Rule);
+            }
+        }
+
+        // a cluster's stops form one run, or two joined up by the drive home
+        for members in self.clusters.iter() {
+            let mut runs: Vec<(usize, usize)> = Vec::new();
+            for (i, v) in stops.iter().enumerate() {
+                if members.contains(v) {
+                    match runs.last_mut() {
+                        Some(run) if run.1 + 1 == i => run.1 = i,
+                        _ => runs.push((i, i)),
+                    }
+                }
+            }
+            let wraps = self.closed && runs.len() == 2 && runs[0].0 == 0;
+            if runs.len() > 2 || (runs.len() == 2 && !(wraps && runs[1].1 + 1 == stops.len())) {
+                return Err(Broken::Rule);
+            }
+        }
+
+        // each leg as the vertices driven through, charging at the middle ones
+        let mut cost = 0u32;
+        let mut routes = Vec::with_capacity(legs.len());
+        let mut charges = Vec::new();
+        let mut charge = self.vehicle.as_ref().map_or(0, |(v, _)| v.range);
+        let resume = self.resume();
+        if let Some((b, progress)) = resume {
+            // the way to where the trip is now is already driven
+            let last = progress.visited[b];
+            if progress.position != last {
+                let leg = self.costs.get(last, progress.position);
+                cost = cost
+                    .checked_add(leg.ok_or(Broken::Rule)?)
+                    .ok_or(Broken::Overflow)?;
+            }
+        }
+        for (i, &(from, to)) in legs.iter().enumerate() {
+            let from = match resume {
+                Some((b, progress)) if i == b => {
+                    // the battery is taken to be full again
+                    charge = self.vehicle.as_ref().map_or(0, |(v, _)| v.range);
+                    progress.position
+                }
+                _ => from,
+            };
+            let mut route = vec![from];
+            if let Some((vehicle, _)) = &self.vehicle {
+                let (via, c, left) = self.charge_route(from, to, charge).ok_or(Broken::Rule)?;
+                for &vertex in via.iter() {
+                    let charger = vehicle.chargers.iter().find(|c| c.0 == vertex);
+                    let seconds = charger.ok_or(Broken::Rule)?.1;
+                    charges.push(Charge {
+                        leg: i,
+                        vertex,
+                        seconds,
+                    });
+                }
+                route.extend(via);
+                cost = cost.checked_add(c).ok_or(Broken::Overflow)?;
+                charge = left;
+            } else {
+                let leg = self.costs.get(from, to).ok_or(Broken::Rule)?;
+                cost = cost.checked_add(leg).ok_or(Broken::Overflow)?;
+            }
+            route.push(to);
+            routes.push(route);
+        }
+        let mut skipped = 0u32;
+        if complete {
+            for v in 0..self.vcount {
+                if !stops.contains(&v) && self.is_kept(v) {
+                    skipped = match self.values[v] {
+                        Some(value) => skipped.checked_add(value).ok_or(Broken::Overflow)?,
+                        None if self.is_skippable(v) => skipped,
+                        None => return Err(Broken::Rule),
+                    };
+                }
+            }
+        }
+        cost = cost.checked_add(skipped).ok_or(Broken::Overflow)?;
+
+        let mut arrivals = Vec::new();
+        let mut nights = 0;
+        let mut end = 0;
+        if self.is_timed() {
+            let times = self.durations.as_ref().unwrap_or(&self.costs);
+            let (mut day, mut driven, mut clock) = (0u32, 0u32, 0u32);
+            let mut charging = charges.iter();
+            arrivals.push(Arrival { day, time: clock });
+            for (i, route) in routes.iter().enumerate() {
+                let resumed = match resume {
+                    Some((b, progress)) if i == b => {
+                        clock = progress.elapsed;
+                        if self.daily.is_some() {
+                            day = clock / DAY;
+                            driven = 0;
+                        }
+                        true
+                    }
+                    Some((b, _)) => i > b,
+                    None => true,
+                };
+                for (h, hop) in route.windows(2).enumerate() {
+                    let t = if hop[0] == hop[1] {
+                        0
+                    } else {
+                        times.get(hop[0], hop[1]).ok_or(Broken::Rule)?
+                    };
+                    if let Some(limit) = self.daily {
+                        if t > limit.max_driving {
+                            return Err(Broken::Rule);
+                        }
+                        if driven.checked_add(t).ok_or(Broken::Overflow)? > limit.max_driving {
+                            // stop for the night
+                            day += 1;
+                            driven = 0;
+                            clock = clock.max(day.checked_mul(DAY).ok_or(Broken::Overflow)?);
+                        }
+                    }
+                    clock = clock.checked_add(t).ok_or(Broken::Overflow)?;
+                    driven = driven.checked_add(t).ok_or(Broken::Overflow)?;
+                    if h + 2 < route.len() {
+                        let charge = charging.next().ok_or(Broken::Rule)?;
+                        clock = clock.checked_add(charge.seconds).ok_or(Broken::Overflow)?;
+                    }
+                }
+                let to = *route.last().unwrap();
+                if !resumed {
+                    // already visited, so its rules were followed
+                    arrivals.push(Arrival { day, time: clock });
+                    continue;
+                }
+                if let Some((earliest, latest)) = self.windows[to] {
+                    if clock > latest {
+                        return Err(Broken::Rule);
+                    }
+                    if clock < earliest {
+                        clock = earliest;
+                        if self.daily.is_some() && clock / DAY > day {
+                            day = clock / DAY;
+                            driven = 0;
+                        }
+                    }
+                }
+                if let Some(max) = self.daily.and_then(|l| l.max_days) {
+                    if day >= max {
+                        return Err(Broken::Rule);
+                    }
+                }
+                arrivals.push(Arrival { day, time: clock });
+
+                let stay = if to == stops[0] { 0 } else { self.stays[to] };
+                if stay > 0 {
+                    clock = clock.checked_add(stay).ok_or(Broken::Overflow)?;
+                    if let Some(limit) = self.daily {
+                        // a stay into the next day uses up its morning
+                        if clock / DAY > day {
+                            day = clock / DAY;
+                            driven = clock - day * DAY;
+                        } else {
+                            driven = driven.checked_add(stay).ok_or(Broken::Overflow)?;
                         }
-                    })
-                    .sum()
+                        if limit.max_days.is_some_and(|max| day >= max) {
+                            return Err(Broken::Rule);
+                        }
+                    }
+                }
+            }
+            nights = day;
+            end = match resume {
+                // the visited stops' times were worked out afresh, and
+                // `elapsed` is what really went by
+                Some((b, progress)) if routes.len() <= b => progress.elapsed,
+                _ => clock,
+            };
+        }
+        if let Some(money) = &self.money {
+            let lodging = money.lodging.checked_mul(nights);
+            cost = lodging
+                .and_then(|l| cost.checked_add(l))
+                .ok_or(Broken::Overflow)?;
+        }
+        if self.elapsed {
+            cost = skipped.checked_add(end).ok_or(Broken::Overflow)?;
+        }
+        Ok(Walk {
+            cost,
+            skipped,
+            arrivals,
+            charges,
+            nights,
+        })
+    }
+
+    /// Finds the cheapest trip that follows every rule, using branch and
+    /// bound.  Variable k is the k-th stop; optional stops that are skipped
+    /// leave room at the end, which is filled with placeholder values.
+    pub fn solve(&self, limits: &Limits) -> TripOutcome {
+        self.solve_observed(limits, &mut ())
+    }
+
+    /// Like `solve`, but lets an observer follow the search and stop it.
+    /// A trip whose cost overflows counts as breaking a rule;
+    /// `solve_checked` tells the two apart.
+    pub fn solve_observed(&self, limits: &Limits, observer: &mut dyn Observer<u32>) -> TripOutcome {
+        self.search(limits, observer).0
+    }
+
+    /// Like `solve_observed`, but fails if the search ran out of trips
+    /// because the cheapest ones cost more than a `u32` holds.  A plan
+    /// that is found is still the cheapest.
+    pub fn solve_checked(
+        &self,
+        limits: &Limits,
+        observer: &mut dyn Observer<u32>,
+    ) -> Result<TripOutcome, CostOverflow> {
+        let (outcome, overflowed) = self.search(limits, observer);
+        checked(outcome.status, overflowed, outcome)
+    }
+
+    /// The branch and bound behind `solve_observed`, with whether any
+    /// partial trip overflowed.
+    fn search(&self, limits: &Limits, observer: &mut dyn Observer<u32>) -> (TripOutcome, bool) {
+        let (csp, choices) = self.model();
+
+        // generate solution using branch and bound; a broken rule costs
+        // the limit so that any partial trip breaking it gets pruned
+        let overflowed = Cell::new(false);
+        let outcome = csp.bnb_observed(
+            |v| self.bound(self.stops(&choices, &|var| v.get_value(var)), &overflowed),
+            Bound::Broken,
+            limits,
+            &mut Costs(observer),
+        );
+
+        let plan = outcome.solution.and_then(|soln| {
+            let (order, _) = self.stops(&choices, &|var| Some(*soln.index(var))).unwrap();
+            self.plan(order)
+        });
+
+        let outcome = TripOutcome {
+            plan,
+            status: outcome.status,
+            nodes: outcome.nodes,
+        };
+        (outcome, overflowed.get())
+    }
+
+    /// What a (partial) trip costs while searching, noting in `overflowed`
+    /// when it is broken because its cost overflows.
+    fn bound(&self, stops: Option<(Vec<usize>, bool)>, overflowed: &Cell<bool>) -> Bound<u32> {
+        let walk = match stops {
+            Some((stops, complete)) => self.walk(&stops, complete),
+            None => Err(Broken::Rule),
+        };
+        match walk {
+            Ok(walk) => Bound::Cost(walk.cost),
+            Err(broken) => {
+                overflowed.set(overflowed.get() || broken == Broken::Overflow);
+                Bound::Broken
+            }
+        }
+    }
+
+    /// Plans the rest of a trip already under way, keeping the stops
+    /// visited and the rules, and compares it with the `previous` plan.
+    /// The visited stops must follow on from home, each a different vertex
+    /// of this trip.  A stop added on the road must already be a vertex of
+    /// this trip, with legs to the rest.
+    pub fn replan(
+        &self,
+        previous: &TripPlan,
+        progress: &Progress,
+        limits: &Limits,
+    ) -> Result<Replan, BadProgress> {
+        if progress.visited.first() != Some(&0) {
+            return Err(BadProgress::NotFromHome);
+        }
+        for (i, &v) in progress.visited.iter().enumerate() {
+            if v >= self.vcount {
+                return Err(BadProgress::NoVertex(v));
+            }
+            if progress.visited[..i].contains(&v) {
+                return Err(BadProgress::Revisited(v));
+            }
+        }
+        if progress.position >= self.costs.len() {
+            return Err(BadProgress::NoVertex(progress.position));
+        }
+        let mut rest = self.clone();
+        rest.progress = Some(progress.clone());
+        let outcome = rest.solve(limits);
+        let change = outcome
+            .plan
+            .as_ref()
+            .map(|plan| plan.cost as i64 - previous.cost as i64);
+        Ok(Replan { outcome, change })
+    }
+
+    /// Finds up to `k` plans, cheapest first, each following every rule and
+    /// differing from every cheaper one in at least `min_different` legs.
+    /// A leg counts the same either way round, so a trip and its reverse
+    /// only both appear when `min_different` is 0.  Fails like
+    /// `solve_checked` if every plan costs more than a `u32` holds.
+    pub fn alternatives(
+        &self,
+        k: usize,
+        min_different: usize,
+        limits: &Limits,
+    ) -> Result<Alternatives, CostOverflow> {
+        let (csp, choices) = self.model();
+        // the legs of a (partial) trip, each with its ends in order
+        let legs = |stops: &[usize], complete: bool| -> Vec<(usize, usize)> {
+            let mut legs: Vec<_> = stops.windows(2).map(|w| (w[0], w[1])).collect();
+            if complete && self.closed && stops.len() > 1 {
+                legs.push((stops[stops.len() - 1], stops[0]));
+            }
+            undirected(&legs)
+        };
+        let most_legs = if self.closed {
+            self.vcount
+        } else {
+            self.vcount - 1
+        };
+
+        let overflowed = Cell::new(false);
+        let ranked = csp.bnb_k_best(
+            |v| self.bound(self.stops(&choices, &|var| v.get_value(var)), &overflowed),
+            Bound::Broken,
+            k,
+            |v, earlier| {
+                let (stops, complete) = match self.stops(&choices, &|var| v.get_value(var)) {
+                    Some(stops) => stops,
+                    None => return true,
+                };
+                let (before, _) = self.stops(&choices, &|var| Some(earlier[var])).unwrap();
+                let before = legs(&before, true);
+                let driven = legs(&stops, complete);
+                let new = driven.iter().filter(|leg| !before.contains(leg)).count();
+                // legs still to come might all be new
+                let to_come = if complete {
+                    0
+                } else {
+                    most_legs.saturating_sub(driven.len())
+                };
+                new + to_come >= min_different
             },
-            u32::MAX,
-        )
-        .unwrap();
+            limits,
+        );
+
+        let plans = ranked
+            .solutions
+            .iter()
+            .filter_map(|soln| {
+                let (order, _) = self.stops(&choices, &|var| Some(soln[var])).unwrap();
+                self.plan(order)
+            })
+            .collect();
+        let alternatives = Alternatives {
+            plans,
+            status: ranked.status,
+            nodes: ranked.nodes,
+        };
+        checked(ranked.status, overflowed.get(), alternatives)
+    }
+
+    /// Finds every trade-off between the trip's cost and something else,
+    /// keeping each plan that no other plan beats on both counts.  Skipped
+    /// stops' values still count unless the trade-off is against stops.
+    /// Fails like `solve_checked` if every plan overflows either measure.
+    pub fn pareto(&self, tradeoff: &Tradeoff, limits: &Limits) -> Result<TripFront, CostOverflow> {
+        let (csp, choices) = self.model();
+        let weights: Legs = match tradeoff {
+            Tradeoff::Legs(edges) => self.legs(edges),
+            Tradeoff::Stops => DistanceMatrix::new(0),
+        };
+        // both measures for a (partial) trip, or why it can't be followed
+        let measures = |stops: &[usize], complete: bool| -> Result<(u32, u32), Broken> {
+            let walk = self.walk(stops, complete)?;
+            match tradeoff {
+                Tradeoff::Legs(_) => {
+                    let mut legs: Vec<_> = stops.windows(2).map(|w| (w[0], w[1])).collect();
+                    if complete && self.closed && stops.len() > 1 {
+                        legs.push((stops[stops.len() - 1], stops[0]));
+                    }
+                    let mut total = 0u32;
+                    for (from, to) in legs {
+                        let weight = weights.get(from, to).ok_or(Broken::Rule)?;
+                        total = total.checked_add(weight).ok_or(Broken::Overflow)?;
+                    }
+                    Ok((walk.cost, total))
+                }
+                // a partial trip may yet visit every stop
+                Tradeoff::Stops if complete => {
+                    // dropped stops and group members left out don't count
+                    let skipped = (0..self.vcount)
+                        .filter(|&v| self.values[v].is_some() && self.is_kept(v))
+                        .filter(|v| !stops.contains(v))
+                        .count();
+                    Ok((walk.cost - walk.skipped, skipped as u32))
+                }
+                Tradeoff::Stops => Ok((walk.cost - walk.skipped, 0)),
+            }
+        };
+
+        let overflowed = Cell::new(false);
+        let outcome = csp.pareto(
+            |v| {
+                let measured = match self.stops(&choices, &|var| v.get_value(var)) {
+                    Some((stops, complete)) => measures(&stops, complete),
+                    None => Err(Broken::Rule),
+                };
+                match measured {
+                    Ok((cost, against)) => vec![Bound::Cost(cost), Bound::Cost(against)],
+                    Err(broken) => {
+                        overflowed.set(overflowed.get() || broken == Broken::Overflow);
+                        vec![Bound::Broken; 2]
+                    }
+                }
+            },
+            vec![Bound::Broken; 2],
+            limits,
+        );
+
+        let plans = outcome
+            .front
+            .into_iter()
+            .filter_map(|(score, soln)| {
+                let (order, _) = self.stops(&choices, &|var| Some(*soln.index(var))).unwrap();
+                Some(TradeoffPlan {
+                    plan: self.plan(order)?,
+                    cost: score[0].cost()?,
+                    against: score[1].cost()?,
+                })
+            })
+            .collect();
+        let front = TripFront {
+            plans,
+            status: outcome.status,
+            nodes: outcome.nodes,
+        };
+        checked(outcome.status, overflowed.get(), front)
+    }
+
+    /// How far the cost of each leg of the best plan, and of each of the
+    /// other `legs` given, can move before a different plan becomes
+    /// cheaper.  A leg is the road between two stops, so a change applies
+    /// to it both ways round.  Only costs change: where they double as
+    /// times, the times stay as they were.  Fails if a plan the analysis
+    /// needs costs more than a `u32` holds, or if the trip minimizes the
+    /// time elapsed, which leg costs don't add up to.
+    pub fn sensitivity(
+        &self,
+        legs: &[(usize, usize)],
+        limits: &Limits,
+    ) -> Result<Sensitivity, SensitivityError> {
+        if self.elapsed {
+            return Err(SensitivityError::Elapsed);
+        }
+        let start = Instant::now();
+        let mut base = self.clone();
+        if base.durations.is_none() && base.is_timed() {
+            base.durations = Some(base.costs.clone());
+        }
+        let (outcome, overflowed) = base.search(limits, &mut ());
+        let outcome = checked(outcome.status, overflowed, outcome)?;
+        let mut nodes = outcome.nodes;
+        let plan = match outcome.plan {
+            Some(plan) if outcome.status == SearchStatus::Optimal => plan,
+            plan => {
+                return Ok(Sensitivity {
+                    plan,
+                    legs: Vec::new(),
+                    status: outcome.status,
+                    nodes,
+                })
+            }
+        };
 
-    // compute the total cost for the trip
-    let net_cost = cycle
-        .windows(2)
-        .map(|cs| {
-            let from = cs[0];
-            let to = cs[1];
+        // a trip to one stop and back drives the same leg twice
+        let mut analysed = Vec::new();
+        for w in plan.order.windows(2) {
+            if !analysed.contains(&(w[0].min(w[1]), w[0].max(w[1]))) {
+                analysed.push((w[0].min(w[1]), w[0].max(w[1])));
+            }
+        }
+        let on_plan = analysed.len();
+        for &(a, b) in legs {
+            if !analysed.contains(&(a.min(b), a.max(b))) {
+                analysed.push((a.min(b), a.max(b)));
+            }
+        }
+
+        // the cheapest plan that drives a leg, leaving out two-stop trips
+        // if asked; those drive it both ways
+        let driving = |(a, b): (usize, usize), one_way: bool, remaining: &Limits| {
+            let (csp, choices) = base.model();
+            let overflowed = Cell::new(false);
+            let outcome = csp.bnb_limited(
+                |v| {
+                    let stops = base
+                        .stops(&choices, &|var| v.get_value(var))
+                        .filter(|(stops, complete)| !avoids(stops, *complete, base.closed, (a, b)))
+                        .filter(|(stops, complete)| !(one_way && *complete && stops.len() == 2));
+                    base.bound(stops, &overflowed)
+                },
+                Bound::Broken,
+                remaining,
+            );
+            let best = outcome.solution.and_then(|soln| {
+                let (order, _) = base.stops(&choices, &|var| Some(soln[var])).unwrap();
+                base.plan(order).map(|p| p.cost)
+            });
+            checked(
+                outcome.status,
+                overflowed.get(),
+                (best, outcome.status, outcome.nodes),
+            )
+        };
 
-            edges_map[&(soln[from], soln[to])]
+        let mut status = SearchStatus::Optimal;
+        let mut sensitivities = Vec::new();
+        let home = plan.order[0];
+        for (i, &(a, b)) in analysed.iter().enumerate() {
+            let in_plan = i < on_plan;
+            // a closed trip through just home and one stop drives the leg
+            // between them both ways, so a change counts twice for it
+            let twice = in_plan && plan.order.len() == 3 && base.closed;
+            // other plans gain or lose on the best one only by how often
+            // they drive the leg, so as it rises only plans driving it less
+            // can overtake, and as it falls only plans driving it more
+            let mut searches = Vec::new();
+            let mut margin: Option<u32> = None;
+            let mut lower = |bound: Option<u32>| {
+                if let Some(bound) = bound {
+                    margin = Some(margin.map_or(bound, |m| m.min(bound)));
+                }
+            };
+            let remaining = |nodes: usize| Limits {
+                max_nodes: limits.max_nodes.map(|max| max.saturating_sub(nodes)),
+                time_limit: limits.time_limit.map(|t| t.saturating_sub(start.elapsed())),
+            };
+            if in_plan {
+                let mut without = base.clone();
+                without.costs.set(a, b, None);
+                without.costs.set(b, a, None);
+                let (outcome, overflowed) = without.search(&remaining(nodes), &mut ());
+                let outcome = checked(outcome.status, overflowed, outcome)?;
+                let best = outcome.plan.map(|p| p.cost);
+                let factor = if twice { 2 } else { 1 };
+                lower(best.map(|cost| (cost - plan.cost) / factor));
+                searches.push((outcome.status, outcome.nodes));
+                if twice {
+                    let (best, searched, searched_nodes) =
+                        driving((a, b), true, &remaining(nodes + outcome.nodes))?;
+                    lower(best.map(|cost| cost - plan.cost));
+                    searches.push((searched, searched_nodes));
+                }
+            } else {
+                let (best, searched, searched_nodes) = driving((a, b), false, &remaining(nodes))?;
+                lower(best.map(|cost| cost - plan.cost));
+                searches.push((searched, searched_nodes));
+                if base.closed && (a == home || b == home) {
+                    match base.walk(&[home, a + b - home], true) {
+                        Ok(two) => lower(Some(two.cost.saturating_sub(plan.cost) / 2)),
+                        Err(Broken::Overflow) => return Err(SensitivityError::Overflow),
+                        Err(Broken::Rule) => {}
+                    }
+                }
+            }
+            for (searched, searched_nodes) in searches {
+                nodes += searched_nodes;
+                if !matches!(searched, SearchStatus::Optimal | SearchStatus::Infeasible) {
+                    status = SearchStatus::Feasible;
+                }
+            }
+            let cheapest = [(a, b), (b, a)]
+                .iter()
+                .filter_map(|&(from, to)| base.costs.get(from, to))
+                .min()
+                .unwrap_or(0);
+            sensitivities.push(LegSensitivity {
+                leg: (a, b),
+                in_plan,
+                increase: margin.filter(|_| in_plan),
+                decrease: margin.filter(|&m| !in_plan && m <= cheapest),
+            });
+        }
+        Ok(Sensitivity {
+            plan: Some(plan),
+            legs: sensitivities,
+            status,
+            nodes,
         })
-        .sum();
+    }
 
-    // iterate through the variables in our cycle and get there value
-    let order: Vec<_> = cycle.iter().map(|var| *soln.index(var.0)).collect();
+    /// The search model: one variable per position in the visiting order,
+    /// with home (or, when replanning, the stops visited) pinned first.
+    fn model(&self) -> (CSP<usize, FD>, Vec<Var>) {
+        let n = self.vcount;
+        let pinned = self
+            .progress
+            .as_ref()
+            .map_or(vec![0], |p| p.visited.clone());
+        let kept: Vec<_> = (0..n).filter(|&v| self.is_kept(v)).collect();
+        let mandatory = pinned.len()
+            + kept
+                .iter()
+                .filter(|&&v| !pinned.contains(&v) && !self.is_skippable(v))
+                .count();
+        let mut csp = CSP::new();
 
-    (order, net_cost)
+        // the placeholder at position p is n + p - mandatory, so that a
+        // trip skipping some stops has only one way to be written down
+        let choices: Vec<_> = (0..kept.len())
+            .map(|p| {
+                if p < pinned.len() {
+                    csp.add_variable(Domain::new(vec![pinned[p]]))
+                } else if p < mandatory {
+                    csp.add_variable(Domain::new(kept.clone()))
+                } else {
+                    let mut values = kept.clone();
+                    values.push(n + p - mandatory);
+                    csp.add_variable(Domain::new(values))
+                }
+            })
+            .collect();
+
+        // we need to visit all vertices, so all variables must be different
+        csp.add_constraint(FD::AllDiff(choices.clone()));
+        (csp, choices)
+    }
+
+    /// The stops assigned so far, and whether the trip is over.
+    fn stops(
+        &self,
+        choices: &[Var],
+        get: &dyn Fn(Var) -> Option<usize>,
+    ) -> Option<(Vec<usize>, bool)> {
+        let n = self.vcount;
+        let mut stops = Vec::with_capacity(n);
+        let mut ended = false;
+        for &var in choices.iter() {
+            match get(var) {
+                None => break,
+                Some(v) if v >= n => ended = true,
+                // nothing can follow a placeholder
+                Some(_) if ended => return None,
+                Some(v) => stops.push(v),
+            }
+        }
+        let complete = ended || stops.len() == choices.len();
+        Some((stops, complete))
+    }
+
+    /// The plan for a visiting order that follows every rule, or `None`
+    /// if its cost breakdown overflows.
+    fn plan(&self, mut order: Vec<usize>) -> Option<TripPlan> {
+        let walk = self.walk(&order, true).ok()?;
+        let skipped = (0..self.vcount)
+            .filter(|&v| !order.contains(&v) && self.is_kept(v))
+            .collect();
+        if self.closed {
+            order.push(order[0]);
+        }
+        let nights = walk.nights;
+        let mut plan = TripPlan {
+            order,
+            cost: walk.cost,
+            skipped,
+            arrivals: Some(walk.arrivals).filter(|_| self.is_timed()),
+            charges: walk.charges,
+            breakdown: None,
+        };
+        if let Some(money) = &self.money {
+            let mut breakdown = CostBreakdown {
+                lodging: money.lodging.checked_mul(nights)?,
+                nights,
+                ..CostBreakdown::default()
+            };
+            for leg in plan.driven_order().windows(2) {
+                let (from, to) = (leg[0], leg[1]);
+                let fuel = money.fuel.get(from, to).unwrap_or(0);
+                let tolls = money.tolls.get(from, to).unwrap_or(0);
+                breakdown.fuel = breakdown.fuel.checked_add(fuel)?;
+                breakdown.tolls = breakdown.tolls.checked_add(tolls)?;
+            }
+            plan.breakdown = Some(breakdown);
+        }
+        Some(plan)
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,9 @@
 // tests
 
 use super::*;
+use crate::cost::OrderedFloat;
+use crate::csp::{Limits, SearchStatus};
+use crate::rng::Rng;
 use std::time::{Duration, Instant};
 
 #[test]
@@ -204,3 +207,1267 @@ fn test_tsp_4() {
     assert_eq!(duration, Duration::new(5, 0));
     println!("Time elapsed in expensive_function() is: {:?}", duration);
 }
+
+/// Vertices on a line at the given positions; legs cost the gap.
+fn line(points: &[u32]) -> Vec<(usize, usize, u32)> {
+    let mut edges = Vec::new();
+    for (a, &x) in points.iter().enumerate() {
+        for (b, &y) in points.iter().enumerate() {
+            if a != b {
+                edges.push((a, b, x.abs_diff(y)));
+            }
+        }
+    }
+    edges
+}
+
+#[test]
+fn test_no_vertices() {
+    let outcome = roadtrip_limited::<u32>(0, &[], &Limits::default());
+    assert_eq!(
+        (outcome.tour, outcome.status),
+        (None, SearchStatus::Infeasible)
+    );
+    let trip = RoadTrip::new(0, &[]);
+    let outcome = trip.solve(&Limits::default());
+    assert_eq!(
+        (outcome.plan, outcome.status),
+        (None, SearchStatus::Infeasible)
+    );
+    assert!(trip
+        .alternatives(2, 0, &Limits::default())
+        .unwrap()
+        .plans
+        .is_empty());
+    assert!(trip
+        .pareto(&Tradeoff::Stops, &Limits::default())
+        .unwrap()
+        .plans
+        .is_empty());
+    assert_eq!(
+        trip.sensitivity(&[], &Limits::default()).unwrap().plan,
+        None
+    );
+}
+
+#[test]
+fn test_roadtrip_open_and_optional() {
+    let edges = line(&[0, 10, 20, 100]);
+    let mut trip = RoadTrip::new(4, &edges);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.cost, 200);
+    assert_eq!(plan.arrivals, None);
+
+    // the far stop is worth less than the 160 it costs to reach
+    trip.set_optional(3, 150);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(
+        (plan.order, plan.cost, plan.skipped),
+        (vec![0, 1, 2, 0], 190, vec![3])
+    );
+
+    trip.set_closed(false);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 1, 2, 3], 100));
+}
+
+#[test]
+fn test_roadtrip_rules() {
+    let edges = line(&[0, 10, 20, 30]);
+    let mut trip = RoadTrip::new(4, &edges);
+    trip.set_closed(false);
+    trip.add_precedence(3, 1);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.cost, 50);
+    assert_eq!(plan.order.last(), Some(&1));
+
+    // costs double as times: 2 by 25, 3 by 35, and 1 no earlier than 40
+    let mut trip = RoadTrip::new(4, &edges);
+    trip.set_window(2, 0, 25);
+    trip.set_window(3, 0, 35);
+    trip.set_window(1, 40, 100);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.order, vec![0, 2, 3, 1, 0]);
+    let times: Vec<_> = plan.arrivals.unwrap().iter().map(|a| a.time).collect();
+    assert_eq!(times, vec![0, 20, 30, 50, 60]);
+    // 2 is 20 away
+    trip.set_window(2, 0, 15);
+    let outcome = trip.solve(&Limits::default());
+    assert_eq!(
+        (outcome.plan, outcome.status),
+        (None, SearchStatus::Infeasible)
+    );
+}
+
+#[test]
+fn test_roadtrip_days() {
+    let hour = 3600;
+    let edges = line(&[0, 5 * hour, 8 * hour, 12 * hour]);
+    let mut trip = RoadTrip::new(4, &edges);
+    trip.set_closed(false);
+    trip.set_daily_limit(DailyLimit {
+        max_driving: 7 * hour,
+        max_days: None,
+    });
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    let arrivals = plan.arrivals.unwrap();
+    let days: Vec<_> = arrivals.iter().map(|a| a.day).collect();
+    assert_eq!(days, vec![0, 0, 1, 1]);
+    assert_eq!(arrivals[2].time, DAY + 3 * hour);
+
+    trip.set_daily_limit(DailyLimit {
+        max_driving: 7 * hour,
+        max_days: Some(1),
+    });
+    assert_eq!(trip.solve(&Limits::default()).plan, None);
+}
+
+#[test]
+fn test_roadtrip_charging() {
+    // stops at 0, 100 and 200 with chargers at 50 and 150, and at stop 1
+    let edges = line(&[0, 100, 200, 50, 150]);
+    let mut trip = RoadTrip::new(3, &edges);
+    trip.set_closed(false);
+    trip.set_vehicle(
+        Vehicle {
+            range: 60,
+            chargers: vec![(3, 10), (4, 10), (1, 20)],
+        },
+        &edges,
+    );
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order.clone(), plan.cost), (vec![0, 1, 2], 240));
+    assert_eq!(
+        plan.charges,
+        vec![
+            Charge {
+                leg: 0,
+                vertex: 3,
+                seconds: 10
+            },
+            Charge {
+                leg: 1,
+                vertex: 1,
+                seconds: 20
+            },
+            Charge {
+                leg: 1,
+                vertex: 4,
+                seconds: 10
+            },
+        ]
+    );
+    assert_eq!(plan.driven_order(), vec![0, 3, 1, 4, 2]);
+
+    // charging holds up the arrivals
+    trip.set_window(2, 0, 1000);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    let times: Vec<_> = plan.arrivals.unwrap().iter().map(|a| a.time).collect();
+    assert_eq!(times, vec![0, 110, 240]);
+    trip.set_window(1, 0, 105);
+    assert_eq!(trip.solve(&Limits::default()).plan, None);
+
+    // too short a range for any leg
+    let mut trip = RoadTrip::new(3, &edges);
+    trip.set_vehicle(
+        Vehicle {
+            range: 40,
+            chargers: vec![(3, 10), (4, 10)],
+        },
+        &edges,
+    );
+    assert_eq!(
+        trip.solve(&Limits::default()).status,
+        SearchStatus::Infeasible
+    );
+}
+
+#[test]
+fn test_roadtrip_money() {
+    // 10 and 20 miles out, with the legs doubling as driving times
+    let edges = line(&[0, 16093, 32187]);
+    let mut trip = RoadTrip::new(3, &edges);
+    trip.set_closed(false);
+    let mut model = CostModel {
+        mpg: 10.0,
+        fuel_prices: vec![300, 600, 300],
+        tolls: vec![(1, 2, 50)],
+        lodging: 0,
+    };
+    trip.set_cost_model(&model, &edges).unwrap();
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 2, 1], 900));
+    let breakdown = plan.breakdown.unwrap();
+    assert_eq!((breakdown.fuel, breakdown.tolls), (900, 0));
+
+    // a night on the road costs more than the toll
+    trip.set_daily_limit(DailyLimit {
+        max_driving: 33000,
+        max_days: None,
+    });
+    model.lodging = 1000;
+    trip.set_cost_model(&model, &edges).unwrap();
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 1, 2], 950));
+    assert_eq!(
+        plan.breakdown,
+        Some(CostBreakdown {
+            fuel: 900,
+            tolls: 50,
+            lodging: 0,
+            nights: 0
+        })
+    );
+
+    model.lodging = 10;
+    trip.set_cost_model(&model, &edges).unwrap();
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 2, 1], 910));
+    let breakdown = plan.breakdown.unwrap();
+    assert_eq!((breakdown.lodging, breakdown.nights), (10, 1));
+
+    // no fuel economy that can't be driven on is taken
+    for mpg in [0.0, -5.0, f64::NAN, f64::INFINITY].iter() {
+        let bad = CostModel {
+            mpg: *mpg,
+            ..model.clone()
+        };
+        assert!(trip.set_cost_model(&bad, &edges).is_err(), "{}", mpg);
+    }
+    assert_eq!(trip.solve(&Limits::default()).plan.unwrap().cost, 910);
+}
+
+#[test]
+fn test_cost_model_overflow() {
+    let edges = line(&[0, 16093, 32187]);
+    let mut trip = RoadTrip::new(3, &edges);
+    trip.set_closed(false);
+    // fuel bought at 2 costs more cents than fit, so no leg leaves it
+    let model = CostModel {
+        mpg: 0.001,
+        fuel_prices: vec![1, 1, u32::MAX],
+        tolls: Vec::new(),
+        lodging: 0,
+    };
+    trip.set_cost_model(&model, &edges).unwrap();
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.order, vec![0, 1, 2]);
+    trip.set_closed(true);
+    assert_eq!(trip.solve(&Limits::default()).plan, None);
+
+    // two nights' lodging costs more than fits
+    let edges = line(&[0, 16093, 32187, 48280]);
+    let mut trip = RoadTrip::new(4, &edges);
+    trip.set_closed(false);
+    trip.set_daily_limit(DailyLimit {
+        max_driving: 17000,
+        max_days: None,
+    });
+    let mut model = CostModel {
+        mpg: 10.0,
+        fuel_prices: vec![0; 4],
+        tolls: Vec::new(),
+        lodging: u32::MAX / 2,
+    };
+    trip.set_cost_model(&model, &edges).unwrap();
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.breakdown.map(|b| b.nights), Some(2));
+    model.lodging += 1;
+    trip.set_cost_model(&model, &edges).unwrap();
+    assert_eq!(trip.solve(&Limits::default()).plan, None);
+}
+
+/// A random trip through 2 to 6 vertices: asymmetric leg costs from 1 to
+/// 50, so there are ties, with about one leg in eight missing.
+fn random_trip(rng: &mut Rng) -> (usize, Vec<(usize, usize, u32)>) {
+    let n = rng.range(2, 6) as usize;
+    let mut edges = Vec::new();
+    for a in 0..n {
+        for b in 0..n {
+            if a != b && rng.unit() < 0.875 {
+                edges.push((a, b, rng.range(1, 50) as u32));
+            }
+        }
+    }
+    (n, edges)
+}
+
+/// Every ordering of `items`.
+fn permutations(items: &[usize]) -> Vec<Vec<usize>> {
+    if items.is_empty() {
+        return vec![Vec::new()];
+    }
+    let mut all = Vec::new();
+    for (i, &first) in items.iter().enumerate() {
+        let mut rest = items.to_vec();
+        rest.remove(i);
+        for mut p in permutations(&rest) {
+            p.insert(0, first);
+            all.push(p);
+        }
+    }
+    all
+}
+
+/// Cost of driving `order` (home first), or `None` if a leg is missing.
+/// Staying put, as in the `[0, 0]` of a trip that skips every stop, is free.
+fn drive(edges: &[(usize, usize, u32)], order: &[usize], closed: bool) -> Option<u32> {
+    let mut legs: Vec<_> = order.windows(2).map(|w| (w[0], w[1])).collect();
+    if closed && order.len() > 1 {
+        legs.push((order[order.len() - 1], order[0]));
+    }
+    legs.iter()
+        .filter(|(a, b)| a != b)
+        .map(|&(a, b)| edges.iter().find(|e| (e.0, e.1) == (a, b)).map(|e| e.2))
+        .sum()
+}
+
+/// The cheapest trip by trying every subset of the optional stops and
+/// every order of the rest, with `precedence` as `(before, after)` pairs.
+fn brute_force(
+    n: usize,
+    edges: &[(usize, usize, u32)],
+    closed: bool,
+    values: &[Option<u32>],
+    precedence: &[(usize, usize)],
+) -> Option<u32> {
+    let mut best = None;
+    for skip in 0u32..1 << n {
+        let skipped: Vec<usize> = (0..n).filter(|&v| skip & (1 << v) != 0).collect();
+        if skipped.iter().any(|&v| values[v].is_none()) {
+            continue;
+        }
+        let penalty: u32 = skipped.iter().map(|&v| values[v].unwrap()).sum();
+        let visited: Vec<usize> = (1..n).filter(|v| !skipped.contains(v)).collect();
+        for mut order in permutations(&visited) {
+            order.insert(0, 0);
+            let at = |v| order.iter().position(|&x| x == v);
+            let follows_rules = precedence.iter().all(|&(b, a)| match (at(b), at(a)) {
+                (Some(i), Some(j)) => i < j,
+                (None, Some(_)) => values[b].is_some(),
+                _ => true,
+            });
+            if let (true, Some(cost)) = (follows_rules, drive(edges, &order, closed)) {
+                best = best.min(Some(cost + penalty)).or(Some(cost + penalty));
+            }
+        }
+    }
+    best
+}
+
+#[test]
+fn test_roadtrip_matches_brute_force() {
+    for seed in 1..300 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let expected = brute_force(n, &edges, true, &vec![None; n], &[]);
+        let outcome = roadtrip_limited(n, &edges, &Limits::default());
+        match (&outcome.tour, expected) {
+            (Some(tour), Some(cost)) => {
+                assert_eq!(tour.cost, cost, "seed {}", seed);
+                assert_eq!(outcome.status, SearchStatus::Optimal, "seed {}", seed);
+                let mut visited = tour.order[..n].to_vec();
+                visited.sort_unstable();
+                assert_eq!(visited, (0..n).collect::<Vec<_>>(), "seed {}", seed);
+                assert_eq!(tour.order[n], 0, "seed {}", seed);
+                assert_eq!(
+                    drive(&edges, &tour.order, false),
+                    Some(cost),
+                    "seed {}",
+                    seed
+                );
+            }
+            (None, None) => assert_eq!(outcome.status, SearchStatus::Infeasible, "seed {}", seed),
+            (found, _) => panic!("seed {}: found {:?}, expected {:?}", seed, found, expected),
+        }
+        if let Some(cost) = expected {
+            assert_eq!(roadtrip(n, &edges).1, cost, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn test_roadtrip_rules_match_brute_force() {
+    for seed in 1..300 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let closed = rng.unit() < 0.5;
+        let values: Vec<_> = (0..n)
+            .map(|v| Some(rng.range(0, 60) as u32).filter(|_| v > 0 && rng.unit() < 0.4))
+            .collect();
+        let precedence: Vec<_> = (0..rng.range(0, 2))
+            .map(|_| (1 + rng.below(n - 1), 1 + rng.below(n - 1)))
+            .filter(|(a, b)| a != b)
+            .collect();
+
+        let mut trip = RoadTrip::new(n, &edges);
+        trip.set_closed(closed);
+        for (v, value) in values.iter().enumerate() {
+            if let Some(value) = value {
+                trip.set_optional(v, *value);
+            }
+        }
+        for &(before, after) in precedence.iter() {
+            trip.add_precedence(before, after);
+        }
+        let outcome = trip.solve(&Limits::default());
+        let expected = brute_force(n, &edges, closed, &values, &precedence);
+        assert_eq!(
+            outcome.plan.as_ref().map(|p| p.cost),
+            expected,
+            "seed {}",
+            seed
+        );
+        if let Some(plan) = outcome.plan {
+            let penalty: u32 = plan.skipped.iter().map(|&v| values[v].unwrap()).sum();
+            let driven = drive(&edges, &plan.order, false).unwrap();
+            assert_eq!(driven + penalty, plan.cost, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn test_groups_match_brute_force() {
+    for seed in 1..300 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let closed = rng.unit() < 0.5;
+        let exactly_one = rng.unit() < 0.5;
+        // each stop is in one of two groups, or in none and so must be visited
+        let groups: Vec<Vec<usize>> = (0..2)
+            .map(|g| (0..n).filter(|&v| v % 3 == g && rng.unit() < 0.8).collect())
+            .filter(|members: &Vec<usize>| !members.is_empty())
+            .collect();
+        let at_most = if exactly_one { 1 } else { n };
+
+        let mut trip = RoadTrip::new(n, &edges);
+        trip.set_closed(closed);
+        for members in groups.iter() {
+            trip.add_group(members, 1, at_most);
+        }
+        let outcome = trip.solve(&Limits::default());
+
+        let mut expected = None;
+        for skip in 0u32..1 << n {
+            let visited: Vec<usize> = (1..n).filter(|&v| skip & (1 << v) == 0).collect();
+            let count = |members: &Vec<usize>| {
+                members
+                    .iter()
+                    .filter(|&&v| v == 0 || visited.contains(&v))
+                    .count()
+            };
+            if skip & 1 != 0
+                || (1..n).any(|v| !visited.contains(&v) && !groups.iter().any(|g| g.contains(&v)))
+                || groups.iter().any(|g| count(g) < 1 || count(g) > at_most)
+            {
+                continue;
+            }
+            for mut order in permutations(&visited) {
+                order.insert(0, 0);
+                if let Some(cost) = drive(&edges, &order, closed) {
+                    expected = expected.min(Some(cost)).or(Some(cost));
+                }
+            }
+        }
+        assert_eq!(
+            outcome.plan.as_ref().map(|p| p.cost),
+            expected,
+            "seed {}",
+            seed
+        );
+        if let Some(plan) = outcome.plan {
+            assert_eq!(
+                drive(&edges, &plan.order, false),
+                Some(plan.cost),
+                "seed {}",
+                seed
+            );
+            for v in plan.skipped.iter() {
+                assert!(groups.iter().any(|g| g.contains(v)), "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_groups() {
+    // two states with a park near home and one far away each
+    let edges = line(&[0, 10, 12, 50, 53]);
+    let mut trip = RoadTrip::new(5, &edges);
+    trip.set_closed(false);
+    trip.add_group(&[1, 4], 1, 1);
+    trip.add_group(&[2, 3], 1, 1);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 1, 2], 12));
+    assert_eq!(plan.skipped, vec![3, 4]);
+
+    // home is in its group already, so exactly one leaves the rest out
+    let mut trip = RoadTrip::new(5, &edges);
+    trip.set_closed(false);
+    trip.add_group(&[0, 1, 2], 1, 1);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 3, 4], 53));
+}
+
+/// Whether `members` are back to back in a trip, going round if `closed`.
+fn is_clustered(order: &[usize], members: &[usize], closed: bool) -> bool {
+    let n = order.len();
+    let inside = |i: usize| members.contains(&order[i % n]);
+    let starts = (0..n)
+        .filter(|&i| {
+            inside(i)
+                && (if closed {
+                    !inside(i + n - 1)
+                } else {
+                    i == 0 || !inside(i - 1)
+                })
+        })
+        .count();
+    starts <= 1
+}
+
+#[test]
+fn test_clusters_match_brute_force() {
+    for seed in 1..300 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let closed = rng.unit() < 0.5;
+        let members: Vec<usize> = (0..n).filter(|_| rng.unit() < 0.5).collect();
+
+        let mut trip = RoadTrip::new(n, &edges);
+        trip.set_closed(closed);
+        trip.add_cluster(&members);
+        let outcome = trip.solve(&Limits::default());
+
+        let mut expected = None;
+        for mut order in permutations(&(1..n).collect::<Vec<_>>()) {
+            order.insert(0, 0);
+            if !is_clustered(&order, &members, closed) {
+                continue;
+            }
+            if let Some(cost) = drive(&edges, &order, closed) {
+                expected = expected.min(Some(cost)).or(Some(cost));
+            }
+        }
+        assert_eq!(
+            outcome.plan.as_ref().map(|p| p.cost),
+            expected,
+            "seed {}",
+            seed
+        );
+        if let Some(plan) = outcome.plan {
+            assert!(
+                is_clustered(&plan.order[..n], &members, closed),
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
+
+#[test]
+fn test_clusters() {
+    // legs between 1, 2 and 3 are cheap, so the best trip takes them in turn
+    let mut edges = line(&[0, 10, 20, 30]);
+    for e in edges.iter_mut() {
+        if (e.0 % 2 == 1) != (e.1 % 2 == 1) && e.0 != 0 && e.1 != 0 {
+            e.2 = 1;
+        }
+    }
+    let mut trip = RoadTrip::new(4, &edges);
+    trip.set_closed(false);
+    assert_eq!(
+        trip.solve(&Limits::default()).plan.unwrap().order,
+        vec![0, 1, 2, 3]
+    );
+    trip.add_cluster(&[1, 3]);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 1, 3, 2], 31));
+
+    // home in a cluster of a closed trip: the cluster may wrap round
+    let mut trip = RoadTrip::new(4, &line(&[0, 10, 20, 30]));
+    trip.add_cluster(&[0, 1]);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.cost, 60);
+}
+
+#[test]
+fn test_roadtrip_matrix() {
+    for seed in 1..50 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let expected = roadtrip_limited(n, &edges, &Limits::default());
+        let matrix = DistanceMatrix::from_edges(n, &edges).unwrap();
+        let outcome = roadtrip_matrix(&matrix, &Limits::default()).unwrap();
+        assert_eq!(
+            outcome.tour.map(|t| t.cost),
+            expected.tour.map(|t| t.cost),
+            "seed {}",
+            seed
+        );
+        assert_eq!(outcome.status, expected.status);
+    }
+
+    // edges to vertices past vcount are left out of the tour
+    let edges: [(usize, usize, u32); 6] = [
+        (0, 1, 1),
+        (1, 0, 1),
+        (0, 2, 5),
+        (2, 0, 5),
+        (1, 2, 5),
+        (2, 1, 5),
+    ];
+    assert_eq!(roadtrip(2, &edges), (vec![0, 1, 0], 2));
+    let outcome = roadtrip_checked(2, &edges, &Limits::default()).unwrap();
+    assert_eq!(outcome, roadtrip_limited(2, &edges, &Limits::default()));
+    let stray = [(0, 1, 1), (1, 0, 1), (0, usize::MAX, 1), (200000, 1, 1)];
+    assert_eq!(roadtrip(2, &stray), (vec![0, 1, 0], 2u32));
+}
+
+#[test]
+fn test_roadtrip_cost_types() {
+    for seed in 1..100 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let expected = roadtrip_limited(n, &edges, &Limits::default());
+        let wide: Vec<_> = edges.iter().map(|&(a, b, c)| (a, b, c as u64)).collect();
+        let halves: Vec<_> = edges
+            .iter()
+            .map(|&(a, b, c)| (a, b, OrderedFloat::new(c as f64 / 2.0).unwrap()))
+            .collect();
+        let wide = roadtrip_limited(n, &wide, &Limits::default());
+        let halves = roadtrip_limited(n, &halves, &Limits::default());
+        assert_eq!(
+            wide.tour.map(|t| t.cost),
+            expected.tour.as_ref().map(|t| t.cost as u64),
+            "seed {}",
+            seed
+        );
+        assert_eq!(
+            halves.tour.map(|t| t.cost.get()),
+            expected.tour.map(|t| t.cost as f64 / 2.0),
+            "seed {}",
+            seed
+        );
+        assert_eq!(
+            (wide.status, halves.status),
+            (expected.status, expected.status)
+        );
+
+        let expected = roadtrip_k_best(n, &edges, 3, 1);
+        let wide_edges: Vec<_> = edges.iter().map(|&(a, b, c)| (a, b, c as u64)).collect();
+        let found: Vec<_> = roadtrip_k_best(n, &wide_edges, 3, 1)
+            .into_iter()
+            .map(|(order, cost)| (order, cost as u32))
+            .collect();
+        assert_eq!(found, expected, "seed {}", seed);
+        let half_edges: Vec<_> = edges
+            .iter()
+            .map(|&(a, b, c)| (a, b, OrderedFloat::new(c as f64 / 2.0).unwrap()))
+            .collect();
+        let found: Vec<_> = roadtrip_k_best(n, &half_edges, 3, 1)
+            .into_iter()
+            .map(|(order, cost)| (order, (cost.get() * 2.0) as u32))
+            .collect();
+        assert_eq!(found, expected, "seed {}", seed);
+
+        let pairs = [(0, 1), (0, n - 1)];
+        let expected = roadtrip_sensitivity(n, &edges, &pairs);
+        let found: Vec<_> = roadtrip_sensitivity(n, &wide_edges, &pairs)
+            .into_iter()
+            .map(|s| (s.leg, s.in_plan, s.increase, s.decrease))
+            .collect();
+        assert_eq!(
+            found,
+            expected
+                .iter()
+                .map(|s| (
+                    s.leg,
+                    s.in_plan,
+                    s.increase.map(u64::from),
+                    s.decrease.map(u64::from)
+                ))
+                .collect::<Vec<_>>(),
+            "seed {}",
+            seed
+        );
+        let found: Vec<_> = roadtrip_sensitivity(n, &half_edges, &pairs)
+            .into_iter()
+            .map(|s| {
+                let doubled = |c: Option<OrderedFloat>| c.map(|c| (c.get() * 2.0) as u32);
+                (s.leg, s.in_plan, doubled(s.increase), doubled(s.decrease))
+            })
+            .collect();
+        assert_eq!(
+            found,
+            expected
+                .iter()
+                .map(|s| (s.leg, s.in_plan, s.increase, s.decrease))
+                .collect::<Vec<_>>(),
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn test_roadtrip_overflow() {
+    // a three-stop tour of 2^31 per leg overflows u32 but not u64
+    let big = 1u64 << 31;
+    let wide: Vec<_> = line(&[0, 1, 2])
+        .iter()
+        .map(|&(a, b, _)| (a, b, big))
+        .collect();
+    let narrow: Vec<_> = wide.iter().map(|&(a, b, c)| (a, b, c as u32)).collect();
+    assert_eq!(
+        roadtrip_checked(3, &narrow, &Limits::default()),
+        Err(CostOverflow)
+    );
+    let outcome = roadtrip_limited(3, &narrow, &Limits::default());
+    assert_eq!(
+        (outcome.tour, outcome.status),
+        (None, SearchStatus::Infeasible)
+    );
+    assert_eq!(roadtrip(3, &wide).1, 3 * big);
+
+    // a tour that fits is still found, and is the cheapest, when others overflow
+    let mut edges = narrow.clone();
+    for e in edges.iter_mut() {
+        if e.0 == 0 || e.1 == 0 {
+            e.2 = 1;
+        }
+    }
+    let outcome = roadtrip_checked(3, &edges, &Limits::default()).unwrap();
+    assert_eq!(outcome.tour.unwrap().cost, 2 + (1 << 31));
+
+    // the largest cost that fits is a tour, not the search's limit
+    let edges = [(0, 1, u32::MAX - 1), (1, 0, 1)];
+    assert_eq!(roadtrip(2, &edges), (vec![0, 1, 0], u32::MAX));
+}
+
+#[test]
+fn test_solve_checked_overflow() {
+    let big = 1u32 << 31;
+    let edges: Vec<_> = line(&[0, 1, 2])
+        .iter()
+        .map(|&(a, b, _)| (a, b, big))
+        .collect();
+    let trip = RoadTrip::new(3, &edges);
+    assert_eq!(
+        trip.solve_checked(&Limits::default(), &mut ()),
+        Err(CostOverflow)
+    );
+    let outcome = trip.solve(&Limits::default());
+    assert_eq!(
+        (outcome.plan, outcome.status),
+        (None, SearchStatus::Infeasible)
+    );
+    let limits = Limits::default();
+    assert_eq!(trip.alternatives(2, 0, &limits), Err(CostOverflow));
+    assert_eq!(trip.pareto(&Tradeoff::Stops, &limits), Err(CostOverflow));
+    assert_eq!(
+        trip.sensitivity(&[(0, 1)], &limits),
+        Err(SensitivityError::Overflow)
+    );
+
+    // a plan costing exactly u32::MAX is kept, not taken for the limit
+    let most = RoadTrip::new(2, &[(0, 1, u32::MAX - 1), (1, 0, 1)]);
+    let alternatives = most.alternatives(2, 0, &limits).unwrap();
+    assert_eq!(alternatives.plans.len(), 1);
+    assert_eq!(alternatives.plans[0].cost, u32::MAX);
+    let front = most.pareto(&Tradeoff::Stops, &limits).unwrap();
+    assert_eq!(front.plans.len(), 1);
+    assert_eq!(front.plans[0].cost, u32::MAX);
+    let analysis = most.sensitivity(&[], &limits).unwrap();
+    assert_eq!(analysis.plan.map(|p| p.cost), Some(u32::MAX));
+
+    // so do hours behind the wheel that overflow under a daily limit
+    let mut trip = RoadTrip::new(3, &line(&[0, 1, 2]));
+    trip.set_closed(false);
+    trip.set_durations(&edges);
+    trip.set_daily_limit(DailyLimit {
+        max_driving: u32::MAX,
+        max_days: None,
+    });
+    assert_eq!(trip.solve_checked(&limits, &mut ()), Err(CostOverflow));
+
+    // skipping the far stop keeps the trip in range
+    let edges: Vec<_> = edges
+        .iter()
+        .map(|&(a, b, c)| (a, b, if a == 2 || b == 2 { c } else { 5 }))
+        .collect();
+    let mut trip = RoadTrip::new(3, &edges);
+    trip.set_optional(2, 7);
+    let plan = trip
+        .solve_checked(&Limits::default(), &mut ())
+        .unwrap()
+        .plan
+        .unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 1, 0], 17));
+
+    // a trip costing the most a u32 holds is a plan, not the search's limit
+    let trip = RoadTrip::new(2, &[(0, 1, u32::MAX - 1), (1, 0, 1)]);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.cost, u32::MAX);
+}
+
+#[test]
+fn test_limited_roadtrip_is_sound() {
+    for seed in 1..200 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let expected = brute_force(n, &edges, true, &vec![None; n], &[]);
+        let limits = Limits {
+            max_nodes: Some(rng.range(1, 60) as usize),
+            time_limit: None,
+        };
+        let outcome = roadtrip_limited(n, &edges, &limits);
+        let found = outcome.tour.map(|t| t.cost);
+        match outcome.status {
+            SearchStatus::Optimal => assert_eq!(found, expected, "seed {}", seed),
+            SearchStatus::Infeasible => assert_eq!(expected, None, "seed {}", seed),
+            SearchStatus::Feasible => assert!(found >= expected, "seed {}", seed),
+            SearchStatus::Unknown => assert_eq!(found, None, "seed {}", seed),
+        }
+    }
+}
+
+#[test]
+fn test_pareto_matches_brute_force() {
+    for seed in 1..150 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let times: Vec<_> = edges
+            .iter()
+            .map(|&(a, b, _)| (a, b, rng.range(1, 50) as u32))
+            .collect();
+        let points: Vec<(u32, u32)> = permutations(&(1..n).collect::<Vec<_>>())
+            .into_iter()
+            .filter_map(|mut order| {
+                order.insert(0, 0);
+                Some((drive(&edges, &order, true)?, drive(&times, &order, true)?))
+            })
+            .collect();
+        let mut expected: Vec<_> = points
+            .iter()
+            .filter(|p| !points.iter().any(|q| q != *p && q.0 <= p.0 && q.1 <= p.1))
+            .cloned()
+            .collect();
+        expected.sort();
+        expected.dedup();
+
+        let front = RoadTrip::new(n, &edges)
+            .pareto(&Tradeoff::Legs(times.clone()), &Limits::default())
+            .unwrap();
+        let found: Vec<_> = front.plans.iter().map(|p| (p.cost, p.against)).collect();
+        assert_eq!(found, expected, "seed {}", seed);
+        for p in front.plans.iter() {
+            assert_eq!(
+                drive(&edges, &p.plan.order, false),
+                Some(p.cost),
+                "seed {}",
+                seed
+            );
+            assert_eq!(
+                drive(&times, &p.plan.order, false),
+                Some(p.against),
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
+
+#[test]
+fn test_pareto_stops() {
+    // each optional stop further out costs more to reach
+    let edges = line(&[0, 10, 20, 30]);
+    let mut trip = RoadTrip::new(4, &edges);
+    trip.set_optional(2, 1000);
+    trip.set_optional(3, 1000);
+    let front = trip.pareto(&Tradeoff::Stops, &Limits::default()).unwrap();
+    assert_eq!(front.status, SearchStatus::Optimal);
+    let found: Vec<_> = front
+        .plans
+        .iter()
+        .map(|p| (p.cost, p.against, p.plan.order.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (20, 2, vec![0, 1, 0]),
+            (40, 1, vec![0, 1, 2, 0]),
+            (60, 0, vec![0, 1, 2, 3, 0]),
+        ]
+    );
+    // the plans still carry the value of what they skip
+    assert_eq!(front.plans[0].plan.cost, 2020);
+
+    // only optional stops count as skipped, not group members left out
+    let mut trip = RoadTrip::new(5, &line(&[0, 10, 20, 30, 40]));
+    trip.set_optional(2, 1000);
+    trip.add_group(&[3, 4], 1, 1);
+    let front = trip.pareto(&Tradeoff::Stops, &Limits::default()).unwrap();
+    let found: Vec<_> = front.plans.iter().map(|p| (p.cost, p.against)).collect();
+    assert_eq!(found, vec![(60, 0)]);
+}
+
+/// How many legs of `a` aren't in `b`, either way round.
+fn new_legs(a: &[usize], b: &[usize]) -> usize {
+    let legs = |order: &[usize]| -> Vec<(usize, usize)> {
+        order
+            .windows(2)
+            .map(|w| (w[0].min(w[1]), w[0].max(w[1])))
+            .collect()
+    };
+    let before = legs(b);
+    legs(a).iter().filter(|leg| !before.contains(leg)).count()
+}
+
+#[test]
+fn test_alternatives_match_brute_force() {
+    for seed in 1..150 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let m = rng.below(3);
+        let tours: Vec<(Vec<usize>, u32)> = permutations(&(1..n).collect::<Vec<_>>())
+            .into_iter()
+            .filter_map(|mut order| {
+                order.insert(0, 0);
+                let cost = drive(&edges, &order, true)?;
+                order.push(0);
+                Some((order, cost))
+            })
+            .collect();
+
+        let found = roadtrip_k_best(n, &edges, 3, m);
+        assert!(found.len() <= 3, "seed {}", seed);
+        for (i, (order, cost)) in found.iter().enumerate() {
+            assert!(tours.contains(&(order.clone(), *cost)), "seed {}", seed);
+            for (earlier, _) in &found[..i] {
+                assert!(new_legs(order, earlier) >= m, "seed {}", seed);
+                assert_ne!(order, earlier, "seed {}", seed);
+            }
+        }
+        // any tour left out is too like a cheaper pick, or costs no less
+        // than the last one
+        for tour in tours.iter().filter(|t| !found.contains(t)) {
+            let blocked =
+                |picks: &[(Vec<usize>, u32)]| picks.iter().any(|(p, _)| new_legs(&tour.0, p) < m);
+            let ok = match found.iter().position(|f| f.1 > tour.1) {
+                Some(i) => blocked(&found[..i]),
+                None => found.len() == 3 || blocked(&found),
+            };
+            assert!(ok, "seed {}: {:?} was missed", seed, tour);
+        }
+    }
+}
+
+#[test]
+fn test_alternatives() {
+    let edges = line(&[0, 10, 20, 30]);
+    let mut trip = RoadTrip::new(4, &edges);
+    trip.set_closed(false);
+    let alternatives = trip.alternatives(3, 2, &Limits::default()).unwrap();
+    assert_eq!(alternatives.status, SearchStatus::Optimal);
+    let found: Vec<_> = alternatives
+        .plans
+        .iter()
+        .map(|p| (p.order.clone(), p.cost))
+        .collect();
+    // 0-1-3-2 has only one leg 0-1-2-3 doesn't, and every other order is
+    // too like one of these two
+    assert_eq!(found, vec![(vec![0, 1, 2, 3], 30), (vec![0, 2, 1, 3], 50)]);
+
+    let found = roadtrip_k_best(4, &edges, 3, 1);
+    let costs: Vec<_> = found.iter().map(|f| f.1).collect();
+    assert_eq!(costs, vec![60, 60, 80]);
+}
+
+#[test]
+fn test_replan() {
+    let edges = line(&[0, 10, 20, 30, 40, 25]);
+    let mut trip = RoadTrip::new(6, &edges);
+    trip.set_optional(5, 0);
+    let previous = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(previous.cost, 80);
+
+    // went to 2 first; 4 is dropped and 1 still to come
+    let progress = Progress {
+        visited: vec![0, 2],
+        position: 2,
+        elapsed: 20,
+        remaining: vec![1, 3],
+    };
+    let replan = trip
+        .replan(&previous, &progress, &Limits::default())
+        .unwrap();
+    let plan = replan.outcome.plan.unwrap();
+    assert_eq!(
+        (plan.order, plan.cost, plan.skipped),
+        (vec![0, 2, 3, 1, 0], 60, vec![])
+    );
+    assert_eq!(replan.change, Some(-20));
+
+    // halfway to 3, at 25, with 3 closing soon after
+    let progress = Progress {
+        visited: vec![0, 2],
+        position: 5,
+        elapsed: 50,
+        remaining: vec![3],
+    };
+    trip.set_window(3, 0, 60);
+    let replan = trip
+        .replan(&previous, &progress, &Limits::default())
+        .unwrap();
+    let plan = replan.outcome.plan.unwrap();
+    assert_eq!((plan.order, plan.cost), (vec![0, 2, 3, 0], 60));
+    let times: Vec<_> = plan.arrivals.unwrap().iter().map(|a| a.time).collect();
+    assert_eq!(times, vec![0, 20, 55, 85]);
+    let late = Progress {
+        elapsed: 56,
+        ..progress
+    };
+    let replan = trip.replan(&previous, &late, &Limits::default()).unwrap();
+    assert_eq!(
+        (replan.outcome.plan, replan.outcome.status, replan.change),
+        (None, SearchStatus::Infeasible, None)
+    );
+
+    // progress that can't have happened on this trip is refused
+    let bad = |visited: Vec<usize>, position: usize| {
+        let progress = Progress {
+            visited,
+            position,
+            elapsed: 0,
+            remaining: vec![3],
+        };
+        trip.replan(&previous, &progress, &Limits::default())
+            .unwrap_err()
+    };
+    assert_eq!(bad(vec![], 0), BadProgress::NotFromHome);
+    assert_eq!(bad(vec![2, 0], 0), BadProgress::NotFromHome);
+    assert_eq!(bad(vec![0, 9], 9), BadProgress::NoVertex(9));
+    assert_eq!(bad(vec![0, 2, 2], 2), BadProgress::Revisited(2));
+    assert_eq!(bad(vec![0, 2], 6), BadProgress::NoVertex(6));
+}
+
+/// `edges` with the leg between `a` and `b` costing `change` more, or
+/// less, both ways round.
+fn shift(
+    edges: &[(usize, usize, u32)],
+    (a, b): (usize, usize),
+    change: i64,
+) -> Vec<(usize, usize, u32)> {
+    edges
+        .iter()
+        .map(|&(x, y, c)| {
+            if (x, y) == (a, b) || (x, y) == (b, a) {
+                (x, y, (c as i64 + change).max(0) as u32)
+            } else {
+                (x, y, c)
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_sensitivity_matches_brute_force() {
+    for seed in 1..200 {
+        let mut rng = Rng::new(seed);
+        let (n, edges) = random_trip(&mut rng);
+        let pairs: Vec<_> = (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .collect();
+        let analysis = RoadTrip::new(n, &edges)
+            .sensitivity(&pairs, &Limits::default())
+            .unwrap();
+        let plan = match analysis.plan {
+            Some(plan) => plan,
+            None => continue,
+        };
+        assert_eq!(analysis.status, SearchStatus::Optimal, "seed {}", seed);
+        // the plain tour search agrees wherever it picks the same tour
+        if roadtrip_limited(n, &edges, &Limits::default())
+            .tour
+            .unwrap()
+            .order
+            == plan.order
+        {
+            assert_eq!(
+                roadtrip_sensitivity(n, &edges, &pairs),
+                analysis.legs,
+                "seed {}",
+                seed
+            );
+        }
+        let best_after = |change: i64, leg| {
+            let edges = shift(&edges, leg, change);
+            let ours = drive(&edges, &plan.order, false).unwrap();
+            (
+                ours,
+                brute_force(n, &edges, true, &vec![None; n], &[]).unwrap(),
+            )
+        };
+        for s in &analysis.legs {
+            let on_plan = plan
+                .order
+                .windows(2)
+                .any(|w| (w[0].min(w[1]), w[0].max(w[1])) == s.leg);
+            assert_eq!(s.in_plan, on_plan, "seed {}", seed);
+            if s.in_plan {
+                assert_eq!(s.decrease, None, "seed {}", seed);
+                let m = s.increase.map_or(1000, |m| m as i64);
+                let (ours, best) = best_after(m, s.leg);
+                assert_eq!(ours, best, "seed {} {:?}", seed, s);
+                if s.increase.is_some() {
+                    let (ours, best) = best_after(m + 1, s.leg);
+                    assert!(best < ours, "seed {} {:?}", seed, s);
+                }
+            } else {
+                assert_eq!(s.increase, None, "seed {}", seed);
+                let cheapest = edges
+                    .iter()
+                    .filter(|e| (e.0.min(e.1), e.0.max(e.1)) == s.leg)
+                    .map(|e| e.2 as i64)
+                    .min()
+                    .unwrap_or(0);
+                let m = s.decrease.map_or(cheapest, |m| m as i64);
+                let (ours, best) = best_after(-m, s.leg);
+                assert_eq!(ours, best, "seed {} {:?}", seed, s);
+                if s.decrease.is_some() && m < cheapest {
+                    let (ours, best) = best_after(-m - 1, s.leg);
+                    assert!(best < ours, "seed {} {:?}", seed, s);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sensitivity() {
+    // a square with sides of 10 and diagonals of 14
+    let mut edges = Vec::new();
+    let at = [(0, 0), (10, 0), (10, 10), (0, 10)];
+    for (a, &(x1, y1)) in at.iter().enumerate() {
+        for (b, &(x2, y2)) in at.iter().enumerate() {
+            let straight = x1 == x2 || y1 == y2;
+            if a != b {
+                edges.push((a, b, if straight { 10u32 } else { 14 }));
+            }
+        }
+    }
+    let found = roadtrip_sensitivity(4, &edges, &[(0, 2), (1, 3)]);
+    let sides: Vec<_> = found.iter().filter(|s| s.in_plan).collect();
+    // any side can rise by 8 before a tour down both diagonals takes over
+    assert_eq!(sides.len(), 4);
+    assert!(sides.iter().all(|s| s.increase == Some(8)));
+    // a diagonal must fall by 8 for a tour down both, which is tied then
+    let diagonals: Vec<_> = found.iter().filter(|s| !s.in_plan).collect();
+    assert_eq!(
+        diagonals
+            .iter()
+            .map(|s| (s.leg, s.decrease))
+            .collect::<Vec<_>>(),
+        vec![((0, 2), Some(8)), ((1, 3), Some(8))]
+    );
+}
+
+/// Two-way edges with the given costs.
+fn both_ways(legs: &[(usize, usize, u32)]) -> Vec<(usize, usize, u32)> {
+    legs.iter()
+        .flat_map(|&(a, b, c)| vec![(a, b, c), (b, a, c)])
+        .collect()
+}
+
+#[test]
+fn test_sensitivity_there_and_back() {
+    // skipping 2 leaves a trip to 1 and back, which drives that leg twice
+    let mut trip = RoadTrip::new(3, &both_ways(&[(0, 1, 10), (0, 2, 10), (1, 2, 10)]));
+    trip.set_optional(2, 5);
+    let analysis = trip.sensitivity(&[], &Limits::default()).unwrap();
+    assert_eq!(
+        analysis.plan.map(|p| (p.order, p.cost)),
+        Some((vec![0, 1, 0], 25))
+    );
+    // rising by 5 costs it 10, and the tour through 2 only 5
+    let leg = &analysis.legs[0];
+    assert_eq!((leg.leg, leg.increase), ((0, 1), Some(5)));
+
+    // the time elapsed isn't a sum of leg costs to move
+    trip.set_minimize_elapsed(true);
+    assert_eq!(
+        trip.sensitivity(&[], &Limits::default()),
+        Err(SensitivityError::Elapsed)
+    );
+
+    // the trip to 3 and back gains twice what the leg falls
+    let edges = both_ways(&[
+        (0, 1, 10),
+        (1, 2, 10),
+        (0, 2, 10),
+        (0, 3, 40),
+        (1, 3, 100),
+        (2, 3, 100),
+    ]);
+    let mut trip = RoadTrip::new(4, &edges);
+    for v in 1..4 {
+        trip.set_optional(v, 25);
+    }
+    let analysis = trip.sensitivity(&[(3, 0)], &Limits::default()).unwrap();
+    assert_eq!(analysis.plan.map(|p| p.cost), Some(55));
+    let leg = analysis.legs.last().unwrap();
+    assert_eq!(
+        (leg.leg, leg.in_plan, leg.decrease),
+        ((0, 3), false, Some(37))
+    );
+}
+
+#[test]
+fn test_roadtrip_stays() {
+    // costs double as times, and stop 1 opens at 100
+    let edges = line(&[0, 10, 20]);
+    let mut trip = RoadTrip::new(3, &edges);
+    trip.set_window(1, 100, 1000);
+    trip.set_stay(2, 50);
+    trip.set_minimize_elapsed(true);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    // going to 1 first waits 90 there and then stays 50 at 2
+    assert_eq!((plan.order, plan.cost), (vec![0, 2, 1, 0], 110));
+    let times: Vec<_> = plan.arrivals.unwrap().iter().map(|a| a.time).collect();
+    assert_eq!(times, vec![0, 20, 100, 110]);
+
+    trip.set_stay(1, 30);
+    let plan = trip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.cost, 140);
+    // back home by 130 is now too soon
+    trip.set_window(0, 0, 130);
+    assert_eq!(trip.solve(&Limits::default()).plan, None);
+
+    let hour = 3600;
+    let edges = line(&[0, 5 * hour, 8 * hour]);
+    let mut trip = RoadTrip::new(3, &edges);
+    trip.set_closed(false);
+    trip.set_daily_limit(DailyLimit {
+        max_driving: 9 * hour,
+        max_days: None,
+    });
+    let arrive_at_2 = |trip: &RoadTrip| {
+        let plan = trip.solve(&Limits::default()).plan.unwrap();
+        assert_eq!(plan.order, vec![0, 1, 2]);
+        plan.arrivals.unwrap()[2]
+    };
+    assert_eq!(arrive_at_2(&trip).time, 8 * hour);
+    // two hours at 1 leave too little of the day for the last leg
+    trip.set_stay(1, 2 * hour);
+    assert_eq!(arrive_at_2(&trip).time, DAY + 3 * hour);
+    // and a stay until the next afternoon uses up that day too
+    trip.set_stay(1, 30 * hour);
+    let arrival = arrive_at_2(&trip);
+    assert_eq!((arrival.day, arrival.time), (2, 2 * DAY + 3 * hour));
+    trip.set_daily_limit(DailyLimit {
+        max_driving: 9 * hour,
+        max_days: Some(2),
+    });
+    assert_eq!(trip.solve(&Limits::default()).plan, None);
+}
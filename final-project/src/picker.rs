@@ -0,0 +1,297 @@
+use crate::csp::{Limits, SearchStatus};
+use crate::matrix::{Objective, TravelMatrix};
+use crate::opt::roadtrip_limited;
+use crate::report::format_distance;
+use crate::roadgraph::haversine;
+use crate::route::Route;
+use crate::waypoint::{gazetteer, Filter, Waypoint};
+use std::io::{self, BufRead, Write};
+
+pub const HELP: &str = "\
+commands:
+  list                 show the waypoints that pass the filters (* = selected)
+  search TEXT          only list waypoints whose name contains TEXT (no TEXT to clear)
+  state NAME           only list waypoints in this state (no NAME to clear)
+  category park|city   only list parks or cities (no argument to clear)
+  clear                clear all filters
+  add 1 4 7-9 | all    select waypoints by their number in the list
+  remove 1 4 | all     unselect waypoints by their number in the list
+  review               show the selection in visiting order; the first is home
+  drop N               unselect the Nth waypoint of the review
+  home N               start and end the trip at the Nth waypoint of the review
+  plan                 plan a round trip through the selection
+  help                 show this text
+  quit                 leave
+";
+
+/// An interactive waypoint picker that replaces `userdata.py`.  It reads one
+/// command per line, so it works the same at a terminal and from a script.
+pub struct Picker {
+    waypoints: Vec<Waypoint>,
+    filter: Filter,
+    /// Indices into `waypoints` of the current listing.
+    listing: Vec<usize>,
+    /// Indices into `waypoints` in visiting order.
+    selected: Vec<usize>,
+    matrix: Option<TravelMatrix>,
+    limits: Limits,
+}
+
+impl Picker {
+    /// Creates a picker over the gazetteer.  Trips are planned with the
+    /// matrix's distances when one is given, otherwise with straight-line
+    /// distances between the waypoints.
+    pub fn new(matrix: Option<TravelMatrix>, limits: Limits) -> Self {
+        let waypoints = gazetteer();
+        let listing = (0..waypoints.len()).collect();
+        Self {
+            waypoints,
+            filter: Filter::default(),
+            listing,
+            selected: Vec::new(),
+            matrix,
+            limits,
+        }
+    }
+
+    /// Returns the selected waypoints in visiting order.
+    pub fn selection(&self) -> Vec<&Waypoint> {
+        self.selected.iter().map(|&i| &self.waypoints[i]).collect()
+    }
+
+    /// Reads and runs commands until `quit` or the end of the input.
+    pub fn run(&mut self, input: &mut dyn BufRead, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{} waypoints to choose from.  Type help for the commands.",
+            self.waypoints.len()
+        )?;
+        let mut line = String::new();
+        loop {
+            write!(out, "> ")?;
+            out.flush()?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(out)?;
+                return Ok(());
+            }
+            let line = line.trim();
+            let (command, rest) = match line.find(char::is_whitespace) {
+                Some(i) => (&line[..i], line[i..].trim()),
+                None => (line, ""),
+            };
+            let reply = match command {
+                "" => continue,
+                "quit" | "exit" | "q" => return Ok(()),
+                "help" | "?" => HELP.to_string(),
+                "list" | "ls" => self.list(),
+                "search" => self.set_filter(|f| f.search = non_empty(rest)),
+                "state" => self.set_filter(|f| f.state = non_empty(rest)),
+                "category" => match non_empty(rest).map(|c| c.parse()).transpose() {
+                    Ok(category) => self.set_filter(|f| f.category = category),
+                    Err(message) => message + "\n",
+                },
+                "clear" => self.set_filter(|f| *f = Filter::default()),
+                "add" => self.add(rest),
+                "remove" | "rm" => self.remove(rest),
+                "review" => self.review(),
+                "drop" => self.drop_stop(rest),
+                "home" => self.home(rest),
+                "plan" => self.plan(),
+                other => format!("unknown command {:?}; type help for the commands\n", other),
+            };
+            write!(out, "{}", reply)?;
+        }
+    }
+
+    fn set_filter(&mut self, change: impl FnOnce(&mut Filter)) -> String {
+        change(&mut self.filter);
+        let filter = &self.filter;
+        self.listing = (0..self.waypoints.len())
+            .filter(|&i| filter.matches(&self.waypoints[i]))
+            .collect();
+        self.list()
+    }
+
+    fn list(&self) -> String {
+        if self.listing.is_empty() {
+            return "No waypoints match the filters.\n".to_string();
+        }
+        let mut text = String::new();
+        for (n, &i) in self.listing.iter().enumerate() {
+            let w = &self.waypoints[i];
+            text.push_str(&format!(
+                "{:>4}{} {:<45} {}\n",
+                n + 1,
+                if self.selected.contains(&i) { '*' } else { ' ' },
+                w.label(),
+                w.category
+            ));
+        }
+        text
+    }
+
+    /// Turns `"1 4 7-9"` or `"all"` into 0-based positions below `len`.
+    fn positions(arg: &str, len: usize) -> Result<Vec<usize>, String> {
+        if arg == "all" {
+            return Ok((0..len).collect());
+        }
+        let number = |s: &str| match s.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= len => Ok(n - 1),
+            _ => Err(format!("{:?} is not a number from 1 to {}\n", s, len)),
+        };
+        let mut positions = Vec::new();
+        for word in arg.split(|c: char| c.is_whitespace() || c == ',') {
+            match word.split_once('-') {
+                _ if word.is_empty() => {}
+                Some((a, b)) => positions.extend(number(a)?..=number(b)?),
+                None => positions.push(number(word)?),
+            }
+        }
+        if positions.is_empty() {
+            return Err("which ones?  Give their numbers, like 1 4 7-9\n".to_string());
+        }
+        Ok(positions)
+    }
+
+    fn add(&mut self, arg: &str) -> String {
+        match Self::positions(arg, self.listing.len()) {
+            Ok(positions) => {
+                for p in positions {
+                    let i = self.listing[p];
+                    if !self.selected.contains(&i) {
+                        self.selected.push(i);
+                    }
+                }
+                format!("{} selected.\n", self.selected.len())
+            }
+            Err(message) => message,
+        }
+    }
+
+    fn remove(&mut self, arg: &str) -> String {
+        match Self::positions(arg, self.listing.len()) {
+            Ok(positions) => {
+                let gone: Vec<_> = positions.iter().map(|&p| self.listing[p]).collect();
+                self.selected.retain(|i| !gone.contains(i));
+                format!("{} selected.\n", self.selected.len())
+            }
+            Err(message) => message,
+        }
+    }
+
+    fn review(&self) -> String {
+        if self.selected.is_empty() {
+            return "Nothing selected yet.\n".to_string();
+        }
+        let mut text = String::new();
+        for (n, w) in self.selection().iter().enumerate() {
+            text.push_str(&format!(
+                "{:>4}. {}{}\n",
+                n + 1,
+                w.label(),
+                if n == 0 { " (home)" } else { "" }
+            ));
+        }
+        text
+    }
+
+    fn drop_stop(&mut self, arg: &str) -> String {
+        match Self::positions(arg, self.selected.len()) {
+            Ok(mut positions) => {
+                positions.sort_unstable();
+                positions.dedup();
+                for p in positions.into_iter().rev() {
+                    self.selected.remove(p);
+                }
+                self.review()
+            }
+            Err(message) => message,
+        }
+    }
+
+    fn home(&mut self, arg: &str) -> String {
+        match Self::positions(arg, self.selected.len()).as_deref() {
+            Ok([p]) => {
+                let i = self.selected.remove(*p);
+                self.selected.insert(0, i);
+                self.review()
+            }
+            Ok(_) => "give a single number from the review\n".to_string(),
+            Err(message) => message.clone(),
+        }
+    }
+
+    /// Leg distances between the selected waypoints, numbered by their
+    /// position in the selection, any driving times, and a note on where
+    /// they came from.
+    fn legs(&self) -> Result<(Edges, Option<Edges>, &'static str), String> {
+        let stops = self.selection();
+        match &self.matrix {
+            Some(matrix) => {
+                let labels: Vec<_> = stops.iter().map(|w| w.label()).collect();
+                let selection = matrix.select(&labels).map_err(|e| e.to_string())?;
+                let edges = matrix
+                    .edges(&selection, Objective::Distance)
+                    .map_err(|e| e.to_string())?;
+                let durations = matrix.edges(&selection, Objective::Duration).ok();
+                Ok((edges, durations, "driving distances from the matrix"))
+            }
+            None => {
+                let mut edges = Vec::new();
+                for (a, from) in stops.iter().enumerate() {
+                    for (b, to) in stops.iter().enumerate() {
+                        if a != b {
+                            let meters = haversine(from.coords(), to.coords()).round() as u32;
+                            edges.push((a, b, meters));
+                        }
+                    }
+                }
+                Ok((edges, None, "straight-line distances"))
+            }
+        }
+    }
+
+    fn plan(&self) -> String {
+        if self.selected.len() < 2 {
+            return "Select at least two waypoints first.\n".to_string();
+        }
+        let (edges, durations, source) = match self.legs() {
+            Ok(found) => found,
+            Err(message) => return format!("Can't plan this trip: {}\n", message),
+        };
+        let outcome = roadtrip_limited(self.selected.len(), &edges, &self.limits);
+        let tour = match outcome.tour {
+            Some(tour) => tour,
+            None => return "No round trip found.\n".to_string(),
+        };
+        let stops = self.selection().into_iter().cloned().collect();
+        let mut route = Route::new(stops, tour.order, &edges);
+        if let Some(durations) = durations {
+            route.add_durations(&durations);
+        }
+
+        let mut text = format!("Itinerary using {}:\n", source);
+        for (n, leg) in route.legs.iter().enumerate() {
+            text.push_str(&format!("{:>4}. {}\n", n + 1, route.describe_leg(leg)));
+        }
+        text.push_str(&format!(
+            "Total: {}{}\n",
+            format_distance(route.total_distance()),
+            match outcome.status {
+                SearchStatus::Optimal => "",
+                _ => " (a limit was reached, so there may be a shorter trip)",
+            }
+        ));
+        text
+    }
+}
+
+type Edges = Vec<(usize, usize, u32)>;
+
+fn non_empty(s: &str) -> Option<String> {
+    Some(s.to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,68 @@
+// tests
+
+use super::*;
+use std::io::Cursor;
+
+fn session(picker: &mut Picker, commands: &str) -> String {
+    let mut out = Vec::new();
+    picker
+        .run(&mut Cursor::new(commands.as_bytes()), &mut out)
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_filter_and_select() {
+    let mut picker = Picker::new(None, Limits::default());
+    let out = session(
+        &mut picker,
+        "state utah\ncategory park\nadd 1 3-4\nremove 3\nsearch bryce\nadd all\nclear\nreview\nhome 2\nquit\n",
+    );
+    // Utah parks: Arches, Bryce Canyon, Canyonlands, Capitol Reef, Zion
+    assert!(out.contains("   1  Arches, Utah"));
+    assert!(out.contains("   2  Bryce Canyon, Utah"));
+    assert!(out.contains("   7* Bryce Canyon, Utah"));
+    assert!(out.contains(
+        "   1. Arches, Utah (home)\n   2. Capitol Reef, Utah\n   3. Bryce Canyon, Utah\n"
+    ));
+    let labels: Vec<_> = picker.selection().iter().map(|w| w.label()).collect();
+    assert_eq!(
+        labels,
+        vec!["Capitol Reef, Utah", "Arches, Utah", "Bryce Canyon, Utah"]
+    );
+}
+
+#[test]
+fn test_bad_input() {
+    let mut picker = Picker::new(None, Limits::default());
+    let out = session(&mut picker, "add 0\nadd two\ncategory lake\nfly\nplan\n");
+    assert!(out.contains("\"0\" is not a number from 1 to 93\n"));
+    assert!(out.contains("\"two\" is not a number from 1 to 93\n"));
+    assert!(out.contains("unknown category \"lake\""));
+    assert!(out.contains("unknown command \"fly\""));
+    assert!(out.contains("Select at least two waypoints first.\n"));
+    assert!(picker.selection().is_empty());
+}
+
+#[test]
+fn test_plan() {
+    let mut picker = Picker::new(None, Limits::default());
+    let out = session(
+        &mut picker,
+        "search arches\nadd 1\nsearch zion\nadd 1\nsearch bryce\nadd 1\nplan\n",
+    );
+    assert!(out.contains("Itinerary using straight-line distances:\n"));
+    assert!(out.contains("   1. Arches, Utah to "));
+    assert!(out.contains("   3. ") && out.contains(" to Arches, Utah: "));
+    assert!(out.contains("Total: "));
+    assert!(!out.contains("a limit was reached"));
+
+    // the bundled matrix only knows ten parks
+    let matrix = TravelMatrix::builtin("ten-parks").unwrap();
+    let mut picker = Picker::new(Some(matrix), Limits::default());
+    let out = session(
+        &mut picker,
+        "search arches\nadd 1\nsearch acadia\nadd 1\nplan\n",
+    );
+    assert!(out.contains("Can't plan this trip: no waypoint named \"Arches, Utah\"\n"));
+}
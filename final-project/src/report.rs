@@ -0,0 +1,219 @@
+use crate::route::Route;
+use crate::states::STATE_OUTLINES;
+use std::fmt::Write;
+use std::path::Path;
+
+const WIDTH: f64 = 960.0;
+const HEIGHT: f64 = 600.0;
+const PADDING: f64 = 20.0;
+
+/// Albers equal-area conic projection with the usual parameters for the
+/// lower 48 (standard parallels 29.5°N and 45.5°N, centred on 96°W).
+/// Returns unscaled `(x, y)` with y growing northwards.
+pub fn albers((lat, lon): (f64, f64)) -> (f64, f64) {
+    let (phi1, phi2) = (29.5f64.to_radians(), 45.5f64.to_radians());
+    let (phi0, lambda0) = (37.5f64.to_radians(), (-96.0f64).to_radians());
+    let n = (phi1.sin() + phi2.sin()) / 2.0;
+    let c = phi1.cos().powi(2) + 2.0 * n * phi1.sin();
+    let rho = |phi: f64| (c - 2.0 * n * phi.sin()).sqrt() / n;
+    let theta = n * (lon.to_radians() - lambda0);
+    let (r, r0) = (rho(lat.to_radians()), rho(phi0));
+    (r * theta.sin(), r0 - r * theta.cos())
+}
+
+/// Maps projected points onto the SVG canvas, keeping the aspect ratio.
+struct Viewport {
+    min: (f64, f64),
+    scale: f64,
+    offset: (f64, f64),
+}
+
+impl Viewport {
+    fn fit(points: &[(f64, f64)]) -> Self {
+        let min_x = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let max_x = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+        let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        let (w, h) = ((max_x - min_x).max(1e-9), (max_y - min_y).max(1e-9));
+        let scale = ((WIDTH - 2.0 * PADDING) / w).min((HEIGHT - 2.0 * PADDING) / h);
+        let offset = ((WIDTH - w * scale) / 2.0, (HEIGHT - h * scale) / 2.0);
+        Self {
+            min: (min_x, min_y),
+            scale,
+            offset,
+        }
+    }
+    /// Projects a `(lat, lon)` point to canvas coordinates, y growing downwards.
+    fn place(&self, at: (f64, f64)) -> (f64, f64) {
+        let (x, y) = albers(at);
+        (
+            self.offset.0 + (x - self.min.0) * self.scale,
+            HEIGHT - self.offset.1 - (y - self.min.1) * self.scale,
+        )
+    }
+    fn points(&self, line: &[(f64, f64)]) -> String {
+        line.iter()
+            .map(|&p| {
+                let (x, y) = self.place(p);
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Renders a route as a standalone HTML page with an inline SVG map of the
+/// lower 48, the route's legs and numbered stops, followed by a table of
+/// legs.  Nothing is fetched over the network, so the page opens offline.
+pub fn to_html(route: &Route, title: &str) -> String {
+    let mut bounds: Vec<_> = STATE_OUTLINES
+        .iter()
+        .flat_map(|(_, rings)| rings.iter().flat_map(|r| r.iter().cloned()))
+        .map(albers)
+        .collect();
+    bounds.extend(route.stops.iter().map(|s| albers(s.coords())));
+    let view = Viewport::fit(&bounds);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" role="img" aria-label="{}">"#,
+        WIDTH,
+        HEIGHT,
+        escape_markup(title)
+    )
+    .unwrap();
+    svg.push_str("<g class=\"states\">\n");
+    for (name, rings) in STATE_OUTLINES.iter() {
+        for ring in rings.iter() {
+            writeln!(
+                svg,
+                r#"<polygon points="{}"><title>{}</title></polygon>"#,
+                view.points(ring),
+                name
+            )
+            .unwrap();
+        }
+    }
+    svg.push_str("</g>\n<g class=\"legs\">\n");
+    for (i, leg) in route.legs.iter().enumerate() {
+        writeln!(
+            svg,
+            r#"<polyline points="{}"><title>Leg {}: {}</title></polyline>"#,
+            view.points(&route.leg_geometry(leg)),
+            i + 1,
+            escape_markup(&route.describe_leg(leg))
+        )
+        .unwrap();
+    }
+    svg.push_str("</g>\n<g class=\"stops\">\n");
+    for position in 0..route.stop_count() {
+        let stop = route.stop_at(position);
+        let (x, y) = view.place(stop.coords());
+        writeln!(
+            svg,
+            r#"<g><title>{}</title><circle cx="{:.1}" cy="{:.1}" r="9"/><text x="{:.1}" y="{:.1}">{}</text></g>"#,
+            escape_markup(&stop.label()),
+            x,
+            y,
+            x,
+            y + 4.0,
+            position + 1
+        )
+        .unwrap();
+    }
+    svg.push_str("</g>\n</svg>\n");
+
+    let mut table = String::new();
+    table.push_str(
+        "<table>\n<thead><tr><th>#</th><th>From</th><th>To</th><th>Distance</th><th>Drive time</th></tr></thead>\n<tbody>\n",
+    );
+    for (i, leg) in route.legs.iter().enumerate() {
+        writeln!(
+            table,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            i + 1,
+            escape_markup(&route.stops[leg.from].label()),
+            escape_markup(&route.stops[leg.to].label()),
+            format_distance(leg.distance as u64),
+            leg.duration
+                .map_or("&ndash;".to_string(), |d| format_duration(d as u64)),
+        )
+        .unwrap();
+    }
+    writeln!(
+        table,
+        "</tbody>\n<tfoot><tr><th colspan=\"3\">Total</th><th>{}</th><th>{}</th></tr></tfoot>\n</table>",
+        format_distance(route.total_distance()),
+        route
+            .total_duration()
+            .map_or("&ndash;".to_string(), format_duration),
+    )
+    .unwrap();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em auto; max-width: 1000px; color: #222; }}
+svg {{ width: 100%; height: auto; background: #eef4f8; }}
+.states polygon {{ fill: #f7f5ef; stroke: #b9b4a6; stroke-width: 0.8; }}
+.legs polyline {{ fill: none; stroke: #c0392b; stroke-width: 2.5; stroke-linejoin: round; }}
+.stops circle {{ fill: #1f4e79; stroke: #fff; stroke-width: 1.5; }}
+.stops text {{ fill: #fff; font-size: 10px; font-weight: bold; text-anchor: middle; }}
+table {{ border-collapse: collapse; width: 100%; margin-top: 1em; }}
+th, td {{ border-bottom: 1px solid #ddd; padding: 4px 8px; text-align: left; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{svg}{table}</body>
+</html>
+"#,
+        title = escape_markup(title),
+        svg = svg,
+        table = table
+    )
+}
+
+/// Writes the HTML report for a route to a file.
+pub fn write_html(route: &Route, title: &str, path: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::write(path, to_html(route, title))
+}
+
+/// Escapes text for use in HTML or XML content and attribute values.
+pub(crate) fn escape_markup(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Formats meters as kilometers and miles, e.g. `"418.6 km (260.1 mi)"`.
+pub fn format_distance(meters: u64) -> String {
+    format!(
+        "{:.1} km ({:.1} mi)",
+        meters as f64 / 1000.0,
+        meters as f64 / 1609.344
+    )
+}
+
+/// Formats seconds as hours and minutes, e.g. `"4h 05m"`.
+pub fn format_duration(seconds: u64) -> String {
+    let minutes = (seconds + 30) / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,57 @@
+// tests
+
+use super::*;
+use crate::opt::roadtrip;
+use crate::waypoint::{lookup, Category, Waypoint};
+
+fn sample_route() -> Route {
+    let stops: Vec<_> = ["Joshua Tree", "Death Valley", "White Sands"]
+        .iter()
+        .map(|n| lookup(n).unwrap())
+        .collect();
+    let distances = &[
+        (0, 1, 418564),
+        (0, 2, 1143520),
+        (1, 0, 418564),
+        (1, 2, 1354266),
+        (2, 0, 1143520),
+        (2, 1, 1354266),
+    ];
+    let (order, _) = roadtrip(3, distances);
+    Route::new(stops, order, distances)
+}
+
+#[test]
+fn test_report_is_offline() {
+    let html = to_html(&sample_route(), "Desert loop");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("src="));
+    assert!(!html.contains("href="));
+    assert!(html.matches("<polygon").count() >= STATE_OUTLINES.len());
+    assert_eq!(html.matches("<polyline").count(), 3);
+    assert_eq!(html.matches("<circle").count(), 3);
+    assert!(html.contains("<td>Death Valley, California</td>"));
+    assert!(html.contains(&format_distance(418564 + 1354266 + 1143520)));
+}
+
+#[test]
+fn test_escaping() {
+    let mut route = sample_route();
+    route.stops[1] = Waypoint::new("Bob's <Diner>", "Nevada", Category::City, 36.0, -115.0);
+    let html = to_html(&route, "R&R");
+    assert!(html.contains("<title>R&amp;R</title>"));
+    assert!(html.contains("Bob&apos;s &lt;Diner&gt;, Nevada"));
+    assert!(!html.contains("<Diner>"));
+}
+
+#[test]
+fn test_projection() {
+    // the projection is centred on 96W, 37.5N
+    let (x, y) = albers((37.5, -96.0));
+    assert!(x.abs() < 1e-9 && y.abs() < 1e-9);
+    // east is right and north is up
+    assert!(albers((37.5, -80.0)).0 > 0.0);
+    assert!(albers((45.0, -96.0)).1 > 0.0);
+    assert_eq!(format_duration(14_700), "4h 05m");
+}
@@ -0,0 +1,455 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::convert::TryInto;
+use std::fmt;
+use std::path::Path;
+
+/// Mean earth radius in meters, used for great-circle estimates.
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Magic bytes at the front of a binary road graph extract.
+const MAGIC: &[u8; 4] = b"RTG1";
+
+/// Great-circle distance in meters between two `(lat, lon)` points given in degrees.
+pub fn haversine(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().asin()
+}
+
+/// Something went wrong loading a road graph or routing over it.
+#[derive(Debug, PartialEq)]
+pub enum GraphError {
+    /// A line of a CSV extract could not be understood.
+    Parse { line: usize, message: String },
+    /// A binary extract was truncated or had the wrong header.
+    Binary(String),
+    /// An edge or waypoint named a node that doesn't exist.
+    UnknownNode(usize),
+    /// There is no road path between the two nodes.
+    Unreachable(usize, usize),
+    /// The shortest road path between the two nodes is too long for a leg.
+    TooLong(usize, usize),
+    /// The extract could not be read from disk.
+    Io(String),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            GraphError::Binary(message) => write!(f, "bad binary extract: {}", message),
+            GraphError::UnknownNode(n) => write!(f, "unknown node {}", n),
+            GraphError::Unreachable(a, b) => write!(f, "no road path from node {} to {}", a, b),
+            GraphError::TooLong(a, b) => write!(
+                f,
+                "the road path from node {} to {} is more than {} meters",
+                a,
+                b,
+                u32::MAX
+            ),
+            GraphError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// Which single-pair shortest path algorithm to use when building leg tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortestPath {
+    /// One full Dijkstra sweep per waypoint; cheapest when most pairs are needed.
+    Dijkstra,
+    /// Goal-directed search per pair, guided by great-circle distance.
+    AStar,
+}
+
+/// Shortest distances from one node and each node's predecessor on its
+/// path, as found by `RoadGraph::dijkstra`.
+pub type Sweep = (Vec<Option<u64>>, Vec<Option<usize>>);
+
+/// A directed road network: nodes carry coordinates, edges carry lengths in meters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoadGraph {
+    coords: Vec<(f64, f64)>,           // (lat, lon) per node
+    adjacency: Vec<Vec<(usize, u32)>>, // outgoing (node, meters) per node
+}
+
+impl RoadGraph {
+    /// Creates an empty road graph.
+    pub fn new() -> Self {
+        Self {
+            coords: Vec::new(),
+            adjacency: Vec::new(),
+        }
+    }
+    /// Adds a node at the given latitude and longitude and returns its index.
+    pub fn add_node(&mut self, lat: f64, lon: f64) -> usize {
+        self.coords.push((lat, lon));
+        self.adjacency.push(Vec::new());
+        self.coords.len() - 1
+    }
+    /// Adds a one-way edge of the given length in meters.
+    pub fn add_edge(&mut self, from: usize, to: usize, meters: u32) -> Result<(), GraphError> {
+        self.check(from)?;
+        self.check(to)?;
+        self.adjacency[from].push((to, meters));
+        Ok(())
+    }
+    /// Adds a two-way road of the given length in meters.
+    pub fn add_road(&mut self, a: usize, b: usize, meters: u32) -> Result<(), GraphError> {
+        self.add_edge(a, b, meters)?;
+        self.add_edge(b, a, meters)
+    }
+    /// Returns the number of nodes in the graph.
+    pub fn len(&self) -> usize {
+        self.coords.len()
+    }
+    /// Returns the `(lat, lon)` of a node.
+    pub fn coords(&self, node: usize) -> Result<(f64, f64), GraphError> {
+        self.check(node)?;
+        Ok(self.coords[node])
+    }
+    /// Fails if the graph has no such node.
+    fn check(&self, node: usize) -> Result<(), GraphError> {
+        if node < self.len() {
+            Ok(())
+        } else {
+            Err(GraphError::UnknownNode(node))
+        }
+    }
+    /// Returns the outgoing edges of a node as `(to, meters)` pairs.
+    pub fn edges_from(&self, node: usize) -> &[(usize, u32)] {
+        &self.adjacency[node]
+    }
+    /// Finds the node closest (as the crow flies) to the given point, for
+    /// snapping waypoints onto the network.  Nodes without real coordinates
+    /// are never closest.
+    pub fn nearest_node(&self, lat: f64, lon: f64) -> Option<usize> {
+        self.coords
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, haversine(*c, (lat, lon))))
+            .filter(|(_, d)| !d.is_nan())
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    /// Parses a CSV extract.  Each non-blank line is either
+    /// `node,<id>,<lat>,<lon>` or `edge,<from>,<to>,<meters>[,oneway]`;
+    /// lines starting with `#` are comments.  Node ids must be listed in
+    /// order starting at 0, and edges are two-way unless marked `oneway`.
+    pub fn from_csv(text: &str) -> Result<Self, GraphError> {
+        let mut graph = Self::new();
+        for (i, raw) in text.lines().enumerate() {
+            let line = i + 1;
+            let raw = raw.trim();
+            if raw.is_empty() || raw.starts_with('#') {
+                continue;
+            }
+            let err = |message: String| GraphError::Parse { line, message };
+            let fields: Vec<_> = raw.split(',').map(str::trim).collect();
+            match fields[0] {
+                "node" if fields.len() == 4 => {
+                    let id: usize = fields[1]
+                        .parse()
+                        .map_err(|_| err(format!("bad node id {:?}", fields[1])))?;
+                    if id != graph.len() {
+                        return Err(err(format!(
+                            "expected node {} but found {}",
+                            graph.len(),
+                            id
+                        )));
+                    }
+                    let lat: f64 = fields[2]
+                        .parse()
+                        .ok()
+                        .filter(|x: &f64| x.is_finite())
+                        .ok_or_else(|| err(format!("bad latitude {:?}", fields[2])))?;
+                    let lon: f64 = fields[3]
+                        .parse()
+                        .ok()
+                        .filter(|x: &f64| x.is_finite())
+                        .ok_or_else(|| err(format!("bad longitude {:?}", fields[3])))?;
+                    graph.add_node(lat, lon);
+                }
+                "edge" if fields.len() == 4 || fields.len() == 5 => {
+                    let mut ends = [0; 2];
+                    for (end, field) in ends.iter_mut().zip(fields[1..3].iter()) {
+                        *end = field
+                            .parse()
+                            .map_err(|_| err(format!("bad node id {:?}", field)))?;
+                    }
+                    let meters: u32 = fields[3]
+                        .parse()
+                        .map_err(|_| err(format!("bad length {:?}", fields[3])))?;
+                    let added = match fields.get(4) {
+                        None => graph.add_road(ends[0], ends[1], meters),
+                        Some(&"oneway") => graph.add_edge(ends[0], ends[1], meters),
+                        Some(other) => return Err(err(format!("unknown edge flag {:?}", other))),
+                    };
+                    added.map_err(|e| err(e.to_string()))?;
+                }
+                _ => return Err(err(format!("unrecognized record {:?}", raw))),
+            }
+        }
+        Ok(graph)
+    }
+    /// Reads a CSV extract from disk; see `from_csv`.
+    pub fn read_csv(path: impl AsRef<Path>) -> Result<Self, GraphError> {
+        let text = std::fs::read_to_string(path).map_err(|e| GraphError::Io(e.to_string()))?;
+        Self::from_csv(&text)
+    }
+
+    /// Parses a binary extract: the magic `RTG1`, a little-endian `u32`
+    /// node count, that many `(f64 lat, f64 lon)` pairs, a `u32` edge
+    /// count, and that many one-way `(u32 from, u32 to, u32 meters)` triples.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GraphError> {
+        if bytes.len() < 4 || &bytes[..4] != MAGIC {
+            return Err(GraphError::Binary("missing RTG1 header".to_string()));
+        }
+        let mut pos = 4;
+        let mut take = |n: usize| -> Result<&[u8], GraphError> {
+            let chunk = bytes
+                .get(pos..pos + n)
+                .ok_or_else(|| GraphError::Binary(format!("truncated at byte {}", pos)))?;
+            pos += n;
+            Ok(chunk)
+        };
+        let mut graph = Self::new();
+        let nodes = read_u32(take(4)?);
+        for _ in 0..nodes {
+            let lat = f64::from_le_bytes(take(8)?.try_into().unwrap());
+            let lon = f64::from_le_bytes(take(8)?.try_into().unwrap());
+            if !lat.is_finite() || !lon.is_finite() {
+                return Err(GraphError::Binary(format!(
+                    "node {} has coordinates ({}, {})",
+                    graph.len(),
+                    lat,
+                    lon
+                )));
+            }
+            graph.add_node(lat, lon);
+        }
+        let edges = read_u32(take(4)?);
+        for _ in 0..edges {
+            let from = read_u32(take(4)?) as usize;
+            let to = read_u32(take(4)?) as usize;
+            let meters = read_u32(take(4)?);
+            graph.add_edge(from, to, meters)?;
+        }
+        Ok(graph)
+    }
+    /// Reads a binary extract from disk; see `from_bytes`.
+    pub fn read_binary(path: impl AsRef<Path>) -> Result<Self, GraphError> {
+        let bytes = std::fs::read(path).map_err(|e| GraphError::Io(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+    /// Serializes the graph in the binary extract format read by `from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        for (lat, lon) in self.coords.iter() {
+            out.extend_from_slice(&lat.to_le_bytes());
+            out.extend_from_slice(&lon.to_le_bytes());
+        }
+        let edges: Vec<_> = self
+            .adjacency
+            .iter()
+            .enumerate()
+            .flat_map(|(from, out)| out.iter().map(move |(to, m)| (from, *to, *m)))
+            .collect();
+        out.extend_from_slice(&(edges.len() as u32).to_le_bytes());
+        for (from, to, meters) in edges {
+            out.extend_from_slice(&(from as u32).to_le_bytes());
+            out.extend_from_slice(&(to as u32).to_le_bytes());
+            out.extend_from_slice(&meters.to_le_bytes());
+        }
+        out
+    }
+
+    /// Single-source shortest paths.  Returns the distance to every node
+    /// (`None` if unreachable) and each node's predecessor on its path.
+    pub fn dijkstra(&self, source: usize) -> Result<Sweep, GraphError> {
+        self.check(source)?;
+        let mut dist = vec![None; self.len()];
+        let mut prev = vec![None; self.len()];
+        let mut heap = BinaryHeap::new();
+        dist[source] = Some(0);
+        heap.push(Reverse((0u64, source)));
+        while let Some(Reverse((d, node))) = heap.pop() {
+            if dist[node].is_some_and(|best| d > best) {
+                continue;
+            }
+            for &(next, meters) in self.adjacency[node].iter() {
+                let nd = d + meters as u64;
+                if dist[next].is_none_or(|best| nd < best) {
+                    dist[next] = Some(nd);
+                    prev[next] = Some(node);
+                    heap.push(Reverse((nd, next)));
+                }
+            }
+        }
+        Ok((dist, prev))
+    }
+
+    /// Shortest path between two nodes using A* with a great-circle
+    /// heuristic.  The heuristic is scaled down by the smallest ratio of edge
+    /// length to straight-line length in the graph so that it stays
+    /// admissible even if some extract under-reports a road's length.
+    /// Returns `None` if `to` can't be reached.
+    pub fn astar(&self, from: usize, to: usize) -> Result<Option<(u64, Vec<usize>)>, GraphError> {
+        self.check(from)?;
+        self.check(to)?;
+        Ok(self.astar_scaled(from, to, self.heuristic_scale()))
+    }
+
+    /// `astar` with the heuristic's scale worked out already, since that
+    /// takes a pass over every edge.
+    fn astar_scaled(&self, from: usize, to: usize, scale: f64) -> Option<(u64, Vec<usize>)> {
+        let goal = self.coords[to];
+        let h = |n: usize| (haversine(self.coords[n], goal) * scale) as u64;
+        let mut dist: Vec<Option<u64>> = vec![None; self.len()];
+        let mut prev = vec![None; self.len()];
+        let mut heap = BinaryHeap::new();
+        dist[from] = Some(0);
+        heap.push(Reverse((h(from), from)));
+        while let Some(Reverse((_, node))) = heap.pop() {
+            if node == to {
+                return Some((dist[to].unwrap(), unwind(&prev, from, to)));
+            }
+            let d = dist[node].unwrap();
+            for &(next, meters) in self.adjacency[node].iter() {
+                let nd = d + meters as u64;
+                if dist[next].is_none_or(|best| nd < best) {
+                    dist[next] = Some(nd);
+                    prev[next] = Some(node);
+                    heap.push(Reverse((nd + h(next), next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// The largest factor by which great-circle distance can be multiplied
+    /// while never exceeding the length of any edge; at most 1.
+    fn heuristic_scale(&self) -> f64 {
+        let mut scale: f64 = 1.0;
+        for (from, out) in self.adjacency.iter().enumerate() {
+            for &(to, meters) in out.iter() {
+                let straight = haversine(self.coords[from], self.coords[to]);
+                if straight > 0.0 {
+                    scale = scale.min(meters as f64 / straight);
+                }
+            }
+        }
+        // Round down a little so float error can't make the heuristic overestimate
+        scale * 0.999
+    }
+
+    /// Computes the shortest road path between every ordered pair of the
+    /// given waypoint nodes.  Waypoint `i` of the result is `waypoints[i]`,
+    /// so the table's `edges` line up with `roadtrip`'s vertex numbering.
+    /// Fails if a leg is longer than a `u32` holds.
+    pub fn legs(&self, waypoints: &[usize], method: ShortestPath) -> Result<LegTable, GraphError> {
+        for &w in waypoints {
+            self.check(w)?;
+        }
+        let scale = match method {
+            ShortestPath::Dijkstra => 1.0,
+            ShortestPath::AStar => self.heuristic_scale(),
+        };
+        let mut legs = HashMap::new();
+        for (i, &from) in waypoints.iter().enumerate() {
+            let sweep = match method {
+                ShortestPath::Dijkstra => Some(self.dijkstra(from)?),
+                ShortestPath::AStar => None,
+            };
+            for (j, &to) in waypoints.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let found = match &sweep {
+                    Some((dist, prev)) => dist[to].map(|d| (d, unwind(prev, from, to))),
+                    None => self.astar_scaled(from, to, scale),
+                };
+                let (meters, path) = found.ok_or(GraphError::Unreachable(from, to))?;
+                let meters = meters
+                    .try_into()
+                    .map_err(|_| GraphError::TooLong(from, to))?;
+                legs.insert((i, j), (meters, path));
+            }
+        }
+        Ok(LegTable {
+            waypoints: waypoints.to_vec(),
+            legs,
+        })
+    }
+}
+
+/// Shortest road legs between a chosen set of waypoints, along with the road
+/// path behind each leg so the route can be drawn afterwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegTable {
+    waypoints: Vec<usize>,                            // graph node per waypoint
+    legs: HashMap<(usize, usize), (u32, Vec<usize>)>, // (meters, graph nodes) per waypoint pair
+}
+
+impl LegTable {
+    /// Returns the number of waypoints.
+    pub fn len(&self) -> usize {
+        self.waypoints.len()
+    }
+    /// Returns the graph node each waypoint was snapped to.
+    pub fn waypoints(&self) -> &[usize] {
+        &self.waypoints
+    }
+    /// Returns the road distance of the leg between two waypoints.
+    pub fn cost(&self, from: usize, to: usize) -> Option<u32> {
+        self.legs.get(&(from, to)).map(|(m, _)| *m)
+    }
+    /// Returns the graph nodes along the leg between two waypoints, both
+    /// ends included.
+    pub fn path(&self, from: usize, to: usize) -> Option<&[usize]> {
+        self.legs.get(&(from, to)).map(|(_, p)| p.as_slice())
+    }
+    /// Returns the `(lat, lon)` polyline of the leg between two waypoints,
+    /// or `None` if there is no such leg or `graph` lacks one of its nodes.
+    pub fn geometry(&self, graph: &RoadGraph, from: usize, to: usize) -> Option<Vec<(f64, f64)>> {
+        self.path(from, to)?
+            .iter()
+            .map(|&n| graph.coords(n).ok())
+            .collect()
+    }
+    /// Returns every leg as a `(from, to, meters)` triple, ready to hand to `roadtrip`.
+    pub fn edges(&self) -> Vec<(usize, usize, u32)> {
+        let mut edges: Vec<_> = self
+            .legs
+            .iter()
+            .map(|(&(from, to), (m, _))| (from, to, *m))
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes.try_into().unwrap())
+}
+
+/// Follows predecessor links back from `to` and returns the path from `from`.
+fn unwind(prev: &[Option<usize>], from: usize, to: usize) -> Vec<usize> {
+    let mut path = vec![to];
+    let mut node = to;
+    while node != from {
+        node = prev[node].unwrap();
+        path.push(node);
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,140 @@
+// tests
+
+use super::*;
+use crate::opt::roadtrip;
+
+// A small grid of roads around four parks, with a slow mountain pass
+// between 0 and 3 that the shortest paths should route around.
+const GRID: &str = "\
+# four waypoints on the corners, two junctions in the middle
+node,0,40.0,-110.0
+node,1,40.0,-109.0
+node,2,41.0,-109.0
+node,3,41.0,-110.0
+node,4,40.5,-109.5
+node,5,40.5,-109.6
+edge,0,1,90000
+edge,1,2,115000
+edge,2,3,90000
+edge,3,0,400000
+edge,0,5,60000
+edge,5,4,9000
+edge,4,2,70000
+edge,4,3,70000
+edge,1,4,70000,oneway
+";
+
+#[test]
+fn test_csv_roundtrip() {
+    let graph = RoadGraph::from_csv(GRID).unwrap();
+    assert_eq!(graph.len(), 6);
+    assert_eq!(graph.edges_from(1), &[(0, 90000), (2, 115000), (4, 70000)]);
+    assert_eq!(RoadGraph::from_bytes(&graph.to_bytes()).unwrap(), graph);
+}
+
+#[test]
+fn test_csv_errors() {
+    assert_eq!(
+        RoadGraph::from_csv("node,0,40,-110\nnode,2,41,-110\n"),
+        Err(GraphError::Parse {
+            line: 2,
+            message: "expected node 1 but found 2".to_string()
+        })
+    );
+    assert!(RoadGraph::from_csv("node,0,40,-110\nedge,0,7,10\n").is_err());
+    assert!(RoadGraph::from_bytes(b"RTG1\x05\x00").is_err());
+}
+
+#[test]
+fn test_non_finite_coordinates() {
+    assert_eq!(
+        RoadGraph::from_csv("node,0,NaN,-110\n"),
+        Err(GraphError::Parse {
+            line: 1,
+            message: "bad latitude \"NaN\"".to_string()
+        })
+    );
+    let mut graph = RoadGraph::new();
+    graph.add_node(40.0, f64::INFINITY);
+    assert!(RoadGraph::from_bytes(&graph.to_bytes()).is_err());
+    // a graph built by hand can still hold one, and is searched around it
+    graph.add_node(f64::NAN, -110.0);
+    graph.add_node(40.0, -110.0);
+    assert_eq!(graph.nearest_node(40.1, -110.0), Some(2));
+}
+
+#[test]
+fn test_dijkstra_matches_astar() {
+    let graph = RoadGraph::from_csv(GRID).unwrap();
+    let corners = [0, 1, 2, 3];
+    let by_dijkstra = graph.legs(&corners, ShortestPath::Dijkstra).unwrap();
+    let by_astar = graph.legs(&corners, ShortestPath::AStar).unwrap();
+    assert_eq!(by_dijkstra.edges(), by_astar.edges());
+    // the pass is avoided in favour of the junctions in the middle
+    assert_eq!(by_dijkstra.cost(0, 3), Some(139000));
+    assert_eq!(by_dijkstra.path(0, 3), Some(&[0, 5, 4, 3][..]));
+    // the one-way ramp only helps in one direction
+    assert_eq!(by_dijkstra.cost(1, 3), Some(140000));
+    assert_eq!(by_dijkstra.cost(3, 1), Some(205000));
+}
+
+#[test]
+fn test_legs_feed_roadtrip() {
+    let graph = RoadGraph::from_csv(GRID).unwrap();
+    let legs = graph.legs(&[0, 1, 2, 3], ShortestPath::Dijkstra).unwrap();
+    let (order, cost) = roadtrip(legs.len(), &legs.edges());
+    assert_eq!(order.first(), Some(&0));
+    assert_eq!(order.last(), Some(&0));
+    assert_eq!(cost, 90000 + 115000 + 90000 + 139000);
+    let geometry = legs.geometry(&graph, 3, 0).unwrap();
+    assert_eq!(geometry.len(), 4);
+    assert_eq!(geometry[0], (41.0, -110.0));
+}
+
+#[test]
+fn test_unreachable() {
+    let mut graph = RoadGraph::new();
+    let a = graph.add_node(40.0, -100.0);
+    let b = graph.add_node(41.0, -100.0);
+    graph.add_edge(a, b, 120000).unwrap();
+    assert_eq!(
+        graph.legs(&[a, b], ShortestPath::AStar),
+        Err(GraphError::Unreachable(b, a))
+    );
+    assert_eq!(graph.nearest_node(40.9, -100.1), Some(b));
+}
+
+#[test]
+fn test_unknown_nodes() {
+    let graph = RoadGraph::from_csv(GRID).unwrap();
+    assert_eq!(graph.coords(6), Err(GraphError::UnknownNode(6)));
+    assert_eq!(graph.dijkstra(9), Err(GraphError::UnknownNode(9)));
+    assert_eq!(graph.astar(0, 7), Err(GraphError::UnknownNode(7)));
+    assert_eq!(
+        graph.legs(&[0, 8], ShortestPath::AStar),
+        Err(GraphError::UnknownNode(8))
+    );
+    assert_eq!(graph.coords(3), Ok((41.0, -110.0)));
+    assert_eq!(graph.astar(0, 3).unwrap().map(|(m, _)| m), Some(139000));
+}
+
+#[test]
+fn test_leg_too_long() {
+    // two roads of 3 billion meters make a path no u32 holds
+    let mut graph = RoadGraph::new();
+    let a = graph.add_node(40.0, -100.0);
+    let b = graph.add_node(41.0, -100.0);
+    let c = graph.add_node(42.0, -100.0);
+    graph.add_road(a, b, 3_000_000_000).unwrap();
+    graph.add_road(b, c, 3_000_000_000).unwrap();
+    for method in [ShortestPath::Dijkstra, ShortestPath::AStar].iter() {
+        assert_eq!(graph.legs(&[a, c], *method), Err(GraphError::TooLong(a, c)));
+    }
+    assert_eq!(
+        graph
+            .legs(&[a, b], ShortestPath::Dijkstra)
+            .unwrap()
+            .cost(0, 1),
+        Some(3_000_000_000)
+    );
+}
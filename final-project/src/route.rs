@@ -0,0 +1,157 @@
+use crate::report::{format_distance, format_duration};
+use crate::roadgraph::{LegTable, RoadGraph};
+use crate::waypoint::Waypoint;
+use std::collections::HashMap;
+
+/// One drive between consecutive stops of a route.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Leg {
+    /// Index into `Route::stops` of where the leg starts.
+    pub from: usize,
+    /// Index into `Route::stops` of where the leg ends.
+    pub to: usize,
+    /// Length in meters.
+    pub distance: u32,
+    /// Driving time in seconds, if known.
+    pub duration: Option<u32>,
+    /// `(lat, lon)` road geometry, if known.
+    pub geometry: Option<Vec<(f64, f64)>>,
+}
+
+/// A planned route: the stops, the order `roadtrip` chose for them, and
+/// what is known about each leg.  This is what the exporters consume.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Route {
+    pub stops: Vec<Waypoint>,
+    pub order: Vec<usize>,
+    pub legs: Vec<Leg>,
+}
+
+impl Route {
+    /// Builds a route from `roadtrip`'s order over `stops`, looking up leg
+    /// distances in the same edge list `roadtrip` was given.  Panics if a
+    /// leg of the order has no edge.
+    pub fn new(stops: Vec<Waypoint>, order: Vec<usize>, distances: &[(usize, usize, u32)]) -> Self {
+        let lookup = edge_map(distances);
+        let legs = order
+            .windows(2)
+            .map(|w| Leg {
+                from: w[0],
+                to: w[1],
+                distance: lookup[&(w[0], w[1])],
+                duration: None,
+                geometry: None,
+            })
+            .collect();
+        Self { stops, order, legs }
+    }
+    /// Fills in leg durations from a `(from, to, seconds)` edge list.
+    pub fn add_durations(&mut self, durations: &[(usize, usize, u32)]) {
+        let lookup = edge_map(durations);
+        for leg in self.legs.iter_mut() {
+            leg.duration = lookup.get(&(leg.from, leg.to)).cloned();
+        }
+    }
+    /// Fills in leg geometry from the road paths of a leg table built over
+    /// the same stops.
+    pub fn add_geometry(&mut self, graph: &RoadGraph, table: &LegTable) {
+        for leg in self.legs.iter_mut() {
+            leg.geometry = table.geometry(graph, leg.from, leg.to);
+        }
+    }
+    /// Returns whether the route ends where it started.
+    pub fn is_closed(&self) -> bool {
+        self.order.len() > 1 && self.order.first() == self.order.last()
+    }
+    /// Returns how many positions of the route are distinct stops, leaving
+    /// out a closed route's return home.
+    pub fn stop_count(&self) -> usize {
+        if self.is_closed() {
+            self.order.len() - 1
+        } else {
+            self.order.len()
+        }
+    }
+    /// Returns the stop at the given position of the route.
+    pub fn stop_at(&self, position: usize) -> &Waypoint {
+        &self.stops[self.order[position]]
+    }
+    /// Returns the total length in meters.
+    pub fn total_distance(&self) -> u64 {
+        self.legs.iter().map(|l| l.distance as u64).sum()
+    }
+    /// Returns the total driving time in seconds, if every leg's is known.
+    pub fn total_duration(&self) -> Option<u64> {
+        self.legs.iter().map(|l| l.duration.map(u64::from)).sum()
+    }
+    /// Returns the distance and (if known) driving time already covered on
+    /// arrival at each position of the route.
+    pub fn arrivals(&self) -> Vec<(u64, Option<u64>)> {
+        let mut at = (0, Some(0));
+        let mut out = vec![at];
+        for leg in self.legs.iter() {
+            at.0 += leg.distance as u64;
+            at.1 = match (at.1, leg.duration) {
+                (Some(t), Some(d)) => Some(t + d as u64),
+                _ => None,
+            };
+            out.push(at);
+        }
+        out
+    }
+    /// Describes the stop at a position for the exporters, e.g.
+    /// `"Stop 2 of 3, a park in California. Arrive after 418.6 km (260.1 mi), 4h 10m."`
+    /// A closed route's last position is described as the return home.
+    pub fn describe_stop(&self, position: usize) -> String {
+        let stop = self.stop_at(position);
+        let mut text = if position == self.stop_count() {
+            format!("Back home at {}.", stop.label())
+        } else {
+            format!(
+                "Stop {} of {}, a {} in {}.",
+                position + 1,
+                self.stop_count(),
+                stop.category,
+                stop.state
+            )
+        };
+        if position > 0 {
+            let (distance, duration) = self.arrivals()[position];
+            text.push_str(&format!(" Arrive after {}", format_distance(distance)));
+            if let Some(d) = duration {
+                text.push_str(&format!(", {}", format_duration(d)));
+            }
+            text.push('.');
+        }
+        text
+    }
+    /// Describes a leg for the exporters, e.g. `"Joshua Tree, California to
+    /// Death Valley, California: 418.6 km (260.1 mi), 4h 10m"`.
+    pub fn describe_leg(&self, leg: &Leg) -> String {
+        let mut text = format!(
+            "{} to {}: {}",
+            self.stops[leg.from].label(),
+            self.stops[leg.to].label(),
+            format_distance(leg.distance as u64)
+        );
+        if let Some(d) = leg.duration {
+            text.push_str(&format!(", {}", format_duration(d as u64)));
+        }
+        text
+    }
+    /// Returns the leg's road geometry, or a straight line between its stops
+    /// when none is known.
+    pub fn leg_geometry(&self, leg: &Leg) -> Vec<(f64, f64)> {
+        match &leg.geometry {
+            Some(g) => g.clone(),
+            None => vec![self.stops[leg.from].coords(), self.stops[leg.to].coords()],
+        }
+    }
+}
+
+fn edge_map(edges: &[(usize, usize, u32)]) -> HashMap<(usize, usize), u32> {
+    edges
+        .iter()
+        .map(|(from, to, cost)| ((*from, *to), *cost))
+        .collect()
+}
@@ -0,0 +1,607 @@
+use crate::csp::{Limits, Observer};
+use crate::geojson;
+use crate::json::Json;
+use crate::opt::TripOutcome;
+use crate::spec::{MatrixSource, SpecError, Trip, TripSpec};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Largest request body accepted, in bytes.
+const MAX_BODY: usize = 1 << 20;
+
+/// Longest request or header line accepted, in bytes.
+const MAX_LINE: usize = 8 << 10;
+
+/// Most header lines a request may have.
+const MAX_HEADERS: usize = 100;
+
+/// How long a client has to send its whole request.
+const READ_TIME: Duration = Duration::from_secs(10);
+
+/// Connections read and answered at once; more that arrive while all of
+/// these are busy are turned away.
+const CONNECTIONS: usize = 8;
+
+/// How often, in search nodes, a running job publishes its node count.
+const REPORT_EVERY: usize = 1024;
+
+/// How the planning service runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerConfig {
+    /// Jobs solved at once, each on its own thread.
+    pub workers: usize,
+    /// Jobs that may wait for a worker; submissions beyond that are refused.
+    pub queue: usize,
+    /// Search limits for every job.
+    pub limits: Limits,
+    /// Finished and cancelled jobs that are kept for fetching; beyond that
+    /// the oldest are forgotten.
+    pub keep: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            queue: 16,
+            limits: Limits {
+                max_nodes: None,
+                time_limit: Some(Duration::from_secs(60)),
+            },
+            keep: 100,
+        }
+    }
+}
+
+/// Where a job is in its life.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Cancelled,
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Done => "done",
+            JobState::Cancelled => "cancelled",
+        })
+    }
+}
+
+struct Job {
+    name: Option<String>,
+    trip: Arc<Trip>,
+    state: JobState,
+    cancel: Arc<AtomicBool>,
+    submitted: Instant,
+    finished: Option<Instant>,
+    /// Cost of the best plan found so far.
+    incumbent: Option<u32>,
+    nodes: usize,
+    outcome: Option<TripOutcome>,
+    /// Why a finished job has no outcome.
+    error: Option<String>,
+}
+
+struct Table {
+    next_id: u64,
+    jobs: BTreeMap<u64, Job>,
+    keep: usize,
+}
+
+impl Table {
+    /// Forgets the oldest finished jobs beyond the `keep` newest.
+    fn prune(&mut self) {
+        let finished: Vec<u64> = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.finished.is_some())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in finished
+            .iter()
+            .take(finished.len().saturating_sub(self.keep))
+        {
+            self.jobs.remove(id);
+        }
+    }
+}
+
+type SharedTable = Arc<Mutex<Table>>;
+
+/// An HTTP request, as much of it as the service looks at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// The path without any query string.
+    pub path: String,
+    pub body: String,
+}
+
+/// An HTTP response.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: Json) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.pretty() + "\n",
+        }
+    }
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, Json::object(vec![("error", Json::str(message))]))
+    }
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            422 => "Unprocessable Entity",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Follows a running job for its worker: publishes each better plan's cost
+/// and the node count, and stops the search once the job is cancelled.
+struct Watcher {
+    id: u64,
+    table: SharedTable,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Observer<u32> for Watcher {
+    fn improved(&mut self, cost: &u32) {
+        if let Some(job) = self.table.lock().unwrap().jobs.get_mut(&self.id) {
+            job.incumbent = Some(*cost);
+        }
+    }
+    fn should_stop(&mut self, nodes: usize) -> bool {
+        if nodes.is_multiple_of(REPORT_EVERY) {
+            if let Some(job) = self.table.lock().unwrap().jobs.get_mut(&self.id) {
+                job.nodes = nodes;
+            }
+        }
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+/// The planning service: a table of jobs, a bounded queue and the pool of
+/// workers that solve them.  `handle` answers one request; `serve` wires
+/// it to a socket.
+///
+/// Endpoints, all JSON:
+///
+/// - `POST /jobs` with a trip spec (see `spec.rs`) queues a job; the spec
+///   may use a bundled or straight-line matrix but not a file
+/// - `GET /jobs` lists jobs, `GET /jobs/ID` shows one with its incumbent cost
+/// - `GET /jobs/ID/result` and `GET /jobs/ID/geojson` fetch a finished plan
+/// - `DELETE /jobs/ID` cancels a job, keeping the best plan found so far
+///
+/// Only the newest `ServerConfig::keep` finished jobs are kept.
+pub struct Service {
+    table: SharedTable,
+    queue: SyncSender<u64>,
+    workers: usize,
+}
+
+impl Service {
+    /// Starts the workers.  They run until the service is dropped.
+    pub fn new(config: &ServerConfig) -> Self {
+        let table = Arc::new(Mutex::new(Table {
+            next_id: 0,
+            jobs: BTreeMap::new(),
+            keep: config.keep,
+        }));
+        let (queue, jobs) = sync_channel(config.queue);
+        let jobs = Arc::new(Mutex::new(jobs));
+        for _ in 0..config.workers.max(1) {
+            let (table, jobs, limits) = (table.clone(), jobs.clone(), config.limits.clone());
+            thread::spawn(move || work(&table, &jobs, &limits));
+        }
+        Self {
+            table,
+            queue,
+            workers: config.workers.max(1),
+        }
+    }
+
+    /// Answers one request.
+    pub fn handle(&self, request: &Request) -> Response {
+        let parts: Vec<_> = request
+            .path
+            .trim_matches('/')
+            .split('/')
+            .filter(|p| !p.is_empty())
+            .collect();
+        let method = request.method.as_str();
+        match parts.as_slice() {
+            ["health"] => match method {
+                "GET" => Response::json(
+                    200,
+                    Json::object(vec![
+                        ("status", Json::str("ok")),
+                        ("workers", self.workers.into()),
+                    ]),
+                ),
+                _ => Response::error(405, "use GET"),
+            },
+            ["jobs"] => match method {
+                "GET" => {
+                    let table = self.table.lock().unwrap();
+                    let jobs = table.jobs.iter().map(|(&id, job)| status(id, job));
+                    Response::json(200, Json::Array(jobs.collect()))
+                }
+                "POST" => self.submit(&request.body),
+                _ => Response::error(405, "use GET or POST"),
+            },
+            ["jobs", id, rest @ ..] => {
+                let id = match id.parse::<u64>() {
+                    Ok(id) => id,
+                    Err(_) => return Response::error(404, "no such job"),
+                };
+                let mut table = self.table.lock().unwrap();
+                let job = match table.jobs.get_mut(&id) {
+                    Some(job) => job,
+                    None => return Response::error(404, "no such job"),
+                };
+                match (method, rest) {
+                    ("GET", []) => Response::json(200, status(id, job)),
+                    ("DELETE", []) => {
+                        let response = cancel(id, job);
+                        table.prune();
+                        response
+                    }
+                    ("GET", ["result"]) => result(id, job, false),
+                    ("GET", ["geojson"]) => result(id, job, true),
+                    (_, []) => Response::error(405, "use GET or DELETE"),
+                    (_, ["result"]) | (_, ["geojson"]) => Response::error(405, "use GET"),
+                    _ => Response::error(404, "no such endpoint"),
+                }
+            }
+            _ => Response::error(404, "no such endpoint"),
+        }
+    }
+
+    fn submit(&self, body: &str) -> Response {
+        let spec = match TripSpec::from_json(body) {
+            Ok(spec) => spec,
+            Err(e) => return spec_error(&e),
+        };
+        // a client mustn't make the service open files on its machine
+        if let MatrixSource::File(_) = spec.matrix {
+            return spec_error(&SpecError::new(
+                "matrix.file",
+                "the service can't read matrix files; use builtin or straight_line",
+            ));
+        }
+        let trip = match spec.resolve() {
+            Ok(trip) => trip,
+            Err(e) => return spec_error(&e),
+        };
+        let mut table = self.table.lock().unwrap();
+        let id = table.next_id;
+        // hold the lock until the job is queued so a worker can't miss it
+        if let Err(e) = self.queue.try_send(id) {
+            return match e {
+                TrySendError::Full(_) => {
+                    Response::error(503, "too many jobs are waiting; try again later")
+                }
+                TrySendError::Disconnected(_) => Response::error(500, "the workers have stopped"),
+            };
+        }
+        table.next_id += 1;
+        let job = Job {
+            name: spec.name,
+            trip: Arc::new(trip),
+            state: JobState::Queued,
+            cancel: Arc::new(AtomicBool::new(false)),
+            submitted: Instant::now(),
+            finished: None,
+            incumbent: None,
+            nodes: 0,
+            outcome: None,
+            error: None,
+        };
+        let reply = status(id, &job);
+        table.jobs.insert(id, job);
+        Response::json(202, reply)
+    }
+}
+
+/// A worker: takes job ids off the queue and solves them until the queue
+/// is closed.
+fn work(table: &SharedTable, jobs: &Mutex<Receiver<u64>>, limits: &Limits) {
+    loop {
+        let id = match jobs.lock().unwrap().recv() {
+            Ok(id) => id,
+            Err(_) => return,
+        };
+        let (trip, cancel) = {
+            let mut table = table.lock().unwrap();
+            match table.jobs.get_mut(&id) {
+                Some(job) if job.state == JobState::Queued => {
+                    job.state = JobState::Running;
+                    (job.trip.clone(), job.cancel.clone())
+                }
+                // cancelled while it waited
+                _ => continue,
+            }
+        };
+        let mut watcher = Watcher {
+            id,
+            table: table.clone(),
+            cancel: cancel.clone(),
+        };
+        let outcome = trip.roadtrip.solve_checked(limits, &mut watcher);
+        let mut table = table.lock().unwrap();
+        if let Some(job) = table.jobs.get_mut(&id) {
+            job.state = if cancel.load(Ordering::Relaxed) {
+                JobState::Cancelled
+            } else {
+                JobState::Done
+            };
+            job.finished = Some(Instant::now());
+            match outcome {
+                Ok(outcome) => {
+                    job.nodes = outcome.nodes;
+                    job.incumbent = outcome.plan.as_ref().map(|p| p.cost);
+                    job.outcome = Some(outcome);
+                }
+                Err(e) => job.error = Some(e.to_string()),
+            }
+        }
+        table.prune();
+    }
+}
+
+fn spec_error(e: &SpecError) -> Response {
+    Response::json(
+        400,
+        Json::object(vec![
+            ("error", Json::str(&e.to_string())),
+            ("field", Json::str(&e.field)),
+        ]),
+    )
+}
+
+fn status(id: u64, job: &Job) -> Json {
+    let mut fields = vec![("id", Json::Number(id as f64))];
+    if let Some(name) = &job.name {
+        fields.push(("name", Json::str(name)));
+    }
+    fields.push(("state", Json::str(&job.state.to_string())));
+    fields.push((
+        "incumbent_cost",
+        job.incumbent.map_or(Json::Null, Json::from),
+    ));
+    fields.push(("nodes", job.nodes.into()));
+    let end = job.finished.unwrap_or_else(Instant::now);
+    fields.push(("seconds", (end - job.submitted).as_secs_f64().into()));
+    if let Some(outcome) = &job.outcome {
+        fields.push(("status", Json::str(&outcome.status.to_string())));
+    }
+    if let Some(error) = &job.error {
+        fields.push(("error", Json::str(error)));
+    }
+    Json::object(fields)
+}
+
+fn cancel(id: u64, job: &mut Job) -> Response {
+    match job.state {
+        JobState::Queued => {
+            job.state = JobState::Cancelled;
+            job.finished = Some(Instant::now());
+        }
+        JobState::Running => job.cancel.store(true, Ordering::Relaxed),
+        JobState::Done | JobState::Cancelled => {
+            return Response::error(409, "the job has already finished")
+        }
+    }
+    Response::json(202, status(id, job))
+}
+
+fn result(id: u64, job: &Job, geojson: bool) -> Response {
+    let outcome = match (&job.outcome, &job.error) {
+        (Some(outcome), _) => outcome,
+        (None, Some(error)) => return Response::error(422, error),
+        (None, None) if job.state == JobState::Cancelled => {
+            return Response::error(404, "the job was cancelled before it started")
+        }
+        (None, None) => return Response::error(409, "the job hasn't finished; poll /jobs/ID"),
+    };
+    if !geojson {
+        let mut fields = vec![
+            ("id", Json::Number(id as f64)),
+            ("state", Json::str(&job.state.to_string())),
+        ];
+        fields.extend(job.trip.outcome_fields(outcome));
+        return Response::json(200, Json::object(fields));
+    }
+    let plan = match &outcome.plan {
+        Some(plan) => plan,
+        None => return Response::error(404, "no plan was found"),
+    };
+    match job.trip.route(&plan.driven_order()) {
+        Some(route) => Response {
+            status: 200,
+            content_type: "application/geo+json",
+            body: geojson::feature_collection(&route).pretty() + "\n",
+        },
+        None => Response::error(422, "some waypoints have no known coordinates"),
+    }
+}
+
+/// Reads from a connection until a deadline, however slowly the client
+/// trickles its bytes in.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left == Duration::ZERO {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Reads one request from a connection, which has `time` to send all of
+/// it.  Errors come back as the response to send.
+fn read_request(stream: &TcpStream, time: Duration) -> Result<Request, Response> {
+    let failed = |e: io::Error, message: &str| match e.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            Response::error(408, "the request took too long to send")
+        }
+        _ => Response::error(400, message),
+    };
+    let mut reader = BufReader::new(Deadline {
+        stream,
+        until: Instant::now() + time,
+    });
+    let mut read_line = |what: &str| -> Result<String, Response> {
+        let mut line = String::new();
+        reader
+            .by_ref()
+            .take(MAX_LINE as u64 + 1)
+            .read_line(&mut line)
+            .map_err(|e| failed(e, &format!("couldn't read the {}", what)))?;
+        if line.len() > MAX_LINE {
+            return Err(Response::error(431, &format!("the {} is too long", what)));
+        }
+        Ok(line)
+    };
+    let line = read_line("request line")?;
+    let mut words = line.split_whitespace();
+    let (method, target) = match (words.next(), words.next()) {
+        (Some(m), Some(t)) => (m.to_string(), t),
+        _ => return Err(Response::error(400, "malformed request line")),
+    };
+    let path = target.split('?').next().unwrap().to_string();
+
+    let mut length = 0;
+    for count in 0.. {
+        if count == MAX_HEADERS {
+            return Err(Response::error(431, "there are too many headers"));
+        }
+        let header = read_line("headers")?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::error(400, "bad Content-Length"))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(Response::error(413, "the request body is too large"));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| failed(e, "the request body was cut short"))?;
+    let body = String::from_utf8(body)
+        .map_err(|_| Response::error(400, "the request body isn't UTF-8"))?;
+    Ok(Request { method, path, body })
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Answers requests on a listener until it fails.  Only loopback listeners
+/// are accepted, and connections from elsewhere are dropped, so the
+/// service is never reachable from another machine.
+pub fn serve(listener: TcpListener, config: &ServerConfig) -> io::Result<()> {
+    if !listener.local_addr()?.ip().is_loopback() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the planning service only listens on localhost",
+        ));
+    }
+    let service = Arc::new(Service::new(config));
+    // a pool of threads reads the requests, so a client that sends slowly
+    // holds up only its own thread until its deadline
+    let (connections, waiting) = sync_channel(CONNECTIONS);
+    let waiting = Arc::new(Mutex::new(waiting));
+    for _ in 0..CONNECTIONS {
+        let (service, waiting) = (service.clone(), waiting.clone());
+        thread::spawn(move || answer(&service, &waiting));
+    }
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        if !stream.peer_addr().is_ok_and(|a| a.ip().is_loopback()) {
+            continue;
+        }
+        if let Err(TrySendError::Full(mut stream)) = connections.try_send(stream) {
+            let busy = Response::error(503, "too many connections; try again later");
+            let _ = write_response(&mut stream, &busy);
+        }
+    }
+    Ok(())
+}
+
+/// A connection thread: answers one request on each connection handed to
+/// it until `serve` stops handing them out.
+fn answer(service: &Service, waiting: &Mutex<Receiver<TcpStream>>) {
+    loop {
+        let mut stream = match waiting.lock().unwrap().recv() {
+            Ok(stream) => stream,
+            Err(_) => return,
+        };
+        let response = match read_request(&stream, READ_TIME) {
+            Ok(request) => service.handle(&request),
+            Err(response) => response,
+        };
+        let _ = write_response(&mut stream, &response);
+    }
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,219 @@
+// tests
+
+use super::*;
+use std::net::TcpStream;
+
+const EXAMPLE: &str = include_str!("../../example-trip.json");
+
+/// A trip through thirteen parks, which takes a while to prove optimal.
+const LONG: &str = r#"{"version": 1, "matrix": {"straight_line": {}}, "home": "Acadia",
+    "waypoints": ["Arches", "Badlands", "Big Bend", "Biscayne", "Bryce Canyon", "Canyonlands",
+    "Capitol Reef", "Carlsbad Caverns", "Congaree", "Crater Lake", "Death Valley", "Everglades"]}"#;
+
+fn request(service: &Service, method: &str, path: &str, body: &str) -> (u16, Json) {
+    let response = service.handle(&Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body: body.to_string(),
+    });
+    (response.status, Json::parse(&response.body).unwrap())
+}
+
+/// Polls a job until it has finished.
+fn wait(service: &Service, id: u64) -> Json {
+    for _ in 0..500 {
+        let (_, job) = request(service, "GET", &format!("/jobs/{}", id), "");
+        if job.get("status").is_some() {
+            return job;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("job {} never finished", id);
+}
+
+#[test]
+fn test_submit_and_fetch() {
+    let service = Service::new(&ServerConfig::default());
+    let (code, job) = request(&service, "POST", "/jobs", EXAMPLE);
+    assert_eq!(code, 202);
+    assert_eq!(job.get("id"), Some(&Json::Number(0.0)));
+
+    let job = wait(&service, 0);
+    assert_eq!(job.get("state"), Some(&Json::str("done")));
+    assert_eq!(job.get("status"), Some(&Json::str("optimal")));
+    let cost = job.get("incumbent_cost").cloned().unwrap();
+
+    let (code, result) = request(&service, "GET", "/jobs/0/result", "");
+    assert_eq!(code, 200);
+    assert_eq!(result.get("cost"), Some(&cost));
+    assert_eq!(
+        result.get("skipped"),
+        Some(&Json::Array(vec![
+            Json::str("Mesa Verde, Colorado"),
+            Json::str("Great Basin, Nevada")
+        ]))
+    );
+    let (code, geo) = request(&service, "GET", "/jobs/0/geojson", "");
+    assert_eq!(code, 200);
+    assert_eq!(geo.get("type"), Some(&Json::str("FeatureCollection")));
+
+    let (code, jobs) = request(&service, "GET", "/jobs", "");
+    assert_eq!((code, jobs.as_array().map(|j| j.len())), (200, Some(1)));
+}
+
+#[test]
+fn test_errors() {
+    let service = Service::new(&ServerConfig::default());
+    let (code, error) = request(
+        &service,
+        "POST",
+        "/jobs",
+        r#"{"version": 1, "matrix": {"builtin": "ten-parks"}, "home": "Acadia", "waypoints": ["Zion"]}"#,
+    );
+    assert_eq!(code, 400);
+    assert_eq!(error.get("field"), Some(&Json::str("waypoints[0]")));
+    // a matrix file would let a client read files on the server
+    let (code, error) = request(
+        &service,
+        "POST",
+        "/jobs",
+        r#"{"version": 1, "matrix": {"file": "/etc/passwd"}, "home": "root", "waypoints": ["daemon"]}"#,
+    );
+    assert_eq!(code, 400);
+    assert_eq!(error.get("field"), Some(&Json::str("matrix.file")));
+    assert!(!error.to_string().contains("passwd"));
+    assert_eq!(request(&service, "GET", "/jobs/7", "").0, 404);
+    assert_eq!(request(&service, "GET", "/jobs/x/result", "").0, 404);
+    assert_eq!(request(&service, "PUT", "/jobs", "").0, 405);
+    assert_eq!(request(&service, "GET", "/trips", "").0, 404);
+}
+
+#[test]
+fn test_deeply_nested_body() {
+    let service = Service::new(&ServerConfig::default());
+    let (code, error) = request(&service, "POST", "/jobs", &"[".repeat(100_000));
+    assert_eq!(code, 400);
+    assert!(error
+        .get("error")
+        .and_then(Json::as_str)
+        .is_some_and(|e| e.contains("nested more than 128 deep")));
+    assert_eq!(request(&service, "GET", "/health", "").0, 200);
+}
+
+#[test]
+fn test_keep_finished_jobs() {
+    let service = Service::new(&ServerConfig {
+        keep: 1,
+        ..ServerConfig::default()
+    });
+    for id in 0..2 {
+        assert_eq!(request(&service, "POST", "/jobs", EXAMPLE).0, 202);
+        wait(&service, id);
+    }
+    let (_, jobs) = request(&service, "GET", "/jobs", "");
+    assert_eq!(jobs.as_array().map(|j| j.len()), Some(1));
+    assert_eq!(request(&service, "GET", "/jobs/0", "").0, 404);
+    assert_eq!(request(&service, "GET", "/jobs/1/result", "").0, 200);
+}
+
+#[test]
+fn test_cancel_and_queue_limit() {
+    let service = Service::new(&ServerConfig {
+        workers: 1,
+        queue: 1,
+        limits: Limits::default(),
+        ..ServerConfig::default()
+    });
+    assert_eq!(request(&service, "POST", "/jobs", LONG).0, 202);
+    // wait for the worker to pick the first job up, so the second waits
+    while request(&service, "GET", "/jobs/0", "").1.get("state") != Some(&Json::str("running")) {
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(request(&service, "POST", "/jobs", LONG).0, 202);
+    assert_eq!(request(&service, "POST", "/jobs", LONG).0, 503);
+
+    let (code, job) = request(&service, "DELETE", "/jobs/1", "");
+    assert_eq!(
+        (code, job.get("state")),
+        (202, Some(&Json::str("cancelled")))
+    );
+    assert_eq!(request(&service, "GET", "/jobs/1/result", "").0, 404);
+
+    assert_eq!(request(&service, "DELETE", "/jobs/0", "").0, 202);
+    let job = wait(&service, 0);
+    assert_eq!(job.get("state"), Some(&Json::str("cancelled")));
+    assert_ne!(job.get("status"), Some(&Json::str("optimal")));
+    assert_eq!(request(&service, "DELETE", "/jobs/0", "").0, 409);
+}
+
+/// Sends `text` and reads it back as a request with `time` to arrive.
+fn read_sent(text: &str, time: Duration) -> Result<Request, u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    client.write_all(text.as_bytes()).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+    read_request(&stream, time).map_err(|r| r.status)
+}
+
+#[test]
+fn test_read_limits() {
+    let time = Duration::from_millis(200);
+    let request = read_sent("GET /jobs?all HTTP/1.1\r\nHost: x\r\n\r\n", time).unwrap();
+    assert_eq!(
+        (request.method.as_str(), request.path.as_str()),
+        ("GET", "/jobs")
+    );
+    // a client that stops partway through is cut off
+    assert_eq!(read_sent("GET /jobs HTTP/1.1\r\nHost", time), Err(408));
+    let long = format!(
+        "GET /jobs HTTP/1.1\r\nX-Pad: {}\r\n\r\n",
+        "a".repeat(MAX_LINE)
+    );
+    assert_eq!(read_sent(&long, time), Err(431));
+    let many = format!(
+        "GET /jobs HTTP/1.1\r\n{}\r\n",
+        "X-Pad: a\r\n".repeat(MAX_HEADERS)
+    );
+    assert_eq!(read_sent(&many, time), Err(431));
+}
+
+#[test]
+fn test_serve_over_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, &ServerConfig::default()));
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let body = EXAMPLE.as_bytes();
+    write!(
+        stream,
+        "POST /jobs HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    )
+    .unwrap();
+    stream.write_all(body).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 202 Accepted\r\n"));
+    assert!(response.contains("\r\nContent-Type: application/json\r\n"));
+    assert!(response.contains("\"id\": 0,"));
+
+    // a client that connects and sends nothing doesn't hold up the next
+    let _idle = TcpStream::connect(addr).unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    assert!(serve(
+        TcpListener::bind("0.0.0.0:0").unwrap(),
+        &ServerConfig::default()
+    )
+    .is_err());
+}
@@ -0,0 +1,1336 @@
+use crate::json::Json;
+use crate::matrix::{Closure, LegChange, Objective, Overlay, TravelMatrix};
+use crate::opt::{
+    Alternatives, CostModel, DailyLimit, RoadTrip, Tradeoff, TripFront, TripOutcome, TripPlan,
+    Vehicle,
+};
+use crate::roadgraph::haversine;
+use crate::route::Route;
+use crate::waypoint::{gazetteer, lookup};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The trip spec format version this planner reads.
+pub const SPEC_VERSION: u32 = 1;
+
+/// Speed used to estimate driving times from straight-line distances when
+/// the spec doesn't give one.
+pub const DEFAULT_SPEED_KMH: f64 = 80.0;
+
+/// A problem with a trip spec.  `field` points at the offending value the
+/// way it would be written in code, like `waypoints[2].window.latest_hours`,
+/// and is empty when the file as a whole is at fault.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecError {
+    pub field: String,
+    pub message: String,
+}
+
+impl SpecError {
+    pub(crate) fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// Where leg costs come from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MatrixSource {
+    /// A tab-separated matrix file as written by `trip.py`.  Relative paths
+    /// are relative to the spec file.
+    File(PathBuf),
+    /// One of the matrices bundled with the planner; see `matrix::BUILTIN`.
+    Builtin(String),
+    /// Great-circle distances between gazetteer coordinates, with driving
+    /// times estimated at a constant speed.
+    StraightLine { speed_kmh: f64 },
+}
+
+/// A waypoint of the trip other than home.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StopSpec {
+    pub name: String,
+    /// What skipping the stop costs, in meters or seconds like the
+    /// objective, if the stop is optional.
+    pub value: Option<u32>,
+    /// Earliest and latest arrival, in seconds after the start.
+    pub window: Option<(u32, u32)>,
+    /// Seconds spent there before driving on.
+    pub stay: u32,
+}
+
+/// An electric vehicle's range and the places it can charge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VehicleSpec {
+    /// Meters on a full battery.
+    pub range: u32,
+    /// Charging locations by name, with the seconds a full charge takes.
+    pub chargers: Vec<(String, u32)>,
+}
+
+/// Groups of waypoints a trip must visit at least one of each, or exactly
+/// one of each.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoverSpec {
+    /// Waypoint names in each group, or `None` to group by state.
+    pub groups: Option<Vec<Vec<String>>>,
+    pub exactly_one: bool,
+}
+
+/// A toll on the leg between two waypoints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TollSpec {
+    pub from: String,
+    pub to: String,
+    pub cents: u32,
+    /// Whether the toll is only paid going from `from` to `to`.
+    pub one_way: bool,
+}
+
+/// Prices for turning a trip into money.  Amounts are in cents.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CostsSpec {
+    /// Miles per gallon.
+    pub mpg: f64,
+    /// Fuel price per gallon where the state has none of its own.
+    pub fuel_price: u32,
+    /// Fuel prices per gallon by state.
+    pub state_prices: Vec<(String, u32)>,
+    pub tolls: Vec<TollSpec>,
+    /// Lodging per night on the road.
+    pub lodging: u32,
+}
+
+/// A trip described in a file rather than in code.  The format is JSON:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "name": "Desert parks",
+///   "matrix": {"builtin": "ten-parks"},
+///   "home": "Joshua Tree",
+///   "objective": "distance",
+///   "trip": "closed",
+///   "waypoints": [
+///     {"name": "Death Valley", "stay_hours": 5},
+///     {"name": "Big Bend", "window": {"earliest_hours": 24, "latest_hours": 96}},
+///     {"name": "Yellowstone", "optional": true, "value": 1500}
+///   ],
+///   "daily": {"max_driving_hours": 9, "max_days": 10},
+///   "precedence": [{"before": "Death Valley", "after": "Big Bend"}],
+///   "cover": {"by": "state"},
+///   "clusters": [["Death Valley", "Joshua Tree"]],
+///   "closures": [{"from": "Big Bend", "to": "Death Valley", "factor": 1.3}],
+///   "vehicle": {"range_km": 400, "chargers": [{"name": "Las Vegas", "charge_hours": 0.75}]},
+///   "costs": {
+///     "mpg": 28,
+///     "fuel_dollars_per_gallon": 3.6,
+///     "fuel_prices": [{"state": "California", "dollars_per_gallon": 5.1}],
+///     "tolls": [{"from": "Joshua Tree", "to": "Death Valley", "dollars": 8, "one_way": true}],
+///     "lodging_dollars_per_night": 140
+///   }
+/// }
+/// ```
+///
+/// `matrix` is `{"file": PATH}`, `{"builtin": NAME}` or
+/// `{"straight_line": {"speed_kmh": N}}`.  An optional stop's `value` is
+/// in kilometers when minimizing distance and hours when minimizing
+/// duration.  A `vehicle` makes the trip charge on the way so no stretch
+/// is longer than its range; chargers may be waypoints or other places,
+/// and the objective then defaults to, and must be, duration.  Only
+/// `version`, `matrix`, `home` and `waypoints` are required.
+///
+/// A waypoint's `stay_hours` are spent there before driving on; they
+/// count towards the day's driving limit and delay every later arrival.
+/// The `elapsed` objective minimizes the time from leaving home to the end
+/// of the trip, stays, waiting and nights included, and an optional stop's
+/// `value` is then in hours.
+///
+/// `cover` visits at least one waypoint of each group instead of all of
+/// them: `{"by": "state"}` groups the waypoints (home included) by state,
+/// and `{"groups": [["Zion", "Arches"], ["Big Bend"]]}` names the groups,
+/// leaving waypoints in none of them to be visited as usual.  With
+/// `"exactly_one": true`, no group is visited twice.
+///
+/// Each of `clusters` lists waypoints to visit back to back, in any order,
+/// such as the parks of one state.  `closures` close the legs between
+/// two waypoints (both ways unless `one_way`) on top of the matrix, or
+/// with a `factor`, multiply their distance and driving time.
+///
+/// `costs` turns the trip into dollars: fuel at the price of the state
+/// each leg starts in, tolls (both ways unless `one_way`), and lodging for
+/// each night the daily limits call for.  The objective then defaults to,
+/// and must be, cost, and an optional stop's `value` is in dollars.  A
+/// trip can't have both `costs` and a `vehicle`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TripSpec {
+    pub version: u32,
+    pub name: Option<String>,
+    pub matrix: MatrixSource,
+    pub home: String,
+    pub objective: Objective,
+    pub closed: bool,
+    pub waypoints: Vec<StopSpec>,
+    pub daily: Option<DailyLimit>,
+    pub precedence: Vec<(String, String)>,
+    pub vehicle: Option<VehicleSpec>,
+    pub costs: Option<CostsSpec>,
+    pub cover: Option<CoverSpec>,
+    /// Waypoint names to visit back to back.
+    pub clusters: Vec<Vec<String>>,
+    pub closures: Vec<Closure>,
+}
+
+/// A spec with its waypoints found and its leg costs loaded, ready to solve.
+#[derive(Clone, Debug)]
+pub struct Trip {
+    /// Waypoint names by vertex; home is vertex 0.  Chargers that aren't
+    /// waypoints come after the waypoints.
+    pub names: Vec<String>,
+    pub objective: Objective,
+    pub roadtrip: RoadTrip,
+    /// Leg lengths in meters, whatever the objective.
+    pub distances: Vec<(usize, usize, u32)>,
+    /// Leg driving times in seconds, if known.
+    pub durations: Option<Vec<(usize, usize, u32)>>,
+}
+
+impl Trip {
+    /// Builds a route for the exporters from a plan's order, or `None` if
+    /// some waypoint isn't in the gazetteer and so has no coordinates.
+    pub fn route(&self, order: &[usize]) -> Option<Route> {
+        let stops = self
+            .names
+            .iter()
+            .map(|n| lookup(n))
+            .collect::<Option<Vec<_>>>()?;
+        let mut route = Route::new(stops, order.to_vec(), &self.distances);
+        if let Some(durations) = &self.durations {
+            route.add_durations(durations);
+        }
+        Some(route)
+    }
+    /// What to trade the objective off against: `"distance"`,
+    /// `"duration"` or `"stops"` (optional stops skipped).
+    pub fn tradeoff(&self, against: &str) -> Result<Tradeoff, String> {
+        if self.roadtrip.vehicle().is_some() {
+            return Err(
+                "trade-offs don't count charging stops, so can't be used with a vehicle"
+                    .to_string(),
+            );
+        }
+        if against == self.objective.to_string() {
+            return Err(format!("the objective is already {}", against));
+        }
+        match against {
+            "stops" => Ok(Tradeoff::Stops),
+            "distance" => Ok(Tradeoff::Legs(self.distances.clone())),
+            "duration" => match &self.durations {
+                Some(durations) => Ok(Tradeoff::Legs(durations.clone())),
+                None => Err("the matrix has no driving times".to_string()),
+            },
+            other => Err(format!(
+                "unknown trade-off {:?} (expected distance, duration or stops)",
+                other
+            )),
+        }
+    }
+
+    /// Describes a Pareto front as JSON fields: the search status and each
+    /// trade-off's cost, what it is traded against, order and skipped stops.
+    pub fn front_fields(&self, front: &TripFront, against: &str) -> Vec<(&'static str, Json)> {
+        let names =
+            |vs: &[usize]| Json::Array(vs.iter().map(|&v| Json::str(&self.names[v])).collect());
+        let plans = front
+            .plans
+            .iter()
+            .map(|p| {
+                Json::object(vec![
+                    ("cost", p.cost.into()),
+                    ("against", p.against.into()),
+                    ("order", names(&p.plan.order)),
+                    ("skipped", names(&p.plan.skipped)),
+                ])
+            })
+            .collect();
+        vec![
+            ("status", Json::str(&front.status.to_string())),
+            ("objective", Json::str(&self.objective.to_string())),
+            ("against", Json::str(against)),
+            ("nodes", front.nodes.into()),
+            ("front", Json::Array(plans)),
+        ]
+    }
+
+    /// Describes the outcome of solving this trip as JSON fields: the
+    /// search status and, if a plan was found, its order, cost, skipped
+    /// stops, charging stops, where the money goes and (when known) the
+    /// day and time of each arrival.
+    pub fn outcome_fields(&self, outcome: &TripOutcome) -> Vec<(&'static str, Json)> {
+        let mut fields = vec![
+            ("status", Json::str(&outcome.status.to_string())),
+            ("objective", Json::str(&self.objective.to_string())),
+            ("nodes", outcome.nodes.into()),
+        ];
+        if let Some(plan) = &outcome.plan {
+            fields.extend(self.plan_fields(plan));
+        }
+        fields
+    }
+
+    /// Describes alternative plans as JSON fields: the search status and
+    /// each plan's order, cost and so on as in `outcome_fields`.
+    pub fn alternatives_fields(&self, alternatives: &Alternatives) -> Vec<(&'static str, Json)> {
+        let plans = alternatives
+            .plans
+            .iter()
+            .map(|plan| Json::object(self.plan_fields(plan)))
+            .collect();
+        vec![
+            ("status", Json::str(&alternatives.status.to_string())),
+            ("objective", Json::str(&self.objective.to_string())),
+            ("nodes", alternatives.nodes.into()),
+            ("alternatives", Json::Array(plans)),
+        ]
+    }
+
+    /// The fields `outcome_fields` gives for a plan.
+    fn plan_fields(&self, plan: &TripPlan) -> Vec<(&'static str, Json)> {
+        let names =
+            |vs: &[usize]| Json::Array(vs.iter().map(|&v| Json::str(&self.names[v])).collect());
+        let mut fields = vec![
+            ("order", names(&plan.order)),
+            ("cost", plan.cost.into()),
+            ("skipped", names(&plan.skipped)),
+        ];
+        if let Some(arrivals) = &plan.arrivals {
+            let arrivals = arrivals
+                .iter()
+                .map(|a| {
+                    Json::object(vec![
+                        ("day", (a.day + 1).into()),
+                        ("seconds", a.time.into()),
+                    ])
+                })
+                .collect();
+            fields.push(("arrivals", Json::Array(arrivals)));
+        }
+        if self.roadtrip.vehicle().is_some() {
+            let charges = plan
+                .charges
+                .iter()
+                .map(|c| {
+                    Json::object(vec![
+                        ("after", Json::str(&self.names[plan.order[c.leg]])),
+                        ("at", Json::str(&self.names[c.vertex])),
+                        ("seconds", c.seconds.into()),
+                    ])
+                })
+                .collect();
+            fields.push(("charging", Json::Array(charges)));
+        }
+        if let Some(b) = &plan.breakdown {
+            let breakdown = Json::object(vec![
+                ("fuel", b.fuel.into()),
+                ("tolls", b.tolls.into()),
+                ("lodging", b.lodging.into()),
+                ("nights", b.nights.into()),
+            ]);
+            fields.push(("breakdown", breakdown));
+        }
+        fields
+    }
+}
+
+/// The vertex of each waypoint and charger, labels by vertex, leg costs,
+/// and driving times if known.
+type Legs = (
+    Vec<usize>,
+    Vec<String>,
+    Vec<(usize, usize, u32)>,
+    Option<Vec<(usize, usize, u32)>>,
+);
+
+/// Joins a field path and a key.
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Checks that a value is an object with only the given keys.
+fn object<'a>(
+    value: &'a Json,
+    path: &str,
+    keys: &[&str],
+) -> Result<&'a [(String, Json)], SpecError> {
+    match value {
+        Json::Object(fields) => {
+            if let Some((key, _)) = fields.iter().find(|(k, _)| !keys.contains(&k.as_str())) {
+                return Err(SpecError::new(
+                    &join(path, key),
+                    format!("unknown field (expected one of {})", keys.join(", ")),
+                ));
+            }
+            Ok(fields)
+        }
+        _ => Err(SpecError::new(path, "expected an object")),
+    }
+}
+
+fn string<'a>(value: &'a Json, path: &str) -> Result<&'a str, SpecError> {
+    match value.as_str() {
+        Some(s) if !s.trim().is_empty() => Ok(s),
+        Some(_) => Err(SpecError::new(path, "must not be empty")),
+        None => Err(SpecError::new(path, "expected a string")),
+    }
+}
+
+fn number(value: &Json, path: &str) -> Result<f64, SpecError> {
+    match value.as_f64() {
+        Some(n) if n >= 0.0 => Ok(n),
+        Some(_) => Err(SpecError::new(path, "must not be negative")),
+        None => Err(SpecError::new(path, "expected a number")),
+    }
+}
+
+/// Reads a non-negative amount and scales it to a whole number of smaller
+/// units, like hours to seconds.
+fn scaled(value: &Json, path: &str, scale: f64) -> Result<u32, SpecError> {
+    let n = number(value, path)? * scale;
+    if n > u32::MAX as f64 {
+        return Err(SpecError::new(path, "is too large"));
+    }
+    Ok(n.round() as u32)
+}
+
+fn required<'a>(
+    fields: &'a [(String, Json)],
+    path: &str,
+    key: &str,
+) -> Result<&'a Json, SpecError> {
+    fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v)
+        .ok_or_else(|| SpecError::new(&join(path, key), "is required"))
+}
+
+fn optional<'a>(fields: &'a [(String, Json)], key: &str) -> Option<&'a Json> {
+    fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+/// Reads an array of arrays of waypoint names.  A list shorter than
+/// `at_least` is reported with `too_short`.
+fn name_lists(
+    value: &Json,
+    path: &str,
+    at_least: usize,
+    too_short: &str,
+) -> Result<Vec<Vec<String>>, SpecError> {
+    value
+        .as_array()
+        .ok_or_else(|| SpecError::new(path, "expected an array"))?
+        .iter()
+        .enumerate()
+        .map(|(i, list)| {
+            let path = format!("{}[{}]", path, i);
+            let names = list
+                .as_array()
+                .ok_or_else(|| SpecError::new(&path, "expected an array"))?;
+            if names.len() < at_least {
+                return Err(SpecError::new(&path, too_short));
+            }
+            names
+                .iter()
+                .enumerate()
+                .map(|(k, n)| string(n, &format!("{}[{}]", path, k)).map(str::to_string))
+                .collect()
+        })
+        .collect()
+}
+
+impl TripSpec {
+    /// Parses and checks a spec.  Waypoint names and the matrix aren't
+    /// looked at until `resolve`.
+    pub fn from_json(text: &str) -> Result<Self, SpecError> {
+        let doc = Json::parse(text).map_err(|e| SpecError::new("", e.to_string()))?;
+        let fields = object(
+            &doc,
+            "",
+            &[
+                "version",
+                "name",
+                "matrix",
+                "home",
+                "objective",
+                "trip",
+                "waypoints",
+                "daily",
+                "precedence",
+                "vehicle",
+                "costs",
+                "cover",
+                "clusters",
+                "closures",
+            ],
+        )?;
+
+        let version = required(fields, "", "version")?;
+        if version.as_f64() != Some(SPEC_VERSION as f64) {
+            return Err(SpecError::new(
+                "version",
+                format!(
+                    "unsupported version {} (this planner reads version {})",
+                    version, SPEC_VERSION
+                ),
+            ));
+        }
+        let name = optional(fields, "name")
+            .map(|n| string(n, "name").map(str::to_string))
+            .transpose()?;
+        let matrix = Self::matrix(required(fields, "", "matrix")?)?;
+        let home = string(required(fields, "", "home")?, "home")?.to_string();
+        let vehicle = optional(fields, "vehicle").map(Self::vehicle).transpose()?;
+        let costs = optional(fields, "costs").map(Self::costs).transpose()?;
+        if vehicle.is_some() && costs.is_some() {
+            return Err(SpecError::new(
+                "costs",
+                "can't be used with a vehicle, whose charging is counted in time",
+            ));
+        }
+        let objective = match optional(fields, "objective") {
+            Some(o) => string(o, "objective")?
+                .parse()
+                .map_err(|e: String| SpecError::new("objective", e))?,
+            None if vehicle.is_some() => Objective::Duration,
+            None if costs.is_some() => Objective::Cost,
+            None => Objective::Distance,
+        };
+        if vehicle.is_some() && objective == Objective::Distance {
+            return Err(SpecError::new(
+                "objective",
+                "must be duration for a vehicle, so charging time counts",
+            ));
+        }
+        match (&costs, objective) {
+            (Some(_), Objective::Cost)
+            | (None, Objective::Distance | Objective::Duration | Objective::Elapsed) => {}
+            (Some(_), _) => {
+                return Err(SpecError::new(
+                    "objective",
+                    "must be cost when costs are given",
+                ))
+            }
+            (None, Objective::Cost) => {
+                return Err(SpecError::new("objective", "needs a costs section"))
+            }
+        }
+        let closed = match optional(fields, "trip")
+            .map(|t| string(t, "trip"))
+            .transpose()?
+        {
+            None | Some("closed") => true,
+            Some("open") => false,
+            Some(_) => return Err(SpecError::new("trip", "expected \"closed\" or \"open\"")),
+        };
+
+        let value_scale = match objective {
+            Objective::Distance => 1000.0,
+            Objective::Duration | Objective::Elapsed => 3600.0,
+            Objective::Cost => 100.0,
+        };
+        let list = required(fields, "", "waypoints")?
+            .as_array()
+            .ok_or_else(|| SpecError::new("waypoints", "expected an array"))?;
+        if list.is_empty() {
+            return Err(SpecError::new(
+                "waypoints",
+                "a trip needs at least one waypoint besides home",
+            ));
+        }
+        let waypoints = list
+            .iter()
+            .enumerate()
+            .map(|(i, w)| Self::stop(w, &format!("waypoints[{}]", i), value_scale))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let daily = optional(fields, "daily")
+            .map(|d| {
+                let f = object(d, "daily", &["max_driving_hours", "max_days"])?;
+                let max_driving = scaled(
+                    required(f, "daily", "max_driving_hours")?,
+                    "daily.max_driving_hours",
+                    3600.0,
+                )?;
+                if max_driving == 0 {
+                    return Err(SpecError::new(
+                        "daily.max_driving_hours",
+                        "must be more than 0",
+                    ));
+                }
+                let max_days = optional(f, "max_days")
+                    .map(|m| match m.as_f64() {
+                        Some(n) if n >= 1.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => {
+                            Ok(n as u32)
+                        }
+                        _ => Err(SpecError::new(
+                            "daily.max_days",
+                            "expected a whole number of days, at least 1",
+                        )),
+                    })
+                    .transpose()?;
+                Ok(DailyLimit {
+                    max_driving,
+                    max_days,
+                })
+            })
+            .transpose()?;
+
+        if daily.is_none() && costs.as_ref().is_some_and(|c| c.lodging > 0) {
+            return Err(SpecError::new(
+                "costs.lodging_dollars_per_night",
+                "needs daily driving limits to know the nights",
+            ));
+        }
+
+        let precedence = match optional(fields, "precedence") {
+            None => Vec::new(),
+            Some(p) => p
+                .as_array()
+                .ok_or_else(|| SpecError::new("precedence", "expected an array"))?
+                .iter()
+                .enumerate()
+                .map(|(i, rule)| {
+                    let path = format!("precedence[{}]", i);
+                    let f = object(rule, &path, &["before", "after"])?;
+                    let before = string(required(f, &path, "before")?, &join(&path, "before"))?;
+                    let after = string(required(f, &path, "after")?, &join(&path, "after"))?;
+                    Ok((before.to_string(), after.to_string()))
+                })
+                .collect::<Result<Vec<_>, SpecError>>()?,
+        };
+
+        let cover = optional(fields, "cover").map(Self::cover).transpose()?;
+        let clusters = optional(fields, "clusters")
+            .map(|c| name_lists(c, "clusters", 2, "a cluster needs at least two waypoints"))
+            .transpose()?
+            .unwrap_or_default();
+        let closures = match optional(fields, "closures") {
+            None => Vec::new(),
+            Some(c) => c
+                .as_array()
+                .ok_or_else(|| SpecError::new("closures", "expected an array"))?
+                .iter()
+                .enumerate()
+                .map(|(i, closure)| Self::closure(closure, &format!("closures[{}]", i)))
+                .collect::<Result<Vec<_>, SpecError>>()?,
+        };
+
+        Ok(Self {
+            version: SPEC_VERSION,
+            name,
+            matrix,
+            home,
+            objective,
+            closed,
+            waypoints,
+            daily,
+            precedence,
+            vehicle,
+            costs,
+            cover,
+            clusters,
+            closures,
+        })
+    }
+
+    /// Reads a spec file.  A matrix file named in it is taken relative to
+    /// the spec's own directory.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SpecError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| SpecError::new("", e.to_string()))?;
+        let mut spec = Self::from_json(&text)?;
+        if let MatrixSource::File(file) = &mut spec.matrix {
+            if file.is_relative() {
+                if let Some(dir) = path.parent() {
+                    *file = dir.join(&file);
+                }
+            }
+        }
+        Ok(spec)
+    }
+
+    fn matrix(value: &Json) -> Result<MatrixSource, SpecError> {
+        let fields = object(value, "matrix", &["file", "builtin", "straight_line"])?;
+        if fields.len() != 1 {
+            return Err(SpecError::new(
+                "matrix",
+                "give exactly one of file, builtin or straight_line",
+            ));
+        }
+        let (key, value) = &fields[0];
+        let path = join("matrix", key);
+        match key.as_str() {
+            "file" => Ok(MatrixSource::File(PathBuf::from(string(value, &path)?))),
+            "builtin" => {
+                let name = string(value, &path)?;
+                if TravelMatrix::builtin(name).is_none() {
+                    return Err(SpecError::new(
+                        &path,
+                        format!("no bundled matrix named {:?}", name),
+                    ));
+                }
+                Ok(MatrixSource::Builtin(name.to_string()))
+            }
+            _ => {
+                let f = object(value, &path, &["speed_kmh"])?;
+                let speed_kmh = match optional(f, "speed_kmh") {
+                    Some(s) => number(s, &join(&path, "speed_kmh"))?,
+                    None => DEFAULT_SPEED_KMH,
+                };
+                if speed_kmh == 0.0 {
+                    return Err(SpecError::new(
+                        &join(&path, "speed_kmh"),
+                        "must be more than 0",
+                    ));
+                }
+                Ok(MatrixSource::StraightLine { speed_kmh })
+            }
+        }
+    }
+
+    fn vehicle(value: &Json) -> Result<VehicleSpec, SpecError> {
+        let f = object(value, "vehicle", &["range_km", "chargers"])?;
+        let range = scaled(
+            required(f, "vehicle", "range_km")?,
+            "vehicle.range_km",
+            1000.0,
+        )?;
+        if range == 0 {
+            return Err(SpecError::new("vehicle.range_km", "must be more than 0"));
+        }
+        let chargers = match optional(f, "chargers") {
+            None => Vec::new(),
+            Some(c) => c
+                .as_array()
+                .ok_or_else(|| SpecError::new("vehicle.chargers", "expected an array"))?
+                .iter()
+                .enumerate()
+                .map(|(i, charger)| {
+                    let path = format!("vehicle.chargers[{}]", i);
+                    let cf = object(charger, &path, &["name", "charge_hours"])?;
+                    let name = string(required(cf, &path, "name")?, &join(&path, "name"))?;
+                    let seconds = scaled(
+                        required(cf, &path, "charge_hours")?,
+                        &join(&path, "charge_hours"),
+                        3600.0,
+                    )?;
+                    Ok((name.to_string(), seconds))
+                })
+                .collect::<Result<Vec<_>, SpecError>>()?,
+        };
+        Ok(VehicleSpec { range, chargers })
+    }
+
+    fn costs(value: &Json) -> Result<CostsSpec, SpecError> {
+        let f = object(
+            value,
+            "costs",
+            &[
+                "mpg",
+                "fuel_dollars_per_gallon",
+                "fuel_prices",
+                "tolls",
+                "lodging_dollars_per_night",
+            ],
+        )?;
+        let mpg = number(required(f, "costs", "mpg")?, "costs.mpg")?;
+        if mpg == 0.0 {
+            return Err(SpecError::new("costs.mpg", "must be more than 0"));
+        }
+        let fuel_price = scaled(
+            required(f, "costs", "fuel_dollars_per_gallon")?,
+            "costs.fuel_dollars_per_gallon",
+            100.0,
+        )?;
+        let list = |key: &str| match optional(f, key) {
+            None => Ok(&[][..]),
+            Some(l) => l
+                .as_array()
+                .ok_or_else(|| SpecError::new(&join("costs", key), "expected an array")),
+        };
+        let states: Vec<String> = gazetteer().into_iter().map(|w| w.state).collect();
+        let state_prices = list("fuel_prices")?
+            .iter()
+            .enumerate()
+            .map(|(i, price)| {
+                let path = format!("costs.fuel_prices[{}]", i);
+                let pf = object(price, &path, &["state", "dollars_per_gallon"])?;
+                let state = string(required(pf, &path, "state")?, &join(&path, "state"))?;
+                let state = match states.iter().find(|s| s.eq_ignore_ascii_case(state.trim())) {
+                    Some(s) => s.clone(),
+                    None => {
+                        return Err(SpecError::new(
+                            &join(&path, "state"),
+                            format!("no waypoints are in a state named {:?}", state),
+                        ))
+                    }
+                };
+                let key = join(&path, "dollars_per_gallon");
+                Ok((
+                    state,
+                    scaled(required(pf, &path, "dollars_per_gallon")?, &key, 100.0)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, SpecError>>()?;
+        let tolls = list("tolls")?
+            .iter()
+            .enumerate()
+            .map(|(i, toll)| {
+                let path = format!("costs.tolls[{}]", i);
+                let tf = object(toll, &path, &["from", "to", "dollars", "one_way"])?;
+                let from = string(required(tf, &path, "from")?, &join(&path, "from"))?;
+                let to = string(required(tf, &path, "to")?, &join(&path, "to"))?;
+                let cents = scaled(
+                    required(tf, &path, "dollars")?,
+                    &join(&path, "dollars"),
+                    100.0,
+                )?;
+                let one_way = match optional(tf, "one_way") {
+                    Some(o) => o.as_bool().ok_or_else(|| {
+                        SpecError::new(&join(&path, "one_way"), "expected true or false")
+                    })?,
+                    None => false,
+                };
+                Ok(TollSpec {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    cents,
+                    one_way,
+                })
+            })
+            .collect::<Result<Vec<_>, SpecError>>()?;
+        let lodging = match optional(f, "lodging_dollars_per_night") {
+            Some(l) => scaled(l, "costs.lodging_dollars_per_night", 100.0)?,
+            None => 0,
+        };
+        Ok(CostsSpec {
+            mpg,
+            fuel_price,
+            state_prices,
+            tolls,
+            lodging,
+        })
+    }
+
+    fn closure(value: &Json, path: &str) -> Result<Closure, SpecError> {
+        let f = object(value, path, &["from", "to", "factor", "one_way"])?;
+        let from = string(required(f, path, "from")?, &join(path, "from"))?;
+        let to = string(required(f, path, "to")?, &join(path, "to"))?;
+        let change = match optional(f, "factor") {
+            None => LegChange::Closed,
+            Some(factor) => {
+                let factor = number(factor, &join(path, "factor"))?;
+                if factor == 0.0 {
+                    return Err(SpecError::new(&join(path, "factor"), "must be more than 0"));
+                }
+                LegChange::Scaled(factor)
+            }
+        };
+        let one_way = match optional(f, "one_way") {
+            Some(o) => o
+                .as_bool()
+                .ok_or_else(|| SpecError::new(&join(path, "one_way"), "expected true or false"))?,
+            None => false,
+        };
+        Ok(Closure {
+            from: from.to_string(),
+            to: to.to_string(),
+            change,
+            one_way,
+        })
+    }
+
+    fn cover(value: &Json) -> Result<CoverSpec, SpecError> {
+        let f = object(value, "cover", &["by", "groups", "exactly_one"])?;
+        let exactly_one = match optional(f, "exactly_one") {
+            Some(e) => e
+                .as_bool()
+                .ok_or_else(|| SpecError::new("cover.exactly_one", "expected true or false"))?,
+            None => false,
+        };
+        let groups = match (optional(f, "by"), optional(f, "groups")) {
+            (Some(by), None) => match string(by, "cover.by")? {
+                "state" => None,
+                _ => return Err(SpecError::new("cover.by", "expected \"state\"")),
+            },
+            (None, Some(groups)) => Some(name_lists(
+                groups,
+                "cover.groups",
+                1,
+                "a group needs at least one waypoint",
+            )?),
+            _ => return Err(SpecError::new("cover", "expected one of by or groups")),
+        };
+        Ok(CoverSpec {
+            groups,
+            exactly_one,
+        })
+    }
+
+    fn stop(value: &Json, path: &str, value_scale: f64) -> Result<StopSpec, SpecError> {
+        if value.as_str().is_some() {
+            return Ok(StopSpec {
+                name: string(value, path)?.to_string(),
+                value: None,
+                window: None,
+                stay: 0,
+            });
+        }
+        let f = object(
+            value,
+            path,
+            &["name", "optional", "value", "window", "stay_hours"],
+        )?;
+        let name = string(required(f, path, "name")?, &join(path, "name"))?.to_string();
+        let is_optional = match optional(f, "optional") {
+            Some(o) => o
+                .as_bool()
+                .ok_or_else(|| SpecError::new(&join(path, "optional"), "expected true or false"))?,
+            None => false,
+        };
+        let value = match (is_optional, optional(f, "value")) {
+            (true, Some(v)) => Some(scaled(v, &join(path, "value"), value_scale)?),
+            (true, None) => {
+                return Err(SpecError::new(
+                    &join(path, "value"),
+                    "optional stops need a value",
+                ))
+            }
+            (false, Some(_)) => {
+                return Err(SpecError::new(
+                    &join(path, "value"),
+                    "only optional stops have a value",
+                ))
+            }
+            (false, None) => None,
+        };
+        let window = optional(f, "window")
+            .map(|w| {
+                let wpath = join(path, "window");
+                let wf = object(w, &wpath, &["earliest_hours", "latest_hours"])?;
+                let bound = |key: &str, default: u32| match optional(wf, key) {
+                    Some(v) => scaled(v, &join(&wpath, key), 3600.0),
+                    None => Ok(default),
+                };
+                let earliest = bound("earliest_hours", 0)?;
+                let latest = bound("latest_hours", u32::MAX)?;
+                if earliest > latest {
+                    return Err(SpecError::new(
+                        &wpath,
+                        "earliest_hours is after latest_hours",
+                    ));
+                }
+                Ok((earliest, latest))
+            })
+            .transpose()?;
+        let stay = optional(f, "stay_hours")
+            .map(|s| scaled(s, &join(path, "stay_hours"), 3600.0))
+            .transpose()?
+            .unwrap_or(0);
+        Ok(StopSpec {
+            name,
+            value,
+            window,
+            stay,
+        })
+    }
+
+    /// Whether any rule needs to know when stops are reached.
+    fn is_timed(&self) -> bool {
+        self.daily.is_some()
+            || self.objective == Objective::Elapsed
+            || self
+                .waypoints
+                .iter()
+                .any(|w| w.window.is_some() || w.stay > 0)
+    }
+
+    /// Finds every waypoint, loads leg costs and builds the model.
+    pub fn resolve(&self) -> Result<Trip, SpecError> {
+        let mut names = vec![self.home.as_str()];
+        let mut fields = vec!["home".to_string()];
+        for (i, w) in self.waypoints.iter().enumerate() {
+            names.push(&w.name);
+            fields.push(format!("waypoints[{}]", i));
+        }
+        let chargers = self.vehicle.as_ref().map_or(&[][..], |v| &v.chargers);
+        for (i, (name, _)) in chargers.iter().enumerate() {
+            names.push(name);
+            fields.push(format!("vehicle.chargers[{}].name", i));
+        }
+
+        // find each waypoint as a matrix index or a gazetteer entry
+        let (vertices, labels, mut distances, mut durations) = match &self.matrix {
+            MatrixSource::File(path) => {
+                let matrix = TravelMatrix::read(path).map_err(|e| {
+                    SpecError::new("matrix.file", format!("{}: {}", path.display(), e))
+                })?;
+                self.matrix_legs(&matrix, &names, &fields)?
+            }
+            MatrixSource::Builtin(name) => {
+                self.matrix_legs(&TravelMatrix::builtin(name).unwrap(), &names, &fields)?
+            }
+            MatrixSource::StraightLine { speed_kmh } => {
+                self.straight_line_legs(*speed_kmh, &names, &fields)?
+            }
+        };
+
+        // a closed leg is left out, so no plan can drive it
+        if !self.closures.is_empty() {
+            let mut overlay = Overlay::default();
+            for (i, closure) in self.closures.iter().enumerate() {
+                let path = format!("closures[{}]", i);
+                let stops = self.waypoints.len() + 1;
+                let from = find_stop(&labels, stops, &closure.from, &join(&path, "from"))?;
+                let to = find_stop(&labels, stops, &closure.to, &join(&path, "to"))?;
+                if from == to {
+                    return Err(SpecError::new(
+                        &path,
+                        "a closure needs two different waypoints",
+                    ));
+                }
+                overlay.legs.push(Closure {
+                    from: labels[from].clone(),
+                    to: labels[to].clone(),
+                    ..closure.clone()
+                });
+            }
+            let mut matrix = TravelMatrix::from_edges(labels.clone(), &distances);
+            if let Some(durations) = &durations {
+                matrix.add_durations(durations);
+            }
+            let matrix = matrix
+                .with_overlay(&overlay)
+                .map_err(|e| SpecError::new("closures", e.to_string()))?;
+            let all: Vec<_> = (0..labels.len()).collect();
+            let edges = |objective| {
+                matrix
+                    .edges(&all, objective)
+                    .map_err(|e| SpecError::new("closures", e.to_string()))
+            };
+            distances = edges(Objective::Distance)?;
+            if durations.is_some() {
+                durations = Some(edges(Objective::Duration)?);
+            }
+        }
+
+        let edges = match (self.objective, &durations) {
+            (Objective::Distance, _) | (Objective::Cost, None) => &distances,
+            (Objective::Cost, Some(durations)) => durations,
+            (Objective::Duration | Objective::Elapsed, Some(durations)) => durations,
+            (Objective::Duration | Objective::Elapsed, None) => {
+                return Err(SpecError::new(
+                    "objective",
+                    "needs driving times, but the matrix has none",
+                ))
+            }
+        };
+        let mut roadtrip = RoadTrip::new(self.waypoints.len() + 1, edges);
+        if let Some(durations) = &durations {
+            roadtrip.set_durations(durations);
+        }
+        roadtrip.set_closed(self.closed);
+        for (i, w) in self.waypoints.iter().enumerate() {
+            if let Some(value) = w.value {
+                roadtrip.set_optional(i + 1, value);
+            }
+            if let Some((earliest, latest)) = w.window {
+                roadtrip.set_window(i + 1, earliest, latest);
+            }
+            roadtrip.set_stay(i + 1, w.stay);
+        }
+        roadtrip.set_minimize_elapsed(self.objective == Objective::Elapsed);
+        if let Some(daily) = self.daily {
+            roadtrip.set_daily_limit(daily);
+        }
+        if let Some(vehicle) = &self.vehicle {
+            let chargers = vertices[self.waypoints.len() + 1..]
+                .iter()
+                .zip(vehicle.chargers.iter())
+                .map(|(&v, &(_, seconds))| (v, seconds))
+                .collect();
+            let vehicle = Vehicle {
+                range: vehicle.range,
+                chargers,
+            };
+            roadtrip.set_vehicle(vehicle, &distances);
+        }
+        for (i, (before, after)) in self.precedence.iter().enumerate() {
+            let path = format!("precedence[{}]", i);
+            let find =
+                |name: &str, key: &str| find_stop(&labels, roadtrip.len(), name, &join(&path, key));
+            let (b, a) = (find(before, "before")?, find(after, "after")?);
+            if a == 0 {
+                return Err(SpecError::new(
+                    &join(&path, "after"),
+                    "the trip starts at home, so nothing can come before it",
+                ));
+            }
+            if a == b {
+                return Err(SpecError::new(&path, "a waypoint can't come before itself"));
+            }
+            roadtrip.add_precedence(b, a);
+        }
+
+        for (c, names) in self.clusters.iter().enumerate() {
+            let members = names
+                .iter()
+                .enumerate()
+                .map(|(k, name)| {
+                    let path = format!("clusters[{}][{}]", c, k);
+                    find_stop(&labels, self.waypoints.len() + 1, name, &path)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            roadtrip.add_cluster(&members);
+        }
+
+        if let Some(cover) = &self.cover {
+            let stops = self.waypoints.len() + 1;
+            let groups = match &cover.groups {
+                Some(groups) => groups
+                    .iter()
+                    .enumerate()
+                    .map(|(g, names)| {
+                        names
+                            .iter()
+                            .enumerate()
+                            .map(|(k, name)| {
+                                let path = format!("cover.groups[{}][{}]", g, k);
+                                find_stop(&labels, stops, name, &path)
+                            })
+                            .collect()
+                    })
+                    .collect::<Result<Vec<Vec<_>>, SpecError>>()?,
+                None => {
+                    let mut states: Vec<(String, Vec<usize>)> = Vec::new();
+                    for (v, label) in labels[..stops].iter().enumerate() {
+                        let state = lookup(label)
+                            .map(|w| w.state)
+                            .or_else(|| label.rsplit_once(',').map(|(_, s)| s.trim().to_string()));
+                        let state = state.ok_or_else(|| {
+                            SpecError::new(&fields[v], "can't tell which state it is in")
+                        })?;
+                        match states.iter_mut().find(|(s, _)| *s == state) {
+                            Some((_, members)) => members.push(v),
+                            None => states.push((state, vec![v])),
+                        }
+                    }
+                    states.into_iter().map(|(_, members)| members).collect()
+                }
+            };
+            for members in groups.iter() {
+                let at_most = if cover.exactly_one { 1 } else { members.len() };
+                roadtrip.add_group(members, 1, at_most);
+            }
+        }
+
+        if let Some(costs) = &self.costs {
+            let fuel_prices = labels
+                .iter()
+                .map(|label| {
+                    let state = lookup(label).map(|w| w.state);
+                    costs
+                        .state_prices
+                        .iter()
+                        .find(|(s, _)| Some(s) == state.as_ref())
+                        .map_or(costs.fuel_price, |&(_, price)| price)
+                })
+                .collect();
+            let mut tolls = Vec::new();
+            for (i, toll) in costs.tolls.iter().enumerate() {
+                let path = format!("costs.tolls[{}]", i);
+                let from = find_stop(&labels, roadtrip.len(), &toll.from, &join(&path, "from"))?;
+                let to = find_stop(&labels, roadtrip.len(), &toll.to, &join(&path, "to"))?;
+                if from == to {
+                    return Err(SpecError::new(
+                        &path,
+                        "a toll needs two different waypoints",
+                    ));
+                }
+                tolls.push((from, to, toll.cents));
+                if !toll.one_way {
+                    tolls.push((to, from, toll.cents));
+                }
+            }
+            let model = CostModel {
+                mpg: costs.mpg,
+                fuel_prices,
+                tolls,
+                lodging: costs.lodging,
+            };
+            roadtrip
+                .set_cost_model(&model, &distances)
+                .map_err(|e| SpecError::new("costs.mpg", e.to_string()))?;
+        }
+
+        Ok(Trip {
+            names: labels,
+            objective: self.objective,
+            roadtrip,
+            distances,
+            durations,
+        })
+    }
+
+    fn matrix_legs(
+        &self,
+        matrix: &TravelMatrix,
+        names: &[&str],
+        fields: &[String],
+    ) -> Result<Legs, SpecError> {
+        let selection = names
+            .iter()
+            .zip(fields.iter())
+            .map(|(n, f)| {
+                matrix
+                    .index_of(n)
+                    .map_err(|e| SpecError::new(f, e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let vertices = self.vertices(&selection, fields)?;
+        let selection = distinct(&selection, &vertices);
+        let distances = matrix
+            .edges(&selection, Objective::Distance)
+            .map_err(|e| SpecError::new("matrix", e.to_string()))?;
+        let durations = matrix.edges(&selection, Objective::Duration).ok();
+        if durations.is_none() && self.is_timed() {
+            let window = self.waypoints.iter().position(|w| w.window.is_some());
+            let stay = self.waypoints.iter().position(|w| w.stay > 0);
+            let field = match (window, stay) {
+                (Some(i), _) => format!("waypoints[{}].window", i),
+                (None, Some(i)) => format!("waypoints[{}].stay_hours", i),
+                _ if self.daily.is_some() => "daily".to_string(),
+                _ => "objective".to_string(),
+            };
+            return Err(SpecError::new(
+                &field,
+                "needs driving times, but the matrix has none",
+            ));
+        }
+        let labels = selection
+            .iter()
+            .map(|&i| matrix.names()[i].clone())
+            .collect();
+        Ok((vertices, labels, distances, durations))
+    }
+
+    fn straight_line_legs(
+        &self,
+        speed_kmh: f64,
+        names: &[&str],
+        fields: &[String],
+    ) -> Result<Legs, SpecError> {
+        let stops = names
+            .iter()
+            .zip(fields.iter())
+            .map(|(n, f)| {
+                lookup(n).ok_or_else(|| SpecError::new(f, format!("no waypoint named {:?}", n)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let labels: Vec<_> = stops.iter().map(|w| w.label()).collect();
+        let vertices = self.vertices(&labels, fields)?;
+        let (stops, labels) = (distinct(&stops, &vertices), distinct(&labels, &vertices));
+        let (mut distances, mut durations) = (Vec::new(), Vec::new());
+        for (a, from) in stops.iter().enumerate() {
+            for (b, to) in stops.iter().enumerate() {
+                if a != b {
+                    let meters = haversine(from.coords(), to.coords());
+                    let seconds = meters / (speed_kmh / 3.6);
+                    distances.push((a, b, meters.round() as u32));
+                    durations.push((a, b, seconds.round() as u32));
+                }
+            }
+        }
+        Ok((vertices, labels, distances, Some(durations)))
+    }
+
+    /// Numbers the waypoints and chargers as vertices.  A charger at a
+    /// waypoint shares its vertex; any other repeat is reported.
+    fn vertices<T: PartialEq>(
+        &self,
+        keys: &[T],
+        fields: &[String],
+    ) -> Result<Vec<usize>, SpecError> {
+        let stops = self.waypoints.len() + 1;
+        let mut vertices: Vec<usize> = Vec::with_capacity(keys.len());
+        let mut count = 0;
+        for i in 0..keys.len() {
+            match (0..i).find(|&j| keys[j] == keys[i]) {
+                None => {
+                    vertices.push(count);
+                    count += 1;
+                }
+                Some(j) if j < stops && i >= stops => vertices.push(vertices[j]),
+                Some(j) => {
+                    let message = if j == 0 {
+                        "is the home base".to_string()
+                    } else if j < stops {
+                        format!("is the same waypoint as {}", fields[j])
+                    } else {
+                        format!("is the same charger as {}", fields[j])
+                    };
+                    return Err(SpecError::new(&fields[i], message));
+                }
+            }
+        }
+        Ok(vertices)
+    }
+}
+
+/// Finds one of the first `stops` waypoints by its label or the part
+/// before the first comma.
+fn find_stop(labels: &[String], stops: usize, name: &str, path: &str) -> Result<usize, SpecError> {
+    let wanted = name.trim().to_lowercase();
+    let short = |n: &String| n.split(',').next().unwrap().trim().to_lowercase();
+    (0..stops)
+        .find(|&v| labels[v].to_lowercase() == wanted)
+        .or_else(|| (0..stops).find(|&v| short(&labels[v]) == wanted))
+        .ok_or_else(|| {
+            SpecError::new(
+                path,
+                format!("{:?} is not one of the trip's waypoints", name),
+            )
+        })
+}
+
+/// The first key for each vertex, in vertex order.
+fn distinct<T: Clone>(keys: &[T], vertices: &[usize]) -> Vec<T> {
+    let mut kept = Vec::new();
+    for (key, &v) in keys.iter().zip(vertices.iter()) {
+        if v == kept.len() {
+            kept.push(key.clone());
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod pub_tests;
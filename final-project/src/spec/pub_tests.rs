@@ -0,0 +1,426 @@
+// tests
+
+use super::*;
+use crate::csp::{Limits, SearchStatus};
+
+const EXAMPLE: &str = include_str!("../../example-trip.json");
+
+#[test]
+fn test_example() {
+    let spec = TripSpec::from_json(EXAMPLE).unwrap();
+    assert_eq!(spec.name.as_deref(), Some("Southwest parks"));
+    assert_eq!(spec.matrix, MatrixSource::StraightLine { speed_kmh: 80.0 });
+    assert_eq!(spec.waypoints.len(), 7);
+    assert_eq!(spec.waypoints[4].value, Some(100_000));
+    assert_eq!(spec.waypoints[5].window, Some((0, 120 * 3600)));
+    assert_eq!(
+        spec.daily,
+        Some(DailyLimit {
+            max_driving: 6 * 3600,
+            max_days: Some(7)
+        })
+    );
+
+    let trip = spec.resolve().unwrap();
+    assert_eq!(trip.names[0], "Joshua Tree, California");
+    let outcome = trip.roadtrip.solve(&Limits::default());
+    assert_eq!(outcome.status, SearchStatus::Optimal);
+    let plan = outcome.plan.unwrap();
+    let order: Vec<_> = plan.order.iter().map(|&v| trip.names[v].as_str()).collect();
+    let zion = order.iter().position(|&n| n == "Zion, Utah").unwrap();
+    let bryce = order
+        .iter()
+        .position(|&n| n == "Bryce Canyon, Utah")
+        .unwrap();
+    assert!(zion < bryce);
+    assert!(plan.arrivals.unwrap().last().unwrap().day < 7);
+}
+
+#[test]
+fn test_builtin_matrix() {
+    let spec = TripSpec::from_json(
+        r#"{"version": 1, "matrix": {"builtin": "ten-parks"}, "home": "Acadia",
+            "trip": "open", "waypoints": ["Mammoth Cave", "Indiana Dunes"]}"#,
+    )
+    .unwrap();
+    let trip = spec.resolve().unwrap();
+    let plan = trip.roadtrip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!(plan.order.len(), 3);
+    assert_eq!(plan.order[0], 0);
+}
+
+#[test]
+fn test_vehicle() {
+    let spec = TripSpec::from_json(
+        r#"{"version": 1, "matrix": {"straight_line": {}}, "home": "Zion",
+            "waypoints": ["Arches"],
+            "vehicle": {"range_km": 200, "chargers": [
+                {"name": "Capitol Reef", "charge_hours": 1},
+                {"name": "Arches", "charge_hours": 0.5}]}}"#,
+    )
+    .unwrap();
+    assert_eq!(spec.objective, Objective::Duration);
+    let trip = spec.resolve().unwrap();
+    assert_eq!(trip.names.len(), 3);
+    assert_eq!(trip.names[2], "Capitol Reef, Utah");
+
+    // Arches is 341 km from Zion, so the trip charges at Capitol Reef
+    // both ways and at Arches before heading back
+    let outcome = trip.roadtrip.solve(&Limits::default());
+    let plan = outcome.plan.clone().unwrap();
+    assert_eq!(plan.order, vec![0, 1, 0]);
+    assert_eq!(plan.driven_order(), vec![0, 2, 1, 2, 0]);
+    let charging: Vec<_> = plan.charges.iter().map(|c| (c.leg, c.seconds)).collect();
+    assert_eq!(charging, vec![(0, 3600), (1, 1800), (1, 3600)]);
+    let fields = trip.outcome_fields(&outcome);
+    let (_, charges) = fields.iter().find(|(k, _)| *k == "charging").unwrap();
+    assert!(charges
+        .to_string()
+        .contains(r#""after":"Arches, Utah","at":"Arches, Utah","seconds":1800"#));
+    assert_eq!(trip.route(&plan.driven_order()).unwrap().order.len(), 5);
+}
+
+#[test]
+fn test_costs() {
+    let spec = TripSpec::from_json(
+        r#"{"version": 1, "matrix": {"straight_line": {}}, "home": "Zion",
+            "trip": "open", "waypoints": ["Bryce Canyon", "Arches"],
+            "daily": {"max_driving_hours": 4},
+            "costs": {"mpg": 25, "fuel_dollars_per_gallon": 3.5,
+                "fuel_prices": [{"state": "utah", "dollars_per_gallon": 4}],
+                "tolls": [{"from": "Bryce Canyon", "to": "Arches", "dollars": 12.5}],
+                "lodging_dollars_per_night": 120}}"#,
+    )
+    .unwrap();
+    assert_eq!(spec.objective, Objective::Cost);
+    let costs = spec.costs.as_ref().unwrap();
+    assert_eq!(costs.state_prices, vec![("Utah".to_string(), 400)]);
+    assert_eq!(costs.tolls[0].cents, 1250);
+
+    // 82 km to Bryce Canyon and 260 on to Arches, with a night between
+    let trip = spec.resolve().unwrap();
+    let outcome = trip.roadtrip.solve(&Limits::default());
+    let plan = outcome.plan.clone().unwrap();
+    assert_eq!(plan.order, vec![0, 1, 2]);
+    let b = plan.breakdown.unwrap();
+    assert_eq!((b.tolls, b.lodging, b.nights), (1250, 12000, 1));
+    assert_eq!(plan.cost, b.fuel + b.tolls + b.lodging);
+    let fields = trip.outcome_fields(&outcome);
+    assert!(fields.iter().any(|(k, _)| *k == "breakdown"));
+}
+
+/// Parses and resolves a spec, returning the error message.
+fn error(text: &str) -> String {
+    TripSpec::from_json(text)
+        .and_then(|spec| spec.resolve().map(|_| ()))
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn test_stays() {
+    let spec = TripSpec::from_json(
+        r#"{"version": 1, "matrix": {"straight_line": {"speed_kmh": 100}}, "home": "Zion",
+            "objective": "elapsed", "trip": "open",
+            "waypoints": [{"name": "Bryce Canyon", "stay_hours": 6}, "Capitol Reef"],
+            "daily": {"max_driving_hours": 8}}"#,
+    )
+    .unwrap();
+    assert_eq!(spec.objective, Objective::Elapsed);
+    assert_eq!(
+        (spec.waypoints[0].stay, spec.waypoints[1].stay),
+        (6 * 3600, 0)
+    );
+    let trip = spec.resolve().unwrap();
+    let plan = trip.roadtrip.solve(&Limits::default()).plan.unwrap();
+    let arrivals = plan.arrivals.unwrap();
+    // the cost is when the trip ends, which is the last arrival here
+    assert_eq!(plan.cost, arrivals.last().unwrap().time);
+    let bryce = plan.order.iter().position(|&v| v == 1).unwrap();
+    if bryce == 1 {
+        // the next leg starts only after six hours at Bryce Canyon
+        assert!(arrivals[2].time >= arrivals[1].time + 6 * 3600);
+    }
+}
+
+#[test]
+fn test_cover() {
+    let text = |cover: &str| {
+        format!(
+            r#"{{"version": 1, "matrix": {{"straight_line": {{}}}}, "home": "Zion",
+                "waypoints": ["Bryce Canyon", "Arches", "Grand Canyon", "Petrified Forest"],
+                "cover": {}}}"#,
+            cover
+        )
+    };
+    let spec = TripSpec::from_json(&text(r#"{"by": "state"}"#)).unwrap();
+    assert_eq!(
+        spec.cover,
+        Some(CoverSpec {
+            groups: None,
+            exactly_one: false
+        })
+    );
+    // home is in Utah, so only Arizona needs a stop: the nearer park
+    let trip = spec.resolve().unwrap();
+    let plan = trip.roadtrip.solve(&Limits::default()).plan.unwrap();
+    assert_eq!((plan.order, plan.skipped), (vec![0, 3, 0], vec![1, 2, 4]));
+
+    let spec = TripSpec::from_json(&text(
+        r#"{"groups": [["Bryce Canyon", "Arches"], ["Petrified Forest"]], "exactly_one": true}"#,
+    ))
+    .unwrap();
+    let trip = spec.resolve().unwrap();
+    let plan = trip.roadtrip.solve(&Limits::default()).plan.unwrap();
+    // Grand Canyon is in no group, so it is still visited
+    assert_eq!(plan.skipped.len(), 1);
+    assert!(plan.order.contains(&3) && plan.order.contains(&4));
+
+    let error = |cover: &str| {
+        TripSpec::from_json(&text(cover))
+            .and_then(|spec| spec.resolve().map(|_| ()))
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error(r#"{"by": "state", "groups": []}"#),
+        "cover: expected one of by or groups"
+    );
+    assert_eq!(
+        error(r#"{"by": "county"}"#),
+        r#"cover.by: expected "state""#
+    );
+    assert_eq!(
+        error(r#"{"groups": [[]]}"#),
+        "cover.groups[0]: a group needs at least one waypoint"
+    );
+    assert_eq!(
+        error(r#"{"groups": [["Arches", "Yosemite"]]}"#),
+        r#"cover.groups[0][1]: "Yosemite" is not one of the trip's waypoints"#
+    );
+}
+
+#[test]
+fn test_clusters() {
+    let text = |clusters: &str| {
+        format!(
+            r#"{{"version": 1, "matrix": {{"straight_line": {{}}}}, "home": "Zion",
+                "waypoints": ["Bryce Canyon", "Grand Canyon", "Arches", "Petrified Forest"],
+                "clusters": {}}}"#,
+            clusters
+        )
+    };
+    let spec = TripSpec::from_json(&text(r#"[["Bryce Canyon", "Petrified Forest"]]"#)).unwrap();
+    assert_eq!(
+        spec.clusters,
+        vec![vec![
+            "Bryce Canyon".to_string(),
+            "Petrified Forest".to_string()
+        ]]
+    );
+    let trip = spec.resolve().unwrap();
+    let plan = trip.roadtrip.solve(&Limits::default()).plan.unwrap();
+    let at = |v| plan.order.iter().position(|&x| x == v).unwrap();
+    // apart otherwise, as Arches and the Grand Canyon lie between them
+    let free = TripSpec::from_json(&text("[]")).unwrap().resolve().unwrap();
+    let free = free.roadtrip.solve(&Limits::default()).plan.unwrap();
+    let free_at = |v| free.order.iter().position(|&x| x == v).unwrap();
+    assert!(free_at(1).abs_diff(free_at(4)) > 1);
+    assert_eq!(at(1).abs_diff(at(4)), 1);
+
+    let error = |clusters: &str| {
+        TripSpec::from_json(&text(clusters))
+            .and_then(|spec| spec.resolve().map(|_| ()))
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error(r#"[["Arches"]]"#),
+        "clusters[0]: a cluster needs at least two waypoints"
+    );
+    assert_eq!(
+        error(r#"[["Arches", 7]]"#),
+        "clusters[0][1]: expected a string"
+    );
+    assert_eq!(
+        error(r#"[["Arches", "Moab"]]"#),
+        r#"clusters[0][1]: "Moab" is not one of the trip's waypoints"#
+    );
+}
+
+#[test]
+fn test_closures() {
+    let text = |closures: &str| {
+        format!(
+            r#"{{"version": 1, "matrix": {{"builtin": "ten-parks"}}, "home": "Joshua Tree",
+                "waypoints": ["Big Bend", "Badlands"], "closures": {}}}"#,
+            closures
+        )
+    };
+    let plan = |closures: &str| {
+        let trip = TripSpec::from_json(&text(closures))
+            .unwrap()
+            .resolve()
+            .unwrap();
+        trip.roadtrip.solve(&Limits::default()).plan
+    };
+    let spec = TripSpec::from_json(&text(
+        r#"[{"from": "Joshua Tree", "to": "Big Bend", "one_way": true}]"#,
+    ))
+    .unwrap();
+    assert_eq!(
+        spec.closures,
+        vec![Closure {
+            from: "Joshua Tree".to_string(),
+            to: "Big Bend".to_string(),
+            change: LegChange::Closed,
+            one_way: true
+        }]
+    );
+    let open = plan("[]").unwrap();
+    let closed = plan(r#"[{"from": "Joshua Tree", "to": "Big Bend", "one_way": true}]"#).unwrap();
+    assert_eq!(closed.order, vec![0, 2, 1, 0]);
+    assert_eq!(closed.cost, open.cost);
+    // both ways closed, three stops can't make a round trip
+    assert!(plan(r#"[{"from": "Big Bend", "to": "Joshua Tree"}]"#).is_none());
+    let dearer = plan(r#"[{"from": "Big Bend", "to": "Badlands", "factor": 2}]"#).unwrap();
+    let leg = |d: &Vec<(usize, usize, u32)>| d.iter().find(|e| (e.0, e.1) == (1, 2)).unwrap().2;
+    let base = TripSpec::from_json(&text("[]")).unwrap().resolve().unwrap();
+    assert_eq!(dearer.cost, open.cost + leg(&base.distances));
+
+    let error = |closures: &str| {
+        TripSpec::from_json(&text(closures))
+            .and_then(|spec| spec.resolve().map(|_| ()))
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error(r#"[{"from": "Big Bend", "to": "Big Bend"}]"#),
+        "closures[0]: a closure needs two different waypoints"
+    );
+    assert_eq!(
+        error(r#"[{"from": "Big Bend", "to": "Badlands", "factor": 0}]"#),
+        "closures[0].factor: must be more than 0"
+    );
+    assert_eq!(
+        error(r#"[{"from": "Big Bend", "to": "Zion"}]"#),
+        r#"closures[0].to: "Zion" is not one of the trip's waypoints"#
+    );
+}
+
+#[test]
+fn test_errors_point_at_fields() {
+    let with = |rest: &str| {
+        format!(
+            r#"{{"version": 1, "matrix": {{"straight_line": {{}}}}, "home": "Zion", {}}}"#,
+            rest
+        )
+    };
+    assert_eq!(
+        error(r#"{"version": 2}"#),
+        "version: unsupported version 2 (this planner reads version 1)"
+    );
+    assert_eq!(
+        error("{\"version\": 1,\n}"),
+        "line 2, column 1: expected a quoted key"
+    );
+    assert_eq!(error(&with(r#""stops": []"#)), "stops: unknown field (expected one of version, name, matrix, home, objective, trip, waypoints, daily, precedence, vehicle, costs, cover, clusters, closures)");
+    assert_eq!(error(&with(r#""trip": "open""#)), "waypoints: is required");
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches", {"name": "Bryce Canyon", "optional": true}]"#
+        )),
+        "waypoints[1].value: optional stops need a value"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches", {"name": "Moab", "window": {"latest_hours": 5}}]"#
+        )),
+        "waypoints[1]: no waypoint named \"Moab\""
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": [{"name": "Arches", "window": {"earliest_hours": 9, "latest_hours": 5}}]"#
+        )),
+        "waypoints[0].window: earliest_hours is after latest_hours"
+    );
+    assert_eq!(
+        error(&with(r#""waypoints": ["Arches", "zion"]"#)),
+        "waypoints[1]: is the home base"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches"], "daily": {"max_driving_hours": -1}"#
+        )),
+        "daily.max_driving_hours: must not be negative"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches"], "precedence": [{"before": "Arches", "after": "Yosemite"}]"#
+        )),
+        "precedence[0].after: \"Yosemite\" is not one of the trip's waypoints"
+    );
+    assert_eq!(
+        error(
+            r#"{"version": 1, "matrix": {"builtin": "ten-parks"}, "home": "Acadia",
+                "waypoints": ["Big Bend"], "daily": {"max_driving_hours": 8}}"#
+        ),
+        "daily: needs driving times, but the matrix has none"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches"], "objective": "distance", "vehicle": {"range_km": 300}"#
+        )),
+        "objective: must be duration for a vehicle, so charging time counts"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches"], "vehicle": {"range_km": 300, "chargers": [
+                {"name": "Bryce Canyon", "charge_hours": 1},
+                {"name": "bryce canyon", "charge_hours": 2}]}"#
+        )),
+        "vehicle.chargers[1].name: is the same charger as vehicle.chargers[0].name"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": [{"name": "Arches", "stay_hours": "long"}]"#
+        )),
+        "waypoints[0].stay_hours: expected a number"
+    );
+    assert_eq!(
+        error(
+            r#"{"version": 1, "matrix": {"builtin": "ten-parks"}, "home": "Acadia",
+                "waypoints": [{"name": "Big Bend", "stay_hours": 3}]}"#
+        ),
+        "waypoints[0].stay_hours: needs driving times, but the matrix has none"
+    );
+    assert_eq!(
+        error(&with(r#""waypoints": ["Arches"], "objective": "cost""#)),
+        "objective: needs a costs section"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches"], "costs": {"mpg": 30, "fuel_dollars_per_gallon": 3,
+                "lodging_dollars_per_night": 90}"#
+        )),
+        "costs.lodging_dollars_per_night: needs daily driving limits to know the nights"
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches"], "costs": {"mpg": 30, "fuel_dollars_per_gallon": 3,
+                "fuel_prices": [{"state": "Utha", "dollars_per_gallon": 4}]}"#
+        )),
+        "costs.fuel_prices[0].state: no waypoints are in a state named \"Utha\""
+    );
+    assert_eq!(
+        error(&with(
+            r#""waypoints": ["Arches"], "costs": {"mpg": 30, "fuel_dollars_per_gallon": 3,
+                "tolls": [{"from": "Arches", "to": "Moab", "dollars": 4}]}"#
+        )),
+        "costs.tolls[0].to: \"Moab\" is not one of the trip's waypoints"
+    );
+    assert_eq!(
+        error(r#"{"version": 1, "matrix": {"builtin": "all-parks"}}"#),
+        "matrix.builtin: no bundled matrix named \"all-parks\""
+    );
+}
@@ -0,0 +1,763 @@
+/// The rings of `(lat, lon)` points making up one state's outline.
+pub type Outline = &'static [&'static [(f64, f64)]];
+
+/// Coarse outlines of the 48 contiguous states as rings of `(lat, lon)`
+/// points.  They are hand-simplified to a few dozen vertices each: good
+/// enough to recognize the country behind a route map, not for geometry.
+pub const STATE_OUTLINES: &[(&str, Outline)] = &[
+    (
+        "Alabama",
+        &[&[
+            (35.0, -88.2),
+            (35.0, -85.6),
+            (32.0, -85.0),
+            (31.0, -85.0),
+            (31.0, -87.6),
+            (30.3, -87.5),
+            (30.4, -88.4),
+            (34.0, -88.1),
+        ]],
+    ),
+    (
+        "Arizona",
+        &[&[
+            (37.0, -114.04),
+            (37.0, -109.05),
+            (31.33, -109.05),
+            (31.33, -111.07),
+            (32.49, -114.82),
+            (32.72, -114.72),
+            (35.0, -114.63),
+            (36.2, -114.04),
+        ]],
+    ),
+    (
+        "Arkansas",
+        &[&[
+            (36.5, -94.62),
+            (36.5, -90.15),
+            (36.0, -90.37),
+            (36.0, -89.7),
+            (35.0, -90.1),
+            (33.0, -91.1),
+            (33.02, -94.04),
+            (33.55, -94.04),
+            (33.64, -94.48),
+            (35.4, -94.43),
+        ]],
+    ),
+    (
+        "California",
+        &[&[
+            (42.0, -124.21),
+            (42.0, -120.0),
+            (39.0, -120.0),
+            (35.0, -114.63),
+            (32.72, -114.72),
+            (32.53, -117.12),
+            (32.9, -117.25),
+            (34.0, -118.5),
+            (34.55, -120.6),
+            (36.6, -121.9),
+            (37.8, -122.5),
+            (39.5, -123.8),
+            (40.4, -124.4),
+        ]],
+    ),
+    (
+        "Colorado",
+        &[&[
+            (41.0, -109.05),
+            (41.0, -102.05),
+            (37.0, -102.05),
+            (37.0, -109.05),
+        ]],
+    ),
+    (
+        "Connecticut",
+        &[&[
+            (42.05, -73.5),
+            (42.02, -71.8),
+            (41.32, -71.85),
+            (41.0, -73.65),
+        ]],
+    ),
+    (
+        "Delaware",
+        &[&[
+            (39.72, -75.79),
+            (39.83, -75.5),
+            (38.8, -75.05),
+            (38.45, -75.05),
+            (38.45, -75.79),
+        ]],
+    ),
+    (
+        "Florida",
+        &[&[
+            (31.0, -87.6),
+            (31.0, -85.0),
+            (30.7, -84.85),
+            (30.57, -82.2),
+            (30.5, -82.0),
+            (30.7, -81.4),
+            (28.5, -80.5),
+            (26.5, -80.0),
+            (25.2, -80.4),
+            (25.1, -81.1),
+            (26.1, -81.8),
+            (28.0, -82.7),
+            (29.2, -82.8),
+            (30.1, -84.0),
+            (29.7, -85.3),
+            (30.4, -86.5),
+            (30.3, -87.5),
+        ]],
+    ),
+    (
+        "Georgia",
+        &[&[
+            (35.0, -85.6),
+            (35.0, -84.32),
+            (35.0, -83.1),
+            (34.5, -82.3),
+            (32.1, -81.0),
+            (30.7, -81.4),
+            (30.5, -82.0),
+            (30.57, -82.2),
+            (30.7, -84.85),
+            (31.0, -85.0),
+            (32.0, -85.0),
+        ]],
+    ),
+    (
+        "Idaho",
+        &[&[
+            (49.0, -117.03),
+            (49.0, -116.05),
+            (47.98, -116.05),
+            (47.45, -115.7),
+            (46.65, -114.32),
+            (45.55, -114.45),
+            (44.86, -113.45),
+            (44.4, -112.8),
+            (44.5, -111.05),
+            (42.0, -111.05),
+            (42.0, -117.03),
+            (43.68, -117.03),
+            (44.1, -116.93),
+            (44.3, -117.2),
+            (45.08, -116.78),
+            (45.57, -116.47),
+            (45.99, -116.92),
+            (46.0, -117.03),
+        ]],
+    ),
+    (
+        "Illinois",
+        &[&[
+            (42.5, -90.6),
+            (42.5, -87.8),
+            (41.76, -87.52),
+            (38.7, -87.52),
+            (37.9, -87.6),
+            (37.5, -88.1),
+            (36.95, -89.1),
+            (37.3, -89.5),
+            (38.2, -90.2),
+            (38.9, -90.2),
+            (39.8, -90.9),
+            (40.4, -91.4),
+            (41.2, -91.0),
+            (41.5, -90.6),
+            (42.1, -90.2),
+        ]],
+    ),
+    (
+        "Indiana",
+        &[&[
+            (41.76, -87.52),
+            (41.76, -84.8),
+            (39.1, -84.8),
+            (38.0, -86.0),
+            (37.9, -87.6),
+            (38.7, -87.52),
+        ]],
+    ),
+    (
+        "Iowa",
+        &[&[
+            (43.5, -96.45),
+            (43.5, -91.2),
+            (42.7, -91.1),
+            (42.5, -90.6),
+            (42.1, -90.2),
+            (41.5, -90.6),
+            (41.2, -91.0),
+            (40.4, -91.4),
+            (40.58, -95.77),
+            (41.0, -95.9),
+            (41.5, -96.0),
+            (42.5, -96.45),
+        ]],
+    ),
+    (
+        "Kansas",
+        &[&[
+            (40.0, -102.05),
+            (40.0, -95.3),
+            (39.1, -94.6),
+            (37.0, -94.62),
+            (37.0, -102.05),
+        ]],
+    ),
+    (
+        "Kentucky",
+        &[&[
+            (36.5, -89.5),
+            (36.5, -88.05),
+            (36.6, -83.68),
+            (37.54, -81.97),
+            (38.2, -82.6),
+            (38.7, -83.0),
+            (39.1, -84.8),
+            (38.0, -86.0),
+            (37.9, -87.6),
+            (37.5, -88.1),
+            (36.95, -89.1),
+        ]],
+    ),
+    (
+        "Louisiana",
+        &[&[
+            (33.02, -94.04),
+            (33.0, -91.1),
+            (32.2, -91.2),
+            (31.0, -91.6),
+            (31.0, -89.73),
+            (30.18, -89.6),
+            (29.1, -89.4),
+            (29.1, -90.5),
+            (29.5, -91.8),
+            (29.7, -93.83),
+            (31.0, -94.04),
+        ]],
+    ),
+    (
+        "Maine",
+        &[&[
+            (45.3, -71.08),
+            (45.9, -70.25),
+            (46.7, -70.0),
+            (47.45, -69.2),
+            (47.35, -68.2),
+            (47.07, -67.8),
+            (45.7, -67.78),
+            (44.8, -67.0),
+            (44.3, -68.8),
+            (43.6, -70.2),
+            (43.1, -70.7),
+            (44.0, -70.98),
+        ]],
+    ),
+    (
+        "Maryland",
+        &[&[
+            (39.72, -79.48),
+            (39.72, -75.79),
+            (38.45, -75.79),
+            (38.45, -75.05),
+            (38.03, -75.24),
+            (38.0, -76.2),
+            (38.4, -77.0),
+            (38.9, -77.1),
+            (39.32, -77.75),
+            (39.6, -77.85),
+            (39.6, -78.35),
+            (39.2, -79.48),
+        ]],
+    ),
+    (
+        "Massachusetts",
+        &[&[
+            (42.05, -73.5),
+            (42.75, -73.25),
+            (42.7, -71.3),
+            (42.87, -70.8),
+            (42.6, -70.6),
+            (42.3, -71.0),
+            (41.8, -70.5),
+            (41.9, -69.95),
+            (41.6, -70.0),
+            (41.55, -70.6),
+            (41.5, -71.12),
+            (42.02, -71.38),
+            (42.02, -71.8),
+        ]],
+    ),
+    (
+        "Michigan",
+        &[
+            &[
+                (41.76, -86.8),
+                (41.73, -83.45),
+                (42.6, -82.5),
+                (43.0, -82.4),
+                (44.0, -82.6),
+                (44.2, -83.3),
+                (45.0, -83.4),
+                (45.8, -84.7),
+                (45.2, -85.5),
+                (44.4, -86.2),
+                (43.0, -86.4),
+            ],
+            &[
+                (46.57, -90.4),
+                (45.8, -88.1),
+                (45.1, -87.6),
+                (45.3, -87.0),
+                (45.9, -86.3),
+                (46.0, -84.7),
+                (46.5, -84.1),
+                (46.8, -85.0),
+                (46.5, -87.0),
+                (47.4, -88.4),
+                (46.8, -89.8),
+            ],
+        ],
+    ),
+    (
+        "Minnesota",
+        &[&[
+            (49.0, -97.23),
+            (49.0, -95.15),
+            (49.38, -95.15),
+            (49.3, -94.8),
+            (48.6, -93.0),
+            (48.1, -90.0),
+            (48.0, -89.5),
+            (46.75, -92.1),
+            (46.1, -92.3),
+            (45.6, -92.9),
+            (44.6, -92.75),
+            (43.5, -91.2),
+            (43.5, -96.45),
+            (45.94, -96.56),
+        ]],
+    ),
+    (
+        "Mississippi",
+        &[&[
+            (33.0, -91.1),
+            (35.0, -90.1),
+            (35.0, -88.2),
+            (34.0, -88.1),
+            (30.4, -88.4),
+            (30.18, -89.6),
+            (31.0, -89.73),
+            (31.0, -91.6),
+            (32.2, -91.2),
+        ]],
+    ),
+    (
+        "Missouri",
+        &[&[
+            (40.58, -95.77),
+            (40.4, -91.4),
+            (39.8, -90.9),
+            (38.9, -90.2),
+            (38.2, -90.2),
+            (37.3, -89.5),
+            (36.95, -89.1),
+            (36.5, -89.5),
+            (36.0, -89.7),
+            (36.0, -90.37),
+            (36.5, -90.15),
+            (36.5, -94.62),
+            (37.0, -94.62),
+            (39.1, -94.6),
+            (40.0, -95.3),
+        ]],
+    ),
+    (
+        "Montana",
+        &[&[
+            (49.0, -116.05),
+            (49.0, -104.05),
+            (45.0, -104.05),
+            (45.0, -111.05),
+            (44.5, -111.05),
+            (44.4, -112.8),
+            (44.86, -113.45),
+            (45.55, -114.45),
+            (46.65, -114.32),
+            (47.45, -115.7),
+            (47.98, -116.05),
+        ]],
+    ),
+    (
+        "Nebraska",
+        &[&[
+            (43.0, -104.05),
+            (43.0, -98.5),
+            (42.5, -96.45),
+            (41.5, -96.0),
+            (41.0, -95.9),
+            (40.0, -95.3),
+            (40.0, -102.05),
+            (41.0, -102.05),
+            (41.0, -104.05),
+        ]],
+    ),
+    (
+        "Nevada",
+        &[&[
+            (42.0, -120.0),
+            (42.0, -114.04),
+            (36.2, -114.04),
+            (35.0, -114.63),
+            (39.0, -120.0),
+        ]],
+    ),
+    (
+        "New Hampshire",
+        &[&[
+            (45.01, -71.5),
+            (45.3, -71.08),
+            (44.0, -70.98),
+            (43.1, -70.7),
+            (42.87, -70.8),
+            (42.7, -71.3),
+            (42.73, -72.45),
+            (44.3, -72.05),
+        ]],
+    ),
+    (
+        "New Jersey",
+        &[&[
+            (41.35, -74.7),
+            (41.0, -73.9),
+            (40.5, -74.0),
+            (39.8, -74.0),
+            (38.93, -74.9),
+            (39.6, -75.5),
+            (40.15, -74.7),
+            (40.8, -75.1),
+        ]],
+    ),
+    (
+        "New Mexico",
+        &[&[
+            (37.0, -109.05),
+            (37.0, -103.0),
+            (36.5, -103.0),
+            (32.0, -103.04),
+            (32.0, -106.62),
+            (31.78, -106.53),
+            (31.78, -108.21),
+            (31.33, -108.21),
+            (31.33, -109.05),
+        ]],
+    ),
+    (
+        "New York",
+        &[&[
+            (42.0, -79.76),
+            (42.5, -79.76),
+            (43.3, -79.05),
+            (43.3, -76.8),
+            (44.2, -76.2),
+            (45.0, -74.7),
+            (45.01, -73.34),
+            (43.6, -73.3),
+            (42.75, -73.25),
+            (42.05, -73.5),
+            (41.0, -73.65),
+            (41.1, -72.0),
+            (40.6, -73.95),
+            (40.7, -74.0),
+            (41.0, -73.9),
+            (41.35, -74.7),
+            (41.5, -75.0),
+            (42.0, -75.35),
+        ]],
+    ),
+    (
+        "North Carolina",
+        &[&[
+            (35.0, -84.32),
+            (35.0, -83.1),
+            (35.2, -82.0),
+            (35.15, -81.0),
+            (34.8, -80.8),
+            (34.8, -79.7),
+            (33.85, -78.55),
+            (34.0, -77.9),
+            (34.7, -76.5),
+            (35.2, -75.5),
+            (36.55, -75.9),
+            (36.59, -81.68),
+            (36.0, -82.6),
+            (35.5, -83.9),
+            (35.22, -84.32),
+        ]],
+    ),
+    (
+        "North Dakota",
+        &[&[
+            (49.0, -104.05),
+            (49.0, -97.23),
+            (45.94, -96.56),
+            (45.94, -104.05),
+        ]],
+    ),
+    (
+        "Ohio",
+        &[&[
+            (41.7, -84.8),
+            (41.73, -83.45),
+            (41.45, -82.7),
+            (41.98, -80.52),
+            (40.64, -80.52),
+            (40.0, -80.7),
+            (39.2, -81.7),
+            (38.2, -82.6),
+            (38.7, -83.0),
+            (39.1, -84.8),
+        ]],
+    ),
+    (
+        "Oklahoma",
+        &[&[
+            (37.0, -103.0),
+            (37.0, -94.62),
+            (35.4, -94.43),
+            (33.64, -94.48),
+            (33.8, -96.5),
+            (33.85, -97.9),
+            (34.4, -99.2),
+            (34.56, -100.0),
+            (36.5, -100.0),
+            (36.5, -103.0),
+        ]],
+    ),
+    (
+        "Oregon",
+        &[&[
+            (46.23, -123.94),
+            (46.2, -123.4),
+            (45.65, -122.76),
+            (45.55, -122.25),
+            (45.6, -121.2),
+            (45.92, -119.6),
+            (46.0, -118.98),
+            (45.99, -116.92),
+            (45.57, -116.47),
+            (45.08, -116.78),
+            (44.3, -117.2),
+            (44.1, -116.93),
+            (43.68, -117.03),
+            (42.0, -117.03),
+            (42.0, -124.21),
+            (42.84, -124.55),
+            (44.6, -124.0),
+        ]],
+    ),
+    (
+        "Pennsylvania",
+        &[&[
+            (42.0, -80.52),
+            (42.27, -79.76),
+            (42.0, -79.76),
+            (42.0, -75.35),
+            (41.5, -75.0),
+            (41.35, -74.7),
+            (40.8, -75.1),
+            (40.15, -74.7),
+            (39.83, -75.5),
+            (39.72, -75.79),
+            (39.72, -80.52),
+        ]],
+    ),
+    (
+        "Rhode Island",
+        &[&[
+            (42.02, -71.8),
+            (42.02, -71.38),
+            (41.5, -71.12),
+            (41.32, -71.85),
+        ]],
+    ),
+    (
+        "South Carolina",
+        &[&[
+            (35.0, -83.1),
+            (35.2, -82.0),
+            (35.15, -81.0),
+            (34.8, -80.8),
+            (34.8, -79.7),
+            (33.85, -78.55),
+            (33.2, -79.2),
+            (32.5, -80.5),
+            (32.1, -81.0),
+            (34.5, -82.3),
+        ]],
+    ),
+    (
+        "South Dakota",
+        &[&[
+            (45.94, -104.05),
+            (45.94, -96.56),
+            (43.5, -96.45),
+            (42.5, -96.45),
+            (43.0, -98.5),
+            (43.0, -104.05),
+        ]],
+    ),
+    (
+        "Tennessee",
+        &[&[
+            (35.0, -90.1),
+            (35.0, -88.2),
+            (35.0, -85.6),
+            (35.0, -84.32),
+            (35.22, -84.32),
+            (35.5, -83.9),
+            (36.0, -82.6),
+            (36.59, -81.68),
+            (36.6, -83.68),
+            (36.5, -88.05),
+            (36.5, -89.5),
+            (36.0, -89.7),
+        ]],
+    ),
+    (
+        "Texas",
+        &[&[
+            (36.5, -103.0),
+            (36.5, -100.0),
+            (34.56, -100.0),
+            (34.4, -99.2),
+            (33.85, -97.9),
+            (33.8, -96.5),
+            (33.64, -94.48),
+            (33.55, -94.04),
+            (31.0, -94.04),
+            (29.7, -93.83),
+            (29.35, -94.7),
+            (27.6, -97.2),
+            (25.95, -97.15),
+            (26.4, -99.1),
+            (28.0, -100.3),
+            (29.77, -101.4),
+            (29.77, -102.4),
+            (29.0, -103.1),
+            (29.6, -104.5),
+            (31.78, -106.53),
+            (32.0, -106.62),
+            (32.0, -103.04),
+        ]],
+    ),
+    (
+        "Utah",
+        &[&[
+            (42.0, -114.04),
+            (42.0, -111.05),
+            (41.0, -111.05),
+            (41.0, -109.05),
+            (37.0, -109.05),
+            (37.0, -114.04),
+        ]],
+    ),
+    (
+        "Vermont",
+        &[&[
+            (45.01, -73.34),
+            (45.01, -71.5),
+            (44.3, -72.05),
+            (42.73, -72.45),
+            (42.75, -73.25),
+            (43.6, -73.3),
+        ]],
+    ),
+    (
+        "Virginia",
+        &[&[
+            (36.6, -83.68),
+            (36.55, -75.9),
+            (37.9, -76.3),
+            (38.4, -77.0),
+            (38.9, -77.1),
+            (39.32, -77.75),
+            (39.4, -78.35),
+            (38.5, -79.5),
+            (37.5, -80.3),
+            (37.54, -81.97),
+        ]],
+    ),
+    (
+        "Washington",
+        &[&[
+            (48.38, -124.73),
+            (48.0, -123.1),
+            (49.0, -122.75),
+            (49.0, -117.03),
+            (46.0, -117.03),
+            (45.99, -116.92),
+            (46.0, -118.98),
+            (45.92, -119.6),
+            (45.6, -121.2),
+            (45.55, -122.25),
+            (45.65, -122.76),
+            (46.2, -123.4),
+            (46.23, -123.94),
+            (47.0, -124.1),
+        ]],
+    ),
+    (
+        "West Virginia",
+        &[&[
+            (37.54, -81.97),
+            (37.5, -80.3),
+            (38.5, -79.5),
+            (39.4, -78.35),
+            (39.32, -77.75),
+            (39.6, -77.85),
+            (39.6, -78.35),
+            (39.2, -79.48),
+            (39.72, -79.48),
+            (39.72, -80.52),
+            (40.64, -80.52),
+            (40.0, -80.7),
+            (39.2, -81.7),
+            (38.2, -82.6),
+        ]],
+    ),
+    (
+        "Wisconsin",
+        &[&[
+            (46.75, -92.1),
+            (46.57, -90.4),
+            (45.8, -88.1),
+            (45.1, -87.6),
+            (45.3, -87.0),
+            (44.0, -87.8),
+            (42.5, -87.8),
+            (42.5, -90.6),
+            (42.7, -91.1),
+            (43.5, -91.2),
+            (44.6, -92.75),
+            (45.6, -92.9),
+            (46.1, -92.3),
+        ]],
+    ),
+    (
+        "Wyoming",
+        &[&[
+            (45.0, -111.05),
+            (45.0, -104.05),
+            (41.0, -104.05),
+            (41.0, -111.05),
+        ]],
+    ),
+];
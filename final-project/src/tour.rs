@@ -0,0 +1,269 @@
+use crate::csp::{Limits, SearchStatus};
+use crate::distance::DistanceMatrix;
+use crate::opt::{roadtrip_limited, Tour, TourOutcome};
+use crate::rng::Rng;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// The tour solvers available to the command line and benchmarks.  All of
+/// them build closed tours starting and ending at vertex 0, like `roadtrip`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Solver {
+    /// Exact branch and bound over the CSP model (`roadtrip`).
+    BranchAndBound,
+    /// Greedily drive to the closest unvisited stop.
+    NearestNeighbor,
+    /// Nearest neighbor, then reverse segments while that shortens the tour.
+    TwoOpt,
+    /// The genetic algorithm from `trip.py`.
+    Genetic,
+}
+
+impl Solver {
+    /// Every solver, exact first.
+    pub const ALL: [Solver; 4] = [
+        Solver::BranchAndBound,
+        Solver::NearestNeighbor,
+        Solver::TwoOpt,
+        Solver::Genetic,
+    ];
+
+    /// Runs the solver.  Only branch and bound can prove a tour optimal; the
+    /// heuristics report `Feasible` when they find a tour and `Unknown`
+    /// when they don't.  With no vertices there is no home to start from,
+    /// so every solver reports `Infeasible`.
+    pub fn solve(
+        &self,
+        vcount: usize,
+        edges: &[(usize, usize, u32)],
+        limits: &Limits,
+    ) -> TourOutcome {
+        if vcount == 0 {
+            return TourOutcome {
+                tour: None,
+                status: SearchStatus::Infeasible,
+                nodes: 0,
+            };
+        }
+        let costs = DistanceMatrix::from_edges_within(vcount, edges);
+        let (cycle, tried) = match self {
+            Solver::BranchAndBound => return roadtrip_limited(vcount, edges, limits),
+            Solver::NearestNeighbor => (nearest_neighbor(&costs), vcount),
+            Solver::TwoOpt => match nearest_neighbor(&costs) {
+                Some(start) => {
+                    let (tour, tried) = two_opt(&costs, start, limits);
+                    (Some(tour), tried)
+                }
+                None => (None, vcount),
+            },
+            Solver::Genetic => genetic(&costs, &GeneticParams::default(), limits),
+        };
+        match cycle {
+            Some(mut order) => {
+                let cost = cycle_cost(&costs, &order).unwrap();
+                order.push(order[0]);
+                TourOutcome {
+                    tour: Some(Tour { order, cost }),
+                    status: SearchStatus::Feasible,
+                    nodes: tried,
+                }
+            }
+            None => TourOutcome {
+                tour: None,
+                status: SearchStatus::Unknown,
+                nodes: tried,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Solver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Solver::BranchAndBound => "bnb",
+            Solver::NearestNeighbor => "nearest",
+            Solver::TwoOpt => "2opt",
+            Solver::Genetic => "genetic",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Solver {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bnb" | "branch-and-bound" => Ok(Solver::BranchAndBound),
+            "nearest" | "nn" => Ok(Solver::NearestNeighbor),
+            "2opt" | "two-opt" => Ok(Solver::TwoOpt),
+            "genetic" | "ga" => Ok(Solver::Genetic),
+            _ => Err(format!(
+                "unknown solver {:?} (expected bnb, nearest, 2opt or genetic)",
+                s
+            )),
+        }
+    }
+}
+
+/// Returns the cost of a closed tour given as its visiting order without
+/// the return home, or `None` if it uses a missing leg or costs more than
+/// a `u32` holds.
+pub fn cycle_cost(costs: &DistanceMatrix, cycle: &[usize]) -> Option<u32> {
+    (0..cycle.len())
+        .map(|i| costs.get(cycle[i], cycle[(i + 1) % cycle.len()]))
+        .try_fold(0u32, |acc, c| acc.checked_add(c?))
+}
+
+/// Parameters for the genetic algorithm; the defaults are the ones used in
+/// `trip.py` (`thisRunGenerations` and `thisRunPopulation_size`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneticParams {
+    pub generations: usize,
+    pub population: usize,
+    /// Each offspring gets between 1 and this many point mutations; 0 is
+    /// taken as 1.
+    pub max_mutations: usize,
+    pub seed: u64,
+}
+
+impl Default for GeneticParams {
+    fn default() -> Self {
+        Self {
+            generations: 5000,
+            population: 100,
+            max_mutations: 3,
+            seed: 1,
+        }
+    }
+}
+
+/// Greedy construction: from vertex 0, always drive to the closest
+/// unvisited vertex.  Returns the visiting order without the return home.
+pub fn nearest_neighbor(costs: &DistanceMatrix) -> Option<Vec<usize>> {
+    let n = costs.len();
+    if n == 0 {
+        return None;
+    }
+    let mut visited = vec![false; n];
+    let mut cycle = vec![0];
+    visited[0] = true;
+    while cycle.len() < n {
+        let here = *cycle.last().unwrap();
+        let next = (0..n)
+            .filter(|&v| !visited[v])
+            .filter_map(|v| costs.get(here, v).map(|c| (c, v)))
+            .min()?;
+        visited[next.1] = true;
+        cycle.push(next.1);
+    }
+    cycle_cost(costs, &cycle).map(|_| cycle)
+}
+
+/// Improves a tour by reversing segments for as long as some reversal
+/// makes it cheaper.  Costs may be asymmetric, so each candidate is
+/// re-costed in full.  Returns the improved tour and how many candidates
+/// were tried.
+pub fn two_opt(
+    costs: &DistanceMatrix,
+    mut cycle: Vec<usize>,
+    limits: &Limits,
+) -> (Vec<usize>, usize) {
+    let deadline = limits.time_limit.map(|t| Instant::now() + t);
+    let mut best = cycle_cost(costs, &cycle);
+    let mut tried = 0;
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..cycle.len() {
+            for j in i + 1..cycle.len() {
+                if limits.max_nodes.is_some_and(|max| tried >= max)
+                    || deadline.is_some_and(|d| Instant::now() >= d)
+                {
+                    return (cycle, tried);
+                }
+                tried += 1;
+                cycle[i..=j].reverse();
+                match cycle_cost(costs, &cycle) {
+                    Some(c) if best.is_none_or(|b| c < b) => {
+                        best = Some(c);
+                        improved = true;
+                    }
+                    _ => cycle[i..=j].reverse(),
+                }
+            }
+        }
+    }
+    (cycle, tried)
+}
+
+/// The genetic algorithm from `trip.py`: each generation keeps the best
+/// tenth of the population and refills it with one exact copy and two
+/// point-mutated offspring of each survivor.  Vertex 0 stays at the front
+/// so that every genome is a tour from home.  Returns the best tour found
+/// (if any genome avoided missing legs) and how many genomes were scored.
+pub fn genetic(
+    costs: &DistanceMatrix,
+    params: &GeneticParams,
+    limits: &Limits,
+) -> (Option<Vec<usize>>, usize) {
+    let n = costs.len();
+    if n == 0 {
+        return (None, 0);
+    }
+    let deadline = limits.time_limit.map(|t| Instant::now() + t);
+    let mut rng = Rng::new(params.seed);
+    // tours with a missing leg, or too dear to cost, rank after the rest
+    let fitness = |g: &Vec<usize>| {
+        let cost = cycle_cost(costs, g);
+        (cost.is_none(), cost)
+    };
+
+    let mut population: Vec<Vec<usize>> = (0..params.population)
+        .map(|_| {
+            let mut genome: Vec<usize> = (0..n).collect();
+            rng.shuffle(&mut genome[1..]);
+            genome
+        })
+        .collect();
+    let survivors = (params.population / 10).max(1);
+    let mut scored = 0;
+
+    for _ in 0..params.generations {
+        if limits.max_nodes.is_some_and(|max| scored >= max)
+            || deadline.is_some_and(|d| Instant::now() >= d)
+        {
+            break;
+        }
+        let mut ranked: Vec<_> = population.iter().map(|g| (fitness(g), g.clone())).collect();
+        scored += ranked.len();
+        ranked.sort();
+        ranked.dedup();
+
+        let mut next = Vec::with_capacity(survivors * 3);
+        for (_, genome) in ranked.into_iter().take(survivors) {
+            for _ in 0..2 {
+                let mut child = genome.clone();
+                if n > 2 {
+                    for _ in 0..rng.range(1, params.max_mutations.max(1) as u64) {
+                        let a = 1 + rng.below(n - 1);
+                        let mut b = a;
+                        while a == b {
+                            b = 1 + rng.below(n - 1);
+                        }
+                        child.swap(a, b);
+                    }
+                }
+                next.push(child);
+            }
+            next.push(genome);
+        }
+        population = next;
+    }
+
+    let best = population.into_iter().min_by_key(fitness);
+    (best.filter(|g| cycle_cost(costs, g).is_some()), scored)
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,101 @@
+// tests
+
+use super::*;
+use crate::opt::roadtrip;
+
+fn square() -> Vec<(usize, usize, u32)> {
+    // corners of a square, diagonals are longer than the sides
+    let mut edges = Vec::new();
+    for a in 0..4 {
+        for b in 0..4 {
+            if a != b {
+                let cost = if (a + 2) % 4 == b { 14 } else { 10 };
+                edges.push((a, b, cost));
+            }
+        }
+    }
+    edges
+}
+
+#[test]
+fn test_heuristics_match_bnb_on_square() {
+    let edges = square();
+    let (_, best) = roadtrip(4, &edges);
+    assert_eq!(best, 40);
+    for solver in Solver::ALL.iter() {
+        let outcome = solver.solve(4, &edges, &Limits::default());
+        let tour = outcome.tour.unwrap();
+        assert_eq!(tour.cost, 40, "{}", solver);
+        assert_eq!(tour.order[0], 0);
+        assert_eq!(*tour.order.last().unwrap(), 0);
+        let expected = match solver {
+            Solver::BranchAndBound => SearchStatus::Optimal,
+            _ => SearchStatus::Feasible,
+        };
+        assert_eq!(outcome.status, expected);
+    }
+}
+
+#[test]
+fn test_two_opt_untangles() {
+    let costs = DistanceMatrix::from_edges(4, &square()).unwrap();
+    // 0 -> 2 -> 1 -> 3 crosses both diagonals
+    assert_eq!(cycle_cost(&costs, &[0, 2, 1, 3]), Some(48));
+    let (cycle, _) = two_opt(&costs, vec![0, 2, 1, 3], &Limits::default());
+    assert_eq!(cycle_cost(&costs, &cycle), Some(40));
+}
+
+#[test]
+fn test_cycle_cost_overflow() {
+    let costs = DistanceMatrix::from_edges(2, &[(0, 1, u32::MAX - 1), (1, 0, 1)]).unwrap();
+    assert_eq!(cycle_cost(&costs, &[0, 1]), Some(u32::MAX));
+    let costs = DistanceMatrix::from_edges(2, &[(0, 1, u32::MAX), (1, 0, 1)]).unwrap();
+    assert_eq!(cycle_cost(&costs, &[0, 1]), None);
+    let outcome =
+        Solver::NearestNeighbor.solve(2, &[(0, 1, u32::MAX), (1, 0, 1)], &Limits::default());
+    assert_eq!(outcome.tour, None);
+}
+
+#[test]
+fn test_missing_legs() {
+    // 0 and 2 are only connected through 1, so no closed tour exists
+    let edges = vec![(0, 1, 5), (1, 0, 5), (1, 2, 5), (2, 1, 5)];
+    let costs = DistanceMatrix::from_edges(3, &edges).unwrap();
+    assert_eq!(nearest_neighbor(&costs), None);
+    let params = GeneticParams {
+        generations: 10,
+        ..GeneticParams::default()
+    };
+    assert_eq!(genetic(&costs, &params, &Limits::default()).0, None);
+    let outcome = Solver::NearestNeighbor.solve(3, &edges, &Limits::default());
+    assert_eq!(outcome.status, SearchStatus::Unknown);
+}
+
+#[test]
+fn test_degenerate_inputs() {
+    for solver in Solver::ALL.iter() {
+        let outcome = solver.solve(0, &[], &Limits::default());
+        assert_eq!(outcome.status, SearchStatus::Infeasible, "{}", solver);
+        assert_eq!(outcome.tour, None);
+    }
+    let params = GeneticParams {
+        generations: 10,
+        max_mutations: 0,
+        ..GeneticParams::default()
+    };
+    let (tour, _) = genetic(
+        &DistanceMatrix::from_edges(4, &square()).unwrap(),
+        &params,
+        &Limits::default(),
+    );
+    assert_eq!(tour.unwrap()[0], 0);
+}
+
+#[test]
+fn test_solver_names() {
+    for solver in Solver::ALL.iter() {
+        assert_eq!(solver.to_string().parse::<Solver>(), Ok(*solver));
+    }
+    assert_eq!("two-opt".parse::<Solver>(), Ok(Solver::TwoOpt));
+    assert!("simplex".parse::<Solver>().is_err());
+}
@@ -0,0 +1,556 @@
+use crate::matrix::TravelMatrix;
+use std::fmt;
+use std::path::Path;
+
+/// Something went wrong reading or writing a TSPLIB file.
+#[derive(Debug, PartialEq)]
+pub enum TsplibError {
+    /// A line of the file could not be understood.
+    Parse { line: usize, message: String },
+    /// The file uses a part of TSPLIB we don't read.
+    Unsupported(String),
+    /// A problem needs a cost for every leg, but this one has none.
+    Missing { from: usize, to: usize },
+    /// The file could not be read.
+    Io(String),
+}
+
+impl fmt::Display for TsplibError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TsplibError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            TsplibError::Unsupported(what) => write!(f, "unsupported {}", what),
+            TsplibError::Missing { from, to } => write!(f, "no cost from {} to {}", from, to),
+            TsplibError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for TsplibError {}
+
+/// How distances between node coordinates are worked out, exactly as the
+/// TSPLIB documentation specifies, so published optima can be matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// Euclidean distance rounded to the nearest integer (`EUC_2D`).
+    Euclidean,
+    /// Great-circle kilometers from `DDD.MM` degrees and minutes (`GEO`).
+    Geographic,
+    /// The pseudo-Euclidean distance of the att48 and att532 problems (`ATT`).
+    Att,
+}
+
+impl Metric {
+    fn keyword(&self) -> &'static str {
+        match self {
+            Metric::Euclidean => "EUC_2D",
+            Metric::Geographic => "GEO",
+            Metric::Att => "ATT",
+        }
+    }
+
+    /// Returns the TSPLIB distance between two nodes.
+    pub fn distance(&self, a: (f64, f64), b: (f64, f64)) -> u32 {
+        let nint = |x: f64| (x + 0.5) as u32;
+        let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+        match self {
+            Metric::Euclidean => nint((dx * dx + dy * dy).sqrt()),
+            Metric::Att => {
+                let r = ((dx * dx + dy * dy) / 10.0).sqrt();
+                let t = nint(r);
+                if (t as f64) < r {
+                    t + 1
+                } else {
+                    t
+                }
+            }
+            Metric::Geographic => {
+                // TSPLIB's own constants, rather than more precise ones
+                #[allow(clippy::approx_constant)]
+                const PI: f64 = 3.141592;
+                const RRR: f64 = 6378.388;
+                let radians = |x: f64| {
+                    let degrees = x.trunc();
+                    PI * (degrees + 5.0 * (x - degrees) / 3.0) / 180.0
+                };
+                let (lat_a, lon_a, lat_b, lon_b) =
+                    (radians(a.0), radians(a.1), radians(b.0), radians(b.1));
+                let q1 = (lon_a - lon_b).cos();
+                let q2 = (lat_a - lat_b).cos();
+                let q3 = (lat_a + lat_b).cos();
+                (RRR * (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos() + 1.0) as u32
+            }
+        }
+    }
+}
+
+/// How an explicit matrix is laid out in `EDGE_WEIGHT_SECTION`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatrixFormat {
+    /// Every row in full, so costs may differ in each direction.
+    FullMatrix,
+    /// The upper triangle without the diagonal, row by row.
+    UpperRow,
+}
+
+impl MatrixFormat {
+    fn keyword(&self) -> &'static str {
+        match self {
+            MatrixFormat::FullMatrix => "FULL_MATRIX",
+            MatrixFormat::UpperRow => "UPPER_ROW",
+        }
+    }
+}
+
+/// Where a problem's leg costs come from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Weights {
+    /// Costs follow from node coordinates.
+    Coords(Metric, Vec<(f64, f64)>),
+    /// Costs are listed, as a full square matrix whatever the file's layout.
+    Explicit(MatrixFormat, Vec<Vec<u32>>),
+}
+
+/// A TSPLIB `TSP` or `ATSP` problem.  Nodes are numbered from 1 in the
+/// file and from 0 here, so node 1 becomes `roadtrip`'s home.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub name: String,
+    pub comment: Option<String>,
+    pub weights: Weights,
+}
+
+impl Problem {
+    /// Parses a `.tsp` or `.atsp` file.
+    pub fn parse(text: &str) -> Result<Self, TsplibError> {
+        let file = Sections::parse(text)?;
+        match file.get("TYPE") {
+            Some("TSP") | Some("ATSP") => {}
+            Some(other) => return Err(TsplibError::Unsupported(format!("TYPE {}", other))),
+            None => return Err(parse_error(1, "no TYPE")),
+        }
+        let dimension = file.dimension()?;
+        let weights = match file.get("EDGE_WEIGHT_TYPE") {
+            Some("EXPLICIT") => {
+                let format = match file.get("EDGE_WEIGHT_FORMAT") {
+                    Some("FULL_MATRIX") => MatrixFormat::FullMatrix,
+                    Some("UPPER_ROW") => MatrixFormat::UpperRow,
+                    Some(other) => {
+                        return Err(TsplibError::Unsupported(format!(
+                            "EDGE_WEIGHT_FORMAT {}",
+                            other
+                        )))
+                    }
+                    None => {
+                        return Err(parse_error(
+                            1,
+                            "EXPLICIT weights need an EDGE_WEIGHT_FORMAT",
+                        ))
+                    }
+                };
+                let count = match format {
+                    MatrixFormat::FullMatrix => dimension * dimension,
+                    MatrixFormat::UpperRow => dimension * (dimension - 1) / 2,
+                };
+                let (line, numbers) = file.section("EDGE_WEIGHT_SECTION")?;
+                if numbers.len() != count {
+                    return Err(parse_error(
+                        line,
+                        &format!(
+                            "expected {} edge weights but found {}",
+                            count,
+                            numbers.len()
+                        ),
+                    ));
+                }
+                let weights = numbers
+                    .iter()
+                    .map(|&(line, w)| {
+                        if w >= 0.0 && w <= u32::MAX as f64 && w.fract() == 0.0 {
+                            Ok(w as u32)
+                        } else {
+                            Err(parse_error(line, &format!("bad edge weight {}", w)))
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let matrix = match format {
+                    MatrixFormat::FullMatrix => {
+                        weights.chunks(dimension).map(<[u32]>::to_vec).collect()
+                    }
+                    MatrixFormat::UpperRow => {
+                        let mut matrix = vec![vec![0; dimension]; dimension];
+                        let pairs =
+                            (0..dimension).flat_map(|i| (i + 1..dimension).map(move |j| (i, j)));
+                        for ((i, j), w) in pairs.zip(weights) {
+                            matrix[i][j] = w;
+                            matrix[j][i] = w;
+                        }
+                        matrix
+                    }
+                };
+                Weights::Explicit(format, matrix)
+            }
+            Some(kind) => {
+                let metric = match kind {
+                    "EUC_2D" => Metric::Euclidean,
+                    "GEO" => Metric::Geographic,
+                    "ATT" => Metric::Att,
+                    other => {
+                        return Err(TsplibError::Unsupported(format!(
+                            "EDGE_WEIGHT_TYPE {}",
+                            other
+                        )))
+                    }
+                };
+                let (line, numbers) = file.section("NODE_COORD_SECTION")?;
+                if numbers.len() != 3 * dimension {
+                    return Err(parse_error(
+                        line,
+                        &format!("expected {} nodes with x and y", dimension),
+                    ));
+                }
+                let mut coords = vec![None; dimension];
+                for node in numbers.chunks(3) {
+                    let (line, id) = node[0];
+                    let i = id as usize;
+                    if id.fract() != 0.0 || i < 1 || i > dimension || coords[i - 1].is_some() {
+                        return Err(parse_error(line, &format!("bad node number {}", id)));
+                    }
+                    coords[i - 1] = Some((node[1].1, node[2].1));
+                }
+                Weights::Coords(metric, coords.into_iter().map(Option::unwrap).collect())
+            }
+            None => return Err(parse_error(1, "no EDGE_WEIGHT_TYPE")),
+        };
+        Ok(Self {
+            name: file.get("NAME").unwrap_or("").to_string(),
+            comment: file.get("COMMENT").map(str::to_string),
+            weights,
+        })
+    }
+
+    /// Reads a problem file from disk; see `parse`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, TsplibError> {
+        let text = std::fs::read_to_string(path).map_err(|e| TsplibError::Io(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    /// Makes an explicit problem from `roadtrip`-style edges.  Symmetric
+    /// costs are written as `UPPER_ROW`, others as a `FULL_MATRIX`.  Every
+    /// leg needs a cost, since TSPLIB has no way to leave one out.
+    pub fn from_edges(
+        name: &str,
+        vcount: usize,
+        edges: &[(usize, usize, u32)],
+    ) -> Result<Self, TsplibError> {
+        let mut matrix = vec![vec![None; vcount]; vcount];
+        for &(from, to, cost) in edges {
+            matrix[from][to] = Some(cost);
+        }
+        let mut full = vec![vec![0; vcount]; vcount];
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, cost) in row.iter().enumerate() {
+                if i != j {
+                    full[i][j] = cost.ok_or(TsplibError::Missing { from: i, to: j })?;
+                }
+            }
+        }
+        let symmetric = (0..vcount).all(|i| (0..i).all(|j| full[i][j] == full[j][i]));
+        let format = if symmetric {
+            MatrixFormat::UpperRow
+        } else {
+            MatrixFormat::FullMatrix
+        };
+        Ok(Self {
+            name: name.to_string(),
+            comment: None,
+            weights: Weights::Explicit(format, full),
+        })
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        match &self.weights {
+            Weights::Coords(_, coords) => coords.len(),
+            Weights::Explicit(_, matrix) => matrix.len(),
+        }
+    }
+
+    /// Returns the cost of the leg between two 0-based nodes.
+    pub fn cost(&self, from: usize, to: usize) -> u32 {
+        match &self.weights {
+            Weights::Coords(metric, coords) => metric.distance(coords[from], coords[to]),
+            Weights::Explicit(_, matrix) => matrix[from][to],
+        }
+    }
+
+    /// Returns `roadtrip`-style edges between every pair of nodes.
+    pub fn edges(&self) -> Vec<(usize, usize, u32)> {
+        let n = self.len();
+        let mut edges = Vec::with_capacity(n * n.saturating_sub(1));
+        for from in 0..n {
+            for to in (0..n).filter(|&to| to != from) {
+                edges.push((from, to, self.cost(from, to)));
+            }
+        }
+        edges
+    }
+
+    /// Returns the problem as a travel matrix whose waypoints are named by
+    /// their TSPLIB node numbers.
+    pub fn to_matrix(&self) -> TravelMatrix {
+        let names = (1..=self.len()).map(|i| i.to_string()).collect();
+        TravelMatrix::from_edges(names, &self.edges())
+    }
+
+    /// Returns the cost of a closed tour through 0-based nodes, given
+    /// with or without the return to its start.
+    pub fn tour_cost(&self, order: &[usize]) -> u64 {
+        let mut order = order.to_vec();
+        if order.len() > 1 && order.first() == order.last() {
+            order.pop();
+        }
+        (0..order.len())
+            .map(|i| self.cost(order[i], order[(i + 1) % order.len()]) as u64)
+            .sum()
+    }
+
+    /// Writes the problem in TSPLIB format.
+    pub fn to_tsplib(&self) -> String {
+        let asymmetric = match &self.weights {
+            Weights::Explicit(MatrixFormat::FullMatrix, m) => {
+                (0..m.len()).any(|i| (0..i).any(|j| m[i][j] != m[j][i]))
+            }
+            _ => false,
+        };
+        let mut text = format!("NAME: {}\n", self.name);
+        text.push_str(&format!(
+            "TYPE: {}\n",
+            if asymmetric { "ATSP" } else { "TSP" }
+        ));
+        if let Some(comment) = &self.comment {
+            text.push_str(&format!("COMMENT: {}\n", comment));
+        }
+        text.push_str(&format!("DIMENSION: {}\n", self.len()));
+        match &self.weights {
+            Weights::Coords(metric, coords) => {
+                text.push_str(&format!("EDGE_WEIGHT_TYPE: {}\n", metric.keyword()));
+                text.push_str("NODE_COORD_SECTION\n");
+                for (i, (x, y)) in coords.iter().enumerate() {
+                    text.push_str(&format!("{} {} {}\n", i + 1, x, y));
+                }
+            }
+            Weights::Explicit(format, matrix) => {
+                text.push_str("EDGE_WEIGHT_TYPE: EXPLICIT\n");
+                text.push_str(&format!("EDGE_WEIGHT_FORMAT: {}\n", format.keyword()));
+                text.push_str("EDGE_WEIGHT_SECTION\n");
+                for (i, row) in matrix.iter().enumerate() {
+                    let row = match format {
+                        MatrixFormat::FullMatrix => &row[..],
+                        MatrixFormat::UpperRow => &row[i + 1..],
+                    };
+                    if !row.is_empty() {
+                        let row: Vec<_> = row.iter().map(|w| w.to_string()).collect();
+                        text.push_str(&row.join(" "));
+                        text.push('\n');
+                    }
+                }
+            }
+        }
+        text.push_str("EOF\n");
+        text
+    }
+}
+
+/// A TSPLIB `TOUR`, such as a published `.opt.tour` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TourFile {
+    pub name: String,
+    pub comment: Option<String>,
+    /// 0-based nodes in visiting order, without the return to the start.
+    pub order: Vec<usize>,
+}
+
+impl TourFile {
+    /// Parses a `.tour` file.  Only the first tour in it is read.
+    pub fn parse(text: &str) -> Result<Self, TsplibError> {
+        let file = Sections::parse(text)?;
+        if let Some(kind) = file.get("TYPE").filter(|&t| t != "TOUR") {
+            return Err(TsplibError::Unsupported(format!(
+                "TYPE {} in a tour file",
+                kind
+            )));
+        }
+        let dimension = file
+            .get("DIMENSION")
+            .map(|_| file.dimension())
+            .transpose()?;
+        let (line, numbers) = file.section("TOUR_SECTION")?;
+        let mut order = Vec::new();
+        for &(line, id) in numbers.iter() {
+            if id == -1.0 {
+                break;
+            }
+            let ok = id.fract() == 0.0 && id >= 1.0 && dimension.is_none_or(|d| id as usize <= d);
+            if !ok || order.contains(&(id as usize - 1)) {
+                return Err(parse_error(line, &format!("bad node number {}", id)));
+            }
+            order.push(id as usize - 1);
+        }
+        if dimension.is_some_and(|d| d != order.len()) {
+            return Err(parse_error(line, "the tour doesn't visit every node"));
+        }
+        Ok(Self {
+            name: file.get("NAME").unwrap_or("").to_string(),
+            comment: file.get("COMMENT").map(str::to_string),
+            order,
+        })
+    }
+
+    /// Reads a tour file from disk; see `parse`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, TsplibError> {
+        let text = std::fs::read_to_string(path).map_err(|e| TsplibError::Io(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    /// Makes a tour file from a `roadtrip` order, which may end with the
+    /// return home.
+    pub fn from_order(name: &str, order: &[usize]) -> Self {
+        let mut order = order.to_vec();
+        if order.len() > 1 && order.first() == order.last() {
+            order.pop();
+        }
+        Self {
+            name: name.to_string(),
+            comment: None,
+            order,
+        }
+    }
+
+    /// Returns the tour the way `roadtrip` does: starting and ending at
+    /// node 0, which must be on it.
+    pub fn roadtrip_order(&self) -> Option<Vec<usize>> {
+        let start = self.order.iter().position(|&v| v == 0)?;
+        let mut order = self.order[start..].to_vec();
+        order.extend_from_slice(&self.order[..start]);
+        order.push(0);
+        Some(order)
+    }
+
+    /// Writes the tour in TSPLIB format.
+    pub fn to_tsplib(&self) -> String {
+        let mut text = format!("NAME: {}\nTYPE: TOUR\n", self.name);
+        if let Some(comment) = &self.comment {
+            text.push_str(&format!("COMMENT: {}\n", comment));
+        }
+        text.push_str(&format!("DIMENSION: {}\nTOUR_SECTION\n", self.order.len()));
+        for v in self.order.iter() {
+            text.push_str(&format!("{}\n", v + 1));
+        }
+        text.push_str("-1\nEOF\n");
+        text
+    }
+}
+
+/// Numbers from a data section, each with the line it is on.
+type Numbers = [(usize, f64)];
+
+/// A data section's name, the line it starts on, and its numbers.
+type Section = (String, usize, Vec<(usize, f64)>);
+
+/// A TSPLIB file split into its `KEY: value` specification lines and its
+/// data sections, whose numbers are kept with the line they came from.
+struct Sections {
+    specification: Vec<(usize, String, String)>,
+    sections: Vec<Section>,
+}
+
+impl Sections {
+    fn parse(text: &str) -> Result<Self, TsplibError> {
+        let mut specification = Vec::new();
+        let mut sections: Vec<Section> = Vec::new();
+        let mut in_section = false;
+        for (i, line) in text.lines().enumerate() {
+            let (n, line) = (i + 1, line.trim());
+            if line.is_empty() {
+                continue;
+            }
+            if line == "EOF" {
+                break;
+            }
+            let starts_with_number =
+                line.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.');
+            if in_section && starts_with_number {
+                let (_, _, numbers) = sections.last_mut().unwrap();
+                for word in line.split_whitespace() {
+                    let number = word
+                        .parse::<f64>()
+                        .map_err(|_| parse_error(n, &format!("bad number {:?}", word)))?;
+                    numbers.push((n, number));
+                }
+            } else if let Some((key, value)) = line.split_once(':') {
+                specification.push((n, key.trim().to_string(), value.trim().to_string()));
+                in_section = false;
+            } else if line.ends_with("_SECTION") {
+                sections.push((line.to_string(), n, Vec::new()));
+                in_section = true;
+            } else {
+                return Err(parse_error(
+                    n,
+                    &format!("expected KEY: value or a section, found {:?}", line),
+                ));
+            }
+        }
+        Ok(Self {
+            specification,
+            sections,
+        })
+    }
+
+    fn find(&self, key: &str) -> Option<(usize, &str)> {
+        self.specification
+            .iter()
+            .find(|(_, k, _)| k == key)
+            .map(|(line, _, v)| (*line, v.as_str()))
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.find(key).map(|(_, v)| v)
+    }
+
+    /// Returns the number of nodes, which must be small enough that a
+    /// full matrix of them can be counted, so the sizes `parse` works out
+    /// never overflow.
+    fn dimension(&self) -> Result<usize, TsplibError> {
+        match self.find("DIMENSION") {
+            Some((line, n)) => match n.parse::<usize>() {
+                Ok(n) if n >= 1 && n.checked_mul(n).is_some() => Ok(n),
+                Ok(n) if n >= 1 => Err(parse_error(line, &format!("DIMENSION {} is too large", n))),
+                _ => Err(parse_error(
+                    line,
+                    "DIMENSION must be a positive whole number",
+                )),
+            },
+            None => Err(parse_error(1, "no DIMENSION")),
+        }
+    }
+
+    /// Returns the line a section starts on and its numbers.
+    fn section(&self, name: &str) -> Result<(usize, &Numbers), TsplibError> {
+        self.sections
+            .iter()
+            .find(|(n, _, _)| n == name)
+            .map(|(_, line, numbers)| (*line, &numbers[..]))
+            .ok_or_else(|| parse_error(1, &format!("no {}", name)))
+    }
+}
+
+fn parse_error(line: usize, message: &str) -> TsplibError {
+    TsplibError::Parse {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod pub_tests;
@@ -0,0 +1,179 @@
+// tests
+
+use super::*;
+use crate::matrix::Objective;
+use crate::opt::roadtrip;
+
+const BURMA14: &str = "NAME: burma14
+TYPE: TSP
+COMMENT: 14-Staedte in Burma (Zaw Win)
+DIMENSION: 14
+EDGE_WEIGHT_TYPE: GEO
+EDGE_WEIGHT_FORMAT: FUNCTION
+DISPLAY_DATA_TYPE: COORD_DISPLAY
+NODE_COORD_SECTION
+   1  16.47       96.10
+   2  16.47       94.44
+   3  20.09       92.54
+   4  22.39       93.37
+   5  25.23       97.24
+   6  22.00       96.05
+   7  20.47       97.02
+   8  17.20       96.29
+   9  16.30       97.38
+  10  14.05       98.12
+  11  16.53       97.38
+  12  21.52       95.59
+  13  19.41       97.13
+  14  20.09       94.55
+EOF
+";
+
+const BURMA14_OPT: &str = "NAME : burma14.opt.tour
+COMMENT : Optimal tour for burma14 (3323)
+TYPE : TOUR
+DIMENSION : 14
+TOUR_SECTION
+1
+2
+14
+3
+4
+5
+6
+12
+7
+13
+8
+11
+9
+10
+-1
+EOF
+";
+
+#[test]
+fn test_published_optimum() {
+    let problem = Problem::parse(BURMA14).unwrap();
+    assert_eq!(problem.name, "burma14");
+    assert_eq!(problem.len(), 14);
+    let tour = TourFile::parse(BURMA14_OPT).unwrap();
+    assert_eq!(tour.order[..3], [0, 1, 13]);
+    assert_eq!(problem.tour_cost(&tour.order), 3323);
+
+    // the first five cities are few enough to solve here
+    let edges: Vec<_> = problem
+        .edges()
+        .into_iter()
+        .filter(|e| e.0 < 5 && e.1 < 5)
+        .collect();
+    let (order, cost) = roadtrip(5, &edges);
+    assert_eq!(problem.tour_cost(&order), cost as u64);
+}
+
+#[test]
+fn test_metrics() {
+    assert_eq!(Metric::Euclidean.distance((0.0, 0.0), (3.0, 4.0)), 5);
+    assert_eq!(Metric::Euclidean.distance((0.0, 0.0), (1.0, 1.0)), 1);
+    // sqrt(500 / 10) is 7.07, which ATT rounds up
+    assert_eq!(Metric::Att.distance((0.0, 0.0), (10.0, 20.0)), 8);
+    assert_eq!(Metric::Att.distance((0.0, 0.0), (0.0, 10.0)), 4);
+}
+
+#[test]
+fn test_explicit_round_trip() {
+    let upper = "NAME: tiny\nTYPE: TSP\nDIMENSION: 4\nEDGE_WEIGHT_TYPE: EXPLICIT\n\
+                 EDGE_WEIGHT_FORMAT: UPPER_ROW\nEDGE_WEIGHT_SECTION\n10 20 30\n40 50\n60\nEOF\n";
+    let problem = Problem::parse(upper).unwrap();
+    assert_eq!(
+        (problem.cost(0, 3), problem.cost(3, 0), problem.cost(2, 3)),
+        (30, 30, 60)
+    );
+    assert_eq!(problem.to_tsplib(), upper);
+
+    // edges go back to the same problem, symmetric as UPPER_ROW
+    let again = Problem::from_edges("tiny", 4, &problem.edges()).unwrap();
+    assert_eq!(again, problem);
+
+    // one-way costs need the full matrix, and the file becomes an ATSP
+    let edges = [
+        (0, 1, 5),
+        (0, 2, 1),
+        (1, 0, 7),
+        (1, 2, 2),
+        (2, 0, 1),
+        (2, 1, 2),
+    ];
+    let asymmetric = Problem::from_edges("oneway", 3, &edges).unwrap();
+    let text = asymmetric.to_tsplib();
+    assert!(text.contains("TYPE: ATSP\n"));
+    assert!(text.contains("FULL_MATRIX\nEDGE_WEIGHT_SECTION\n0 5 1\n7 0 2\n1 2 0\nEOF\n"));
+    let parsed = Problem::parse(&text).unwrap();
+    assert_eq!(parsed.edges(), edges.to_vec());
+    assert_eq!(
+        Problem::from_edges("gap", 3, &edges[1..]),
+        Err(TsplibError::Missing { from: 0, to: 1 })
+    );
+
+    let matrix = parsed.to_matrix();
+    assert_eq!(matrix.names()[1], "2");
+    assert_eq!(matrix.cost(1, 0, Objective::Distance), Some(7));
+}
+
+#[test]
+fn test_tours() {
+    let tour = TourFile::from_order("t", &[0, 2, 1, 0]);
+    assert_eq!(tour.order, vec![0, 2, 1]);
+    let text = tour.to_tsplib();
+    assert_eq!(
+        text,
+        "NAME: t\nTYPE: TOUR\nDIMENSION: 3\nTOUR_SECTION\n1\n3\n2\n-1\nEOF\n"
+    );
+    assert_eq!(TourFile::parse(&text).unwrap(), tour);
+
+    let rotated = TourFile::parse("TYPE: TOUR\nTOUR_SECTION\n3 1 2 -1\n").unwrap();
+    assert_eq!(rotated.roadtrip_order(), Some(vec![0, 1, 2, 0]));
+}
+
+#[test]
+fn test_errors() {
+    let parse = |text: &str| Problem::parse(text).unwrap_err().to_string();
+    assert_eq!(
+        parse("NAME: x\nTYPE: HCP\nDIMENSION: 3\n"),
+        "unsupported TYPE HCP"
+    );
+    assert_eq!(
+        parse("TYPE: TSP\nDIMENSION: 2\nEDGE_WEIGHT_TYPE: MAN_2D\n"),
+        "unsupported EDGE_WEIGHT_TYPE MAN_2D"
+    );
+    assert_eq!(
+        parse("TYPE: TSP\nDIMENSION: two\n"),
+        "line 2: DIMENSION must be a positive whole number"
+    );
+    assert_eq!(
+        parse(
+            "TYPE: TSP\nDIMENSION: 5000000000\nEDGE_WEIGHT_TYPE: EXPLICIT\n\
+             EDGE_WEIGHT_FORMAT: FULL_MATRIX\nEDGE_WEIGHT_SECTION\n1\n"
+        ),
+        "line 2: DIMENSION 5000000000 is too large"
+    );
+    assert_eq!(
+        parse(
+            "TYPE: TSP\nDIMENSION: 2\nEDGE_WEIGHT_TYPE: EUC_2D\nNODE_COORD_SECTION\n1 0 0\n3 1 1\n"
+        ),
+        "line 6: bad node number 3"
+    );
+    assert_eq!(
+        parse(
+            "TYPE: TSP\nDIMENSION: 3\nEDGE_WEIGHT_TYPE: EXPLICIT\n\
+             EDGE_WEIGHT_FORMAT: UPPER_ROW\nEDGE_WEIGHT_SECTION\n1 2\n"
+        ),
+        "line 5: expected 3 edge weights but found 2"
+    );
+    assert_eq!(
+        TourFile::parse("TYPE: TOUR\nDIMENSION: 3\nTOUR_SECTION\n1\n2\n2\n-1\n")
+            .unwrap_err()
+            .to_string(),
+        "line 6: bad node number 2"
+    );
+}
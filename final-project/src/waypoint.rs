@@ -0,0 +1,213 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// What kind of place a waypoint is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    Park,
+    City,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Category::Park => write!(f, "park"),
+            Category::City => write!(f, "city"),
+        }
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "park" | "parks" => Ok(Category::Park),
+            "city" | "cities" => Ok(Category::City),
+            _ => Err(format!("unknown category {:?} (expected park or city)", s)),
+        }
+    }
+}
+
+/// A named place a trip can stop at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Waypoint {
+    pub name: String,
+    pub state: String,
+    pub category: Category,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Waypoint {
+    /// Creates a new waypoint.
+    pub fn new(name: &str, state: &str, category: Category, lat: f64, lon: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            state: state.to_string(),
+            category,
+            lat,
+            lon,
+        }
+    }
+    /// The `"Name, State"` label used as the waypoint key in `trip.py`'s
+    /// distance files and the Google Maps queries.
+    pub fn label(&self) -> String {
+        format!("{}, {}", self.name, self.state)
+    }
+    /// Returns the `(lat, lon)` of the waypoint.
+    pub fn coords(&self) -> (f64, f64) {
+        (self.lat, self.lon)
+    }
+}
+
+/// Narrows the gazetteer down.  Every field that is set must match; text
+/// is compared without regard to case.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    /// Text that must appear somewhere in the `"Name, State"` label.
+    pub search: Option<String>,
+    /// The full state name.
+    pub state: Option<String>,
+    pub category: Option<Category>,
+}
+
+impl Filter {
+    /// Returns whether a waypoint passes the filter.
+    pub fn matches(&self, waypoint: &Waypoint) -> bool {
+        self.search
+            .as_ref()
+            .is_none_or(|s| waypoint.label().to_lowercase().contains(&s.to_lowercase()))
+            && self
+                .state
+                .as_ref()
+                .is_none_or(|s| waypoint.state.eq_ignore_ascii_case(s))
+            && self.category.is_none_or(|c| c == waypoint.category)
+    }
+}
+
+/// National parks in the lower 48, taken from `data.py`.
+const PARKS: &[(&str, &str, f64, f64)] = &[
+    ("Acadia", "Maine", 44.35, -68.21),
+    ("Arches", "Utah", 38.68, -109.57),
+    ("Badlands", "South Dakota", 43.75, -102.50),
+    ("Big Bend", "Texas", 29.25, -103.25),
+    ("Biscayne", "Florida", 25.65, -80.08),
+    ("Black Canyon of the Gunnison", "Colorado", 38.57, -107.72),
+    ("Bryce Canyon", "Utah", 37.57, -112.18),
+    ("Canyonlands", "Utah", 38.20, -109.93),
+    ("Capitol Reef", "Utah", 38.20, -111.17),
+    ("Carlsbad Caverns", "New Mexico", 32.17, -104.44),
+    ("Congaree", "South Carolina", 33.78, -80.78),
+    ("Crater Lake", "Oregon", 42.94, -122.10),
+    ("Cuyahoga Valley", "Ohio", 41.24, -81.55),
+    ("Death Valley", "California", 36.24, -116.82),
+    ("Everglades", "Florida", 25.32, -80.93),
+    ("Great Sand Dunes", "Colorado", 37.73, -105.51),
+    ("Great Smoky Mountains", "North Carolina", 35.68, -83.53),
+    ("Gateway Arch", "Missouri", 38.63, -90.19),
+    ("Glacier", "Montana", 48.80, -114.00),
+    ("Grand Canyon", "Arizona", 36.06, -112.14),
+    ("Great Basin", "Nevada", 38.98, -114.30),
+    ("Guadalupe Mountains", "Texas", 31.92, -104.87),
+    ("Hot Springs", "Arkansas", 34.51, -93.05),
+    ("Indiana Dunes", "Indiana", 41.65, -87.05),
+    ("Joshua Tree", "California", 33.79, -115.90),
+    ("Kings Canyon", "California", 36.80, -118.55),
+    ("Lassen Volcanic", "California", 40.49, -121.51),
+    ("Mammoth Cave", "Kentucky", 37.18, -86.10),
+    ("Mesa Verde", "Colorado", 37.18, -108.49),
+    ("Mount Rainier", "Washington", 46.85, -121.75),
+    ("North Cascades", "Washington", 48.70, -121.20),
+    ("Petrified Forest", "Arizona", 35.07, -109.78),
+    ("Pinnacles", "California", 36.48, -121.16),
+    ("Redwood", "California", 41.30, -124.00),
+    ("Rocky Mountain", "Colorado", 40.40, -105.58),
+    ("Saguaro", "Arizona", 32.25, -110.50),
+    ("Sequoia", "California", 36.43, -118.68),
+    ("Shenandoah", "Virginia", 38.53, -78.35),
+    ("Theodore Roosevelt", "North Dakota", 46.97, -103.45),
+    ("Voyageurs", "Minnesota", 48.50, -92.88),
+    ("White Sands", "New Mexico", 32.78, -106.17),
+    ("Wind Cave", "South Dakota", 43.57, -103.48),
+    ("Yellowstone", "Wyoming", 44.60, -110.50),
+    ("Yosemite", "California", 37.83, -119.50),
+    ("Zion", "Utah", 37.30, -113.05),
+];
+
+/// One large city per state in the lower 48, taken from `data.py`.
+const CITIES: &[(&str, &str, f64, f64)] = &[
+    ("Birmingham", "Alabama", 33.52, -86.80),
+    ("Phoenix", "Arizona", 33.45, -112.07),
+    ("Little Rock", "Arkansas", 34.75, -92.29),
+    ("Los Angeles", "California", 34.05, -118.24),
+    ("Denver", "Colorado", 39.74, -104.99),
+    ("Bridgeport", "Connecticut", 41.19, -73.20),
+    ("Wilmington", "Delaware", 39.74, -75.55),
+    ("Jacksonville", "Florida", 30.33, -81.66),
+    ("Atlanta", "Georgia", 33.75, -84.39),
+    ("Boise", "Idaho", 43.62, -116.20),
+    ("Chicago", "Illinois", 41.88, -87.63),
+    ("Indianapolis", "Indiana", 39.77, -86.16),
+    ("Des Moines", "Iowa", 41.59, -93.62),
+    ("Wichita", "Kansas", 37.69, -97.34),
+    ("Louisville", "Kentucky", 38.25, -85.76),
+    ("New Orleans", "Louisiana", 29.95, -90.07),
+    ("Portland", "Maine", 43.66, -70.26),
+    ("Baltimore", "Maryland", 39.29, -76.61),
+    ("Boston", "Massachusetts", 42.36, -71.06),
+    ("Detroit", "Michigan", 42.33, -83.05),
+    ("Minneapolis", "Minnesota", 44.98, -93.27),
+    ("Jackson", "Mississippi", 32.30, -90.18),
+    ("Kansas City", "Missouri", 39.10, -94.58),
+    ("Billings", "Montana", 45.78, -108.50),
+    ("Omaha", "Nebraska", 41.26, -95.93),
+    ("Las Vegas", "Nevada", 36.17, -115.14),
+    ("Manchester", "New Hampshire", 42.99, -71.46),
+    ("Newark", "New Jersey", 40.74, -74.17),
+    ("Albuquerque", "New Mexico", 35.08, -106.65),
+    ("New York City", "New York", 40.71, -74.01),
+    ("Charlotte", "North Carolina", 35.23, -80.84),
+    ("Fargo", "North Dakota", 46.88, -96.79),
+    ("Columbus", "Ohio", 39.96, -83.00),
+    ("Oklahoma City", "Oklahoma", 35.47, -97.52),
+    ("Portland", "Oregon", 45.52, -122.68),
+    ("Philadelphia", "Pennsylvania", 39.95, -75.17),
+    ("Providence", "Rhode Island", 41.82, -71.41),
+    ("Charleston", "South Carolina", 32.78, -79.93),
+    ("Sioux Falls", "South Dakota", 43.55, -96.73),
+    ("Nashville", "Tennessee", 36.16, -86.78),
+    ("Houston", "Texas", 29.76, -95.37),
+    ("Salt Lake City", "Utah", 40.76, -111.89),
+    ("Burlington", "Vermont", 44.48, -73.21),
+    ("Virginia Beach", "Virginia", 36.85, -75.98),
+    ("Seattle", "Washington", 47.61, -122.33),
+    ("Charleston", "West Virginia", 38.35, -81.63),
+    ("Milwaukee", "Wisconsin", 43.04, -87.91),
+    ("Cheyenne", "Wyoming", 41.14, -104.82),
+];
+
+/// Every park and city the planner knows about, parks first.
+pub fn gazetteer() -> Vec<Waypoint> {
+    let parks = PARKS
+        .iter()
+        .map(|&(name, state, lat, lon)| Waypoint::new(name, state, Category::Park, lat, lon));
+    let cities = CITIES
+        .iter()
+        .map(|&(name, state, lat, lon)| Waypoint::new(name, state, Category::City, lat, lon));
+    parks.chain(cities).collect()
+}
+
+/// Looks up a gazetteer entry by its `"Name, State"` label or, failing
+/// that, by a name that only one entry has.  Case is ignored.
+pub fn lookup(key: &str) -> Option<Waypoint> {
+    let key = key.trim().to_lowercase();
+    let all = gazetteer();
+    if let Some(w) = all.iter().find(|w| w.label().to_lowercase() == key) {
+        return Some(w.clone());
+    }
+    let mut named = all.into_iter().filter(|w| w.name.to_lowercase() == key);
+    match (named.next(), named.next()) {
+        (Some(w), None) => Some(w),
+        _ => None,
+    }
+}